@@ -26,6 +26,7 @@ define_app_errors!(
     SerdeYaml(serde_yaml::Error);
     Base64Decode(base64::DecodeError);
     Grass(Box<grass::Error>);
+    Zip(zip::result::ZipError);
 );
 
 impl From<&str> for SeelenLibError {
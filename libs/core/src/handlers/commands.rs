@@ -244,6 +244,7 @@ slu_commands_declaration! {
 
     // Windows Manager
     WmGetRenderTree = wm_get_render_tree() -> TwmGlobalRuntimeTree,
+    WmGetStacks = wm_get_stacks() -> HashMap<WorkspaceId, Vec<TwmStackTabs>>,
     SetAppWindowsPositions = set_app_windows_positions(positions: HashMap<isize, Rect>),
     RequestFocus = request_focus(hwnd: isize),
     WmSetStackActiveWindow = wm_set_stack_active_window(hwnd: isize),
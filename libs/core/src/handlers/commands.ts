@@ -107,6 +107,7 @@ export enum SeelenCommand {
   WegPinItem = "weg_pin_item",
   WegImportPinnedTaskbarItems = "weg_import_pinned_taskbar_items",
   WmGetRenderTree = "wm_get_render_tree",
+  WmGetStacks = "wm_get_stacks",
   SetAppWindowsPositions = "set_app_windows_positions",
   RequestFocus = "request_focus",
   WmSetStackActiveWindow = "wm_set_stack_active_window",
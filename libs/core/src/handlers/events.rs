@@ -82,6 +82,7 @@ slu_events_declaration! {
     WMSetReservation(Option<twm::TwmReservation>) as "wm::set-reservation",
     WMForceRetiling as "wm::force-retiling",
     WMTreeChanged(TwmGlobalRuntimeTree) as "wm::tree-changed",
+    WMStacksChanged(HashMap<WorkspaceId, Vec<TwmStackTabs>>) as "wm::stacks-changed",
 
     StateSettingsChanged(Settings) as "settings-changed",
     StateThemesChanged(Vec<Theme>) as "themes",
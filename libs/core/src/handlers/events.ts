@@ -29,6 +29,7 @@ export enum SeelenEvent {
   WMSetReservation = "wm::set-reservation",
   WMForceRetiling = "wm::force-retiling",
   WMTreeChanged = "wm::tree-changed",
+  WMStacksChanged = "wm::stacks-changed",
   StateSettingsChanged = "settings-changed",
   StateThemesChanged = "themes",
   StateIconPacksChanged = "icon-packs",
@@ -1,22 +1,35 @@
 use std::{
-    io::{Cursor, Read, Seek, SeekFrom, Write},
-    path::Path,
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
 };
 
 use base64::Engine;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
 
 use crate::{error::Result, utils::TsUnknown};
 
 use super::yaml_ext;
 
-use super::Resource;
+use super::{Resource, SluSignature, SluSignatureStatus};
+
+/// First bytes of a zip archive, v3 files are plain zip archives.
+const ZIP_SIGNATURE: [u8; 4] = *b"PK\x03\x04";
+const MANIFEST_ENTRY: &str = "manifest.yml";
+const DOCUMENT_ENTRY: &str = "resource.yml";
+const ASSETS_DIR: &str = "assets/";
 
 /// A container for Seelen UI resources.
 ///
 /// This struct contains all the necessary data that a resource needs.
 /// It uses a custom `.slu` file extension format that can change over time
 /// with new versions.
+///
+/// - v1 and v2: a small header followed by the base64 encoded YAML document.
+/// - v3: a zip archive with a `manifest.yml`, the `resource.yml` document and an `assets`
+///   folder with the binary files of the resource.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
 #[serde(rename_all = "camelCase")]
@@ -27,22 +40,151 @@ pub struct SluResourceFile {
     pub resource: Resource,
     /// real resource data to be deserialized on load
     pub data: TsUnknown,
+    /// signature of the publisher
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SluSignature>,
+    /// binary files of the resource, only stored by v3 files
+    #[serde(skip)]
+    pub assets: SluAssets,
+    /// result of the last signature check, filled on load
+    #[serde(skip)]
+    pub signature_status: SluSignatureStatus,
+}
+
+/// Header of a v3 archive.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SluManifest {
+    version: u32,
+    /// names of the files inside the `assets` folder
+    #[serde(default)]
+    assets: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SluAsset {
+    /// content kept in memory
+    Bytes(Vec<u8>),
+    /// file on disk, streamed into the archive on encode
+    File(PathBuf),
+    /// entry of the archive the resource was loaded from, read on demand
+    Archived,
+}
+
+/// Binary files of a resource by their path relative to the `assets` folder, like `images/bg.png`.
+#[derive(Debug, Clone, Default)]
+pub struct SluAssets {
+    /// archive from where [`SluAsset::Archived`] entries are read
+    archive: Option<PathBuf>,
+    entries: BTreeMap<String, SluAsset>,
+}
+
+impl SluAssets {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    pub fn insert(&mut self, name: &str, asset: SluAsset) -> Result<()> {
+        validate_asset_name(name)?;
+        self.entries.insert(name.to_string(), asset);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<SluAsset> {
+        self.entries.remove(name)
+    }
+
+    fn open_archive(&self) -> Result<Option<ZipArchive<BufReader<File>>>> {
+        let has_archived = self
+            .entries
+            .values()
+            .any(|asset| matches!(asset, SluAsset::Archived));
+        match &self.archive {
+            Some(path) if has_archived => {
+                Ok(Some(ZipArchive::new(BufReader::new(File::open(path)?))?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Streams the content of the asset into the writer
+    pub fn copy_to<W: Write>(&self, name: &str, writer: &mut W) -> Result<u64> {
+        let asset = self
+            .entries
+            .get(name)
+            .ok_or_else(|| format!("asset {name} not found"))?;
+        let copied = match asset {
+            SluAsset::Bytes(bytes) => std::io::copy(&mut bytes.as_slice(), writer)?,
+            SluAsset::File(path) => std::io::copy(&mut File::open(path)?, writer)?,
+            SluAsset::Archived => {
+                let mut archive = self
+                    .open_archive()?
+                    .ok_or_else(|| format!("asset {name} is not available"))?;
+                let mut entry = archive.by_name(&format!("{ASSETS_DIR}{name}"))?;
+                std::io::copy(&mut entry, writer)?
+            }
+        };
+        Ok(copied)
+    }
+
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.copy_to(name, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Writes all the assets inside the folder, keeping their relative paths
+    pub fn extract(&self, folder: &Path) -> Result<()> {
+        for name in self.entries.keys() {
+            let path = folder.join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = BufWriter::new(File::create(path)?);
+            self.copy_to(name, &mut file)?;
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Asset names are relative paths using `/` as separator, they can't leave the `assets` folder.
+pub(crate) fn validate_asset_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('/')
+        && !name.contains(['\\', ':'])
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..");
+    if !valid {
+        return Err(format!("invalid asset name: {name}").into());
+    }
+    Ok(())
 }
 
 impl SluResourceFile {
     pub fn decode<R: Read + Seek>(mut reader: R) -> Result<Self> {
-        let mut version = [0u8; 1];
-        reader.read_exact(&mut version)?;
+        let mut signature = [0u8; 4];
+        reader.read_exact(&mut signature)?;
+        if signature == ZIP_SIGNATURE {
+            reader.seek(SeekFrom::Current(-4))?;
+            return Self::decode_archive(reader);
+        }
 
-        match version[0] {
-            1 => {
-                reader.seek(SeekFrom::Current(3))?; // SLU mime type
-            }
+        match signature[0] {
+            // the version is followed by the SLU mime type
+            1 => {}
             2 => {
-                reader.seek(SeekFrom::Current(3))?; // SLU mime type
                 reader.seek(SeekFrom::Current(4))?; // 32 bits reserved
             }
-            // todo for version 3, use zip crate
             _ => {
                 return Err("unsupported slu file version".into());
             }
@@ -52,14 +194,56 @@ impl SluResourceFile {
         let mut buffer = Vec::new();
         reader.read_to_end(&mut buffer)?;
         let decoded = base64::engine::general_purpose::STANDARD.decode(&buffer)?;
-        let value: serde_yaml::Value = serde_yaml::from_slice(&decoded)?;
-        let self_id = yaml_ext::extract_self_id_slu(&value)?;
-        let vars = std::collections::HashMap::from([("self.id".to_string(), self_id)]);
+        Self::from_document(&decoded)
+    }
+
+    fn decode_archive<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut archive = ZipArchive::new(reader)?;
+        let manifest: SluManifest = serde_yaml::from_reader(archive.by_name(MANIFEST_ENTRY)?)?;
+        if manifest.version != 3 {
+            return Err("unsupported slu file version".into());
+        }
+
+        let mut document = Vec::new();
+        archive
+            .by_name(DOCUMENT_ENTRY)?
+            .read_to_end(&mut document)?;
+        let mut decoded = Self::from_document(&document)?;
+        decoded.version = manifest.version;
+
+        for name in archive.file_names() {
+            let Some(asset) = name.strip_prefix(ASSETS_DIR) else {
+                continue;
+            };
+            if asset.is_empty() || asset.ends_with('/') {
+                continue; // folder entries
+            }
+            decoded.assets.insert(asset, SluAsset::Archived)?;
+        }
+
+        if let Some(missing) = manifest
+            .assets
+            .iter()
+            .find(|name| !decoded.assets.contains(name))
+        {
+            return Err(format!("asset {missing} is listed but not present in the file").into());
+        }
+        Ok(decoded)
+    }
+
+    fn from_document(document: &[u8]) -> Result<Self> {
+        let value: serde_yaml::Value = serde_yaml::from_slice(document)?;
+        let vars = yaml_ext::extract_vars_slu(&value)?;
         let value = yaml_ext::resolve_vars_yaml(value, &vars);
         Ok(serde_yaml::from_value(value)?)
     }
 
-    pub fn encode<W: Write>(&self, mut writer: W) -> Result<()> {
+    /// Encodes the file as v3 if its version asks for it or it has assets, as v2 otherwise.
+    pub fn encode<W: Write + Seek>(&self, mut writer: W) -> Result<()> {
+        if self.version >= 3 || !self.assets.is_empty() {
+            return self.encode_archive(writer);
+        }
+
         let data = serde_yaml::to_string(self)?;
         let encoded = base64::engine::general_purpose::STANDARD.encode(data);
 
@@ -70,19 +254,100 @@ impl SluResourceFile {
         Ok(())
     }
 
+    fn encode_archive<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(writer);
+
+        let manifest = SluManifest {
+            version: 3,
+            assets: self.assets.names().map(str::to_string).collect(),
+        };
+        zip.start_file(MANIFEST_ENTRY, options)?;
+        serde_yaml::to_writer(&mut zip, &manifest)?;
+
+        let mut document = serde_yaml::to_value(self)?;
+        document["version"] = manifest.version.into();
+        zip.start_file(DOCUMENT_ENTRY, options)?;
+        serde_yaml::to_writer(&mut zip, &document)?;
+
+        // archived assets are copied as they are, without recompressing them
+        let mut source = self.assets.open_archive()?;
+        for (name, asset) in &self.assets.entries {
+            let entry_name = format!("{ASSETS_DIR}{name}");
+            match (asset, source.as_mut()) {
+                (SluAsset::Archived, Some(source)) => {
+                    zip.raw_copy_file(source.by_name(&entry_name)?)?;
+                }
+                (SluAsset::Archived, None) => {
+                    return Err(format!("asset {name} is not available").into());
+                }
+                _ => {
+                    zip.start_file(entry_name, options)?;
+                    self.assets.copy_to(name, &mut zip)?;
+                }
+            }
+        }
+
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Decodes the file at the path and checks its signature, see [`Self::signature_status`].
+    /// v3 assets are kept inside the archive until read.
     pub async fn load(path: &Path) -> Result<Self> {
-        let bytes = tokio::fs::read(path).await?;
-        let mut decoded = Self::decode(Cursor::new(bytes))?;
+        let path = path.to_path_buf();
+        let mut decoded = tokio::task::spawn_blocking(move || -> Result<Self> {
+            let file = BufReader::new(File::open(&path)?);
+            let mut decoded = Self::decode(file)?;
+            decoded.assets.archive = Some(path);
+            decoded.verify_signature()?;
+            Ok(decoded)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
         decoded.resource.sanitize();
         decoded.resource.verify()?;
         Ok(decoded)
     }
 
+    /// Encodes the file at the path, it can be the same path the file was loaded from.
     pub async fn store(&self, path: &Path) -> Result<()> {
-        let mut buf = Vec::new();
-        self.encode(&mut buf)?;
-        tokio::fs::write(path, buf).await?;
-        Ok(())
+        if self.assets.is_empty() && self.version < 3 {
+            let mut buf = Cursor::new(Vec::new());
+            self.encode(&mut buf)?;
+            tokio::fs::write(path, buf.into_inner()).await?;
+            return Ok(());
+        }
+
+        // archived assets could be read from the destination, so the new file is written aside
+        let file = self.clone();
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let temp_path = path.with_extension("slu.tmp");
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            file.encode(&mut writer)?;
+            writer.flush()?;
+            drop(writer);
+            std::fs::rename(&temp_path, &path)?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    /// Reads an asset of the `.slu` file at the path without loading the resource.
+    pub async fn read_asset(path: &Path, name: &str) -> Result<Vec<u8>> {
+        validate_asset_name(name)?;
+        let path = path.to_path_buf();
+        let entry_name = format!("{ASSETS_DIR}{name}");
+        tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+            let mut archive = ZipArchive::new(BufReader::new(File::open(&path)?))?;
+            let mut buffer = Vec::new();
+            archive.by_name(&entry_name)?.read_to_end(&mut buffer)?;
+            Ok(buffer)
+        })
+        .await
+        .map_err(|e| e.to_string())?
     }
 
     pub fn try_parse_into<T>(&self) -> Result<T>
@@ -107,3 +372,137 @@ impl SluResourceFile {
         Ok(serde_json::from_value(obj.into())?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource_file(version: u32) -> SluResourceFile {
+        let document = r#"
+version: 2
+resource:
+  id: 1f7d5f0e-5d1a-4c1e-9d55-8e4b0b1b6f3a
+  dataId: 6c2b8a56-3f7e-4a63-a0a4-6f4f4b1bb0d1
+  creatorId: 0b0a6a7e-9f8e-4b2e-8a41-3e0c2a1d5c77
+  slug: aurora
+  kind: Wallpaper
+  metadata:
+    displayName: Aurora
+    description: Northern lights
+  createdAt: 2025-01-01T00:00:00Z
+  updatedAt: 2025-01-01T00:00:00Z
+  status: Published
+  version: 1
+  stars: 0
+  downloads: 0
+data:
+  filename: ${self.id}.mp4
+"#;
+        let mut file = SluResourceFile::from_document(document.as_bytes()).unwrap();
+        file.version = version;
+        file
+    }
+
+    fn round_trip(file: &SluResourceFile) -> Result<(Vec<u8>, SluResourceFile)> {
+        let mut buffer = Cursor::new(Vec::new());
+        file.encode(&mut buffer)?;
+        let bytes = buffer.into_inner();
+        let decoded = SluResourceFile::decode(Cursor::new(bytes.clone()))?;
+        Ok((bytes, decoded))
+    }
+
+    #[test]
+    fn test_legacy_versions_round_trip() -> Result<()> {
+        let file = resource_file(2);
+        assert_eq!(
+            file.data.0["filename"],
+            "1f7d5f0e-5d1a-4c1e-9d55-8e4b0b1b6f3a.mp4"
+        );
+
+        let (bytes, decoded) = round_trip(&file)?;
+        assert_eq!(&bytes[..4], b"\x02SLU");
+        assert_eq!(decoded.version, 2);
+        assert_eq!(decoded.data, file.data);
+
+        // v1 has no reserved bytes after the mime type
+        let mut v1 = vec![1u8];
+        v1.extend_from_slice(b"SLU");
+        v1.extend_from_slice(&bytes[8..]);
+        let decoded = SluResourceFile::decode(Cursor::new(v1))?;
+        assert_eq!(decoded.resource.id, file.resource.id);
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_round_trip_with_assets() -> Result<()> {
+        let mut file = resource_file(2);
+        file.assets
+            .insert("video/aurora.mp4", SluAsset::Bytes(vec![7; 1024]))?;
+
+        // assets force the v3 container
+        let (bytes, decoded) = round_trip(&file)?;
+        assert_eq!(bytes[..4], ZIP_SIGNATURE);
+        assert_eq!(decoded.version, 3);
+        assert_eq!(decoded.data, file.data);
+        assert_eq!(
+            decoded.assets.names().collect::<Vec<_>>(),
+            vec!["video/aurora.mp4"]
+        );
+
+        // archived assets are not loaded by decode
+        assert!(decoded.assets.read("video/aurora.mp4").is_err());
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        let mut asset = Vec::new();
+        archive
+            .by_name("assets/video/aurora.mp4")?
+            .read_to_end(&mut asset)?;
+        assert_eq!(asset, vec![7; 1024]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_asset_names() {
+        let mut assets = SluAssets::default();
+        for name in ["bg.png", "sounds/click.wav"] {
+            assert!(assets.insert(name, SluAsset::Bytes(Vec::new())).is_ok());
+        }
+        for name in [
+            "",
+            "/etc/passwd",
+            "../bg.png",
+            "a//b",
+            r"sounds\click.wav",
+            "C:/bg.png",
+        ] {
+            assert!(assets.insert(name, SluAsset::Bytes(Vec::new())).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_over_the_loaded_file() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("slu-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&folder)?;
+        let path = folder.join("aurora.slu");
+
+        let mut file = resource_file(3);
+        file.assets
+            .insert("bg.png", SluAsset::Bytes(vec![1, 2, 3]))?;
+        file.store(&path).await?;
+
+        // the archived asset is copied from the file being replaced
+        let mut loaded = SluResourceFile::load(&path).await?;
+        loaded.data.0["filename"] = "edited.mp4".into();
+        loaded.store(&path).await?;
+
+        let reloaded = SluResourceFile::load(&path).await?;
+        assert_eq!(reloaded.data.0["filename"], "edited.mp4");
+        assert_eq!(reloaded.assets.read("bg.png")?, vec![1, 2, 3]);
+        assert_eq!(
+            SluResourceFile::read_asset(&path, "bg.png").await?,
+            vec![1, 2, 3]
+        );
+
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+}
@@ -5,7 +5,8 @@ use serde::{de::DeserializeOwned, Serialize};
 use crate::{
     error::Result,
     resource::{
-        deserialize_extended_yaml, deserialize_extended_yaml_no_vars, ResourceKind, SluResourceFile,
+        deserialize_extended_yaml, deserialize_extended_yaml_no_vars, sort_yaml_keys,
+        validate_asset_name, InternalResourceMetadata, ResourceKind, SluResourceFile,
     },
     utils::search_resource_entrypoint,
 };
@@ -50,6 +51,7 @@ pub trait SluResource: Sized + Serialize + DeserializeOwned {
 
                 let mut parsed: Self = file.try_parse_into()?;
                 parsed.metadata_mut().internal.remote = Some(Box::new(file.resource.clone()));
+                parsed.metadata_mut().internal.signature = file.signature_status;
                 parsed
             }
             _ => return Err("Invalid file extension".into()),
@@ -91,6 +93,32 @@ pub trait SluResource: Sized + Serialize + DeserializeOwned {
         Self::load_ext(path, true).await
     }
 
+    /// Reads a file shipped with the resource by its relative path, like `images/bg.png`.
+    /// Packed resources read it from the `assets` folder of their `.slu` archive, unpacked ones
+    /// from their own folder.
+    async fn read_asset(&self, name: &str) -> Result<Vec<u8>> {
+        validate_asset_name(name)?;
+        let mut path = self.metadata().internal.path.to_path_buf();
+        if path.is_dir() {
+            if let Some(entrypoint) = search_resource_entrypoint(&path).await {
+                if is_slu_file(&entrypoint) {
+                    path = entrypoint;
+                }
+            }
+        }
+
+        if is_slu_file(&path) {
+            return SluResourceFile::read_asset(&path, name).await;
+        }
+
+        let folder = if path.is_dir() {
+            path.as_path()
+        } else {
+            path.parent().ok_or("Invalid resource path")?
+        };
+        Ok(tokio::fs::read(folder.join(name)).await?)
+    }
+
     /// Sanitize the resource data
     fn sanitize(&mut self) {}
 
@@ -119,6 +147,8 @@ pub trait SluResource: Sized + Serialize + DeserializeOwned {
             "slu" => {
                 let mut slu_file = SluResourceFile::load(&save_path).await?;
                 slu_file.data = serde_json::to_value(self)?.into();
+                // local changes can't keep the signature of the publisher
+                slu_file.signature = None;
                 slu_file.store(&save_path).await?;
             }
             "yml" | "yaml" => {
@@ -136,6 +166,24 @@ pub trait SluResource: Sized + Serialize + DeserializeOwned {
         Ok(())
     }
 
+    /// YAML document of the resource to be shared. It is reproducible: keys are sorted and
+    /// the data only meaningful on this machine (paths, write dates) is left out.
+    fn to_bundle_yaml(&self) -> Result<String> {
+        let mut value = serde_yaml::to_value(self)?;
+        let internal = serde_yaml::to_value(InternalResourceMetadata::default())?;
+        if let (Some(metadata), Some(internal)) = (
+            value
+                .get_mut("metadata")
+                .and_then(serde_yaml::Value::as_mapping_mut),
+            internal.as_mapping(),
+        ) {
+            for key in internal.keys() {
+                metadata.remove(key);
+            }
+        }
+        Ok(serde_yaml::to_string(&sort_yaml_keys(value))?)
+    }
+
     async fn delete(&self) -> Result<()> {
         let path = self.metadata().internal.path.to_path_buf();
         if path.is_dir() {
@@ -146,3 +194,8 @@ pub trait SluResource: Sized + Serialize + DeserializeOwned {
         Ok(())
     }
 }
+
+fn is_slu_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("slu"))
+}
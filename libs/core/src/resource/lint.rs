@@ -0,0 +1,771 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use jsonschema::{error::ValidationErrorKind, ValidationError};
+use schemars::JsonSchema;
+use serde_json::Value;
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+use crate::{
+    error::{Result, SeelenLibError},
+    resource::{
+        deserialize_extended_yaml, extend_path, normalize_path, ResourceText, SluResource,
+        SluResourceFile, ALLOWED_ENV_VARS,
+    },
+    utils::search_resource_entrypoint,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for LintSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintSeverity::Error => write!(f, "error"),
+            LintSeverity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Position in a source file, line and column are 1-indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub severity: LintSeverity,
+    /// JSON pointer of the offending value in the resolved document, empty for the whole document
+    pub pointer: String,
+    /// Where the value is written, following the `!extend`ed files.
+    /// Unknown for values that are not in the sources, like defaults.
+    pub location: Option<SourceLocation>,
+    pub message: String,
+}
+
+/// Checks the resource at the path (folder or entrypoint file) without loading it, reporting
+/// every problem found instead of stopping at the first one:
+/// - the document against the JSON schema of the resource,
+/// - the validations done when loading it,
+/// - the texts without the `en` fallback,
+/// - the compatibility of `appTargetVersion` with the given app version.
+pub async fn lint_resource<R>(path: &Path, app_version: (u32, u32, u32)) -> Result<Vec<LintIssue>>
+where
+    R: SluResource + JsonSchema,
+{
+    let entrypoint = if path.is_dir() {
+        search_resource_entrypoint(path)
+            .await
+            .ok_or("No metadata file found")?
+    } else {
+        path.to_path_buf()
+    };
+    let ext = entrypoint
+        .extension()
+        .ok_or("Invalid file extension")?
+        .to_string_lossy()
+        .to_lowercase();
+
+    let mut linter = Linter::default();
+    let document: Value = match ext.as_str() {
+        "yml" | "yaml" => {
+            let file = normalize_path(&entrypoint);
+            linter.sources = tokio::task::spawn_blocking(move || SourceMap::scan_document(&file))
+                .await
+                .map_err(|e| e.to_string())?;
+            if !linter.sources.issues.is_empty() {
+                return Ok(linter.sources.issues);
+            }
+
+            match deserialize_extended_yaml(&entrypoint).await {
+                Ok(document) => document,
+                Err(err) => {
+                    linter.error("", error_message(err));
+                    return Ok(linter.issues);
+                }
+            }
+        }
+        "json" | "jsonc" => {
+            let bytes = tokio::fs::read(&entrypoint).await?;
+            match serde_json::from_slice(&bytes) {
+                Ok(document) => document,
+                Err(err) => {
+                    linter.issues.push(LintIssue {
+                        severity: LintSeverity::Error,
+                        pointer: String::new(),
+                        location: Some(SourceLocation {
+                            file: entrypoint,
+                            line: err.line(),
+                            column: err.column(),
+                        }),
+                        message: format!("invalid JSON: {err}"),
+                    });
+                    return Ok(linter.issues);
+                }
+            }
+        }
+        "slu" => {
+            let file = SluResourceFile::load(&entrypoint).await?;
+            if R::KIND != file.resource.kind {
+                return Err(format!(
+                    "Resource file is not of expected kind: {:?} instead is {:?}",
+                    R::KIND,
+                    file.resource.kind
+                )
+                .into());
+            }
+            serde_json::to_value(&file.data)?
+        }
+        _ => return Err("Invalid file extension".into()),
+    };
+
+    linter.check::<R>(document, app_version)?;
+    Ok(linter.issues)
+}
+
+#[derive(Debug, Default)]
+struct Linter {
+    sources: SourceMap,
+    issues: Vec<LintIssue>,
+}
+
+impl Linter {
+    fn push(&mut self, severity: LintSeverity, pointer: &str, message: String) {
+        self.issues.push(LintIssue {
+            severity,
+            pointer: pointer.to_string(),
+            location: self.sources.locate(pointer),
+            message,
+        });
+    }
+
+    fn error(&mut self, pointer: &str, message: String) {
+        self.push(LintSeverity::Error, pointer, message);
+    }
+
+    fn warning(&mut self, pointer: &str, message: String) {
+        self.push(LintSeverity::Warning, pointer, message);
+    }
+
+    fn check<R>(&mut self, document: Value, app_version: (u32, u32, u32)) -> Result<()>
+    where
+        R: SluResource + JsonSchema,
+    {
+        let mut unresolved = Vec::new();
+        find_unresolved_vars(&document, String::new(), &mut unresolved);
+        for (pointer, name) in unresolved {
+            let message = match name.strip_prefix("env.") {
+                Some(env) if !ALLOWED_ENV_VARS.contains(&env) => format!(
+                    "`${{{name}}}` is not an allowed environment variable, allowed ones are {}",
+                    ALLOWED_ENV_VARS.join(", ")
+                ),
+                _ => format!("unknown variable `${{{name}}}`, it is left as is"),
+            };
+            self.warning(&pointer, message);
+        }
+
+        let schema = serde_json::to_value(schemars::schema_for!(R))?;
+        let validator = jsonschema::validator_for(&schema).map_err(|e| e.to_string())?;
+        let mut schema_errors = Vec::new();
+        for error in validator.iter_errors(&document) {
+            collect_schema_errors(&error, &mut schema_errors);
+        }
+
+        let mut resource: R = match serde_path_to_error::deserialize(document) {
+            Ok(resource) => resource,
+            Err(err) => {
+                // the schema errors are more precise, and there can be many
+                if schema_errors.is_empty() {
+                    let pointer = to_pointer(err.path());
+                    self.error(&pointer, err.into_inner().to_string());
+                }
+                for (pointer, message) in schema_errors {
+                    self.error(&pointer, message);
+                }
+                return Ok(());
+            }
+        };
+
+        // accepted by the app, like aliases or legacy formats, but editors will complain
+        for (pointer, message) in schema_errors {
+            self.warning(
+                &pointer,
+                format!("{message} (the app accepts it, editors using the schema don't)"),
+            );
+        }
+
+        resource.sanitize();
+        if let Err(err) = resource.validate() {
+            self.error("", error_message(err));
+        }
+
+        // defaults are included, as missing texts are shown as missing in the app too
+        let serialized = serde_json::to_value(&resource)?;
+        let mut missing = BTreeSet::new();
+        let defs = schema.get("$defs").cloned().unwrap_or_default();
+        find_texts_without_en(&defs, &schema, &serialized, String::new(), &mut missing);
+        for pointer in missing {
+            self.error(
+                &pointer,
+                "missing `en` text, used when there is no translation for the user language"
+                    .to_string(),
+            );
+        }
+
+        match resource.metadata().app_target_version {
+            None => self.warning(
+                "/metadata",
+                "missing `appTargetVersion`, the compatibility with the app can't be checked"
+                    .to_string(),
+            ),
+            Some(target) if target > app_version => self.error(
+                "/metadata/appTargetVersion",
+                format!(
+                    "targets v{}.{}.{}, newer than the app v{}.{}.{}",
+                    target.0, target.1, target.2, app_version.0, app_version.1, app_version.2
+                ),
+            ),
+            Some(target) if target < app_version => self.warning(
+                "/metadata/appTargetVersion",
+                format!(
+                    "targets v{}.{}.{}, it will be shown as outdated in the app v{}.{}.{}",
+                    target.0, target.1, target.2, app_version.0, app_version.1, app_version.2
+                ),
+            ),
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+fn error_message(err: SeelenLibError) -> String {
+    match err {
+        SeelenLibError::Custom(message) => message,
+        err => err.to_string(),
+    }
+}
+
+/// Flattens the error, replacing the `anyOf`/`oneOf` errors by the errors of the closest
+/// alternative, the one going deeper in the document, as that's usually the intended one.
+fn collect_schema_errors(error: &ValidationError<'_>, errors: &mut Vec<(String, String)>) {
+    if let ValidationErrorKind::AnyOf { context } | ValidationErrorKind::OneOfNotValid { context } =
+        error.kind()
+    {
+        let depth = |e: &ValidationError<'_>| e.instance_path().as_str().matches('/').count();
+        let closest = context
+            .iter()
+            .filter(|alternative| !alternative.is_empty())
+            .max_by_key(|alternative| {
+                (
+                    alternative.iter().map(depth).max(),
+                    Reverse(alternative.len()),
+                )
+            });
+        if let Some(alternative) = closest {
+            for error in alternative {
+                collect_schema_errors(error, errors);
+            }
+            return;
+        }
+    }
+    errors.push((
+        error.instance_path().as_str().to_string(),
+        error.masked().to_string(),
+    ));
+}
+
+/// Collects the `${vars.*}` and `${env.*}` placeholders left after the interpolation.
+/// `${self.*}` are not checked, as `self` is a common name in the scripts of widgets.
+fn find_unresolved_vars(value: &Value, pointer: String, found: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                rest = &rest[start + 2..];
+                let Some(end) = rest.find('}') else {
+                    break;
+                };
+                let name = &rest[..end];
+                if name.starts_with("vars.") || name.starts_with("env.") {
+                    found.push((pointer.clone(), name.to_string()));
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map {
+                let pointer = format!("{pointer}/{}", escape_pointer(key));
+                find_unresolved_vars(value, pointer, found);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                find_unresolved_vars(item, format!("{pointer}/{index}"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn to_pointer(path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+    let mut pointer = String::new();
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => pointer.push_str(&format!("/{index}")),
+            Segment::Map { key } => pointer.push_str(&format!("/{}", escape_pointer(key))),
+            Segment::Enum { .. } | Segment::Unknown => {}
+        }
+    }
+    pointer
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Collects the pointers of the [`ResourceText`] values of the instance without `en` text,
+/// following the schema of the document.
+fn find_texts_without_en(
+    defs: &Value,
+    schema: &Value,
+    instance: &Value,
+    pointer: String,
+    missing: &mut BTreeSet<String>,
+) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    if let Some(name) = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix("#/$defs/"))
+    {
+        if name == ResourceText::schema_name() {
+            let has_en = match instance {
+                Value::Object(texts) => texts
+                    .get("en")
+                    .and_then(Value::as_str)
+                    .is_some_and(|text| !text.is_empty()),
+                _ => true,
+            };
+            if !has_en {
+                missing.insert(pointer);
+            }
+            return;
+        }
+        if let Some(def) = defs.get(name) {
+            find_texts_without_en(defs, def, instance, pointer.clone(), missing);
+        }
+    }
+
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        for sub_schema in schema
+            .get(keyword)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            find_texts_without_en(defs, sub_schema, instance, pointer.clone(), missing);
+        }
+    }
+
+    match instance {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, value) in map {
+                let sub_schema = match properties.and_then(|p| p.get(key)) {
+                    Some(sub_schema) => sub_schema,
+                    None => match schema.get("additionalProperties") {
+                        Some(sub_schema) => sub_schema,
+                        None => continue,
+                    },
+                };
+                let pointer = format!("{pointer}/{}", escape_pointer(key));
+                find_texts_without_en(defs, sub_schema, value, pointer, missing);
+            }
+        }
+        Value::Array(items) => {
+            let prefix = schema.get("prefixItems").and_then(Value::as_array);
+            for (index, item) in items.iter().enumerate() {
+                let sub_schema = match prefix.and_then(|p| p.get(index)) {
+                    Some(sub_schema) => sub_schema,
+                    None => match schema.get("items") {
+                        Some(sub_schema) => sub_schema,
+                        None => continue,
+                    },
+                };
+                let pointer = format!("{pointer}/{index}");
+                find_texts_without_en(defs, sub_schema, item, pointer, missing);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Locations of the values of a YAML document by JSON pointer, following its `!extend`s.
+#[derive(Debug, Default)]
+struct SourceMap {
+    locations: BTreeMap<String, SourceLocation>,
+    /// `!extend`ed files being scanned, to detect cycles
+    chain: Vec<PathBuf>,
+    /// problems found on the sources, like syntax errors or missing files
+    issues: Vec<LintIssue>,
+}
+
+impl SourceMap {
+    fn scan_document(file: &Path) -> Self {
+        let mut map = Self::default();
+        map.scan(file, "", None);
+        map
+    }
+
+    /// Location of the value at the pointer, or of its closest written ancestor.
+    fn locate(&self, pointer: &str) -> Option<SourceLocation> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(location) = self.locations.get(pointer) {
+                return Some(location.clone());
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    fn issue(&mut self, pointer: &str, location: Option<SourceLocation>, message: String) {
+        self.issues.push(LintIssue {
+            severity: LintSeverity::Error,
+            pointer: pointer.to_string(),
+            location,
+            message,
+        });
+    }
+
+    /// Scans the file whose content is placed at `prefix`, `origin` is where it is referenced.
+    fn scan(&mut self, file: &Path, prefix: &str, origin: Option<SourceLocation>) {
+        let content = match std::fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(err) => {
+                self.issue(
+                    prefix,
+                    origin,
+                    format!("can't read {}: {err}", file.display()),
+                );
+                return;
+            }
+        };
+
+        let location = |mark: &Marker| SourceLocation {
+            file: file.to_path_buf(),
+            line: mark.line(),
+            column: mark.col() + 1,
+        };
+
+        let mut recorder = LocationRecorder::new(prefix);
+        if let Err(err) = Parser::new_from_str(&content).load(&mut recorder, false) {
+            self.issue(
+                prefix,
+                Some(location(err.marker())),
+                format!("invalid YAML: {}", err.info()),
+            );
+            return;
+        }
+
+        for (pointer, mark) in &recorder.locations {
+            self.locations
+                .entry(pointer.clone())
+                .or_insert_with(|| location(mark));
+        }
+
+        let document: serde_yaml::Value = match serde_yaml::from_str(&content) {
+            Ok(document) => document,
+            Err(err) => {
+                let location = err.location().map(|l| SourceLocation {
+                    file: file.to_path_buf(),
+                    line: l.line(),
+                    column: l.column(),
+                });
+                self.issue(prefix, location, format!("invalid YAML: {err}"));
+                return;
+            }
+        };
+        let mut tagged = Vec::new();
+        tagged_paths(&document, prefix.to_string(), &mut tagged);
+
+        let base = file.parent().unwrap_or(Path::new(""));
+        self.chain.push(file.to_path_buf());
+        for tagged in tagged {
+            let target = normalize_path(&base.join(&tagged.path));
+            let origin = self.locate(&tagged.pointer);
+            match tagged.tag.as_str() {
+                "extend" => {
+                    if self.chain.contains(&target) {
+                        self.issue(
+                            &tagged.pointer,
+                            origin,
+                            format!("circular !extend of {}", target.display()),
+                        );
+                        continue;
+                    }
+                    self.scan(&target, &tagged.pointer, origin);
+                }
+                "include" => {
+                    let is_sass = target
+                        .extension()
+                        .is_some_and(|ext| ext == "scss" || ext == "sass");
+                    let result = if is_sass {
+                        grass::from_path(&target, &grass::Options::default())
+                            .map(|_| ())
+                            .map_err(|e| e.to_string())
+                    } else {
+                        std::fs::metadata(&target)
+                            .map(|_| ())
+                            .map_err(|e| e.to_string())
+                    };
+                    if let Err(err) = result {
+                        self.issue(
+                            &tagged.pointer,
+                            origin,
+                            format!("can't include {}: {err}", target.display()),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.chain.pop();
+    }
+}
+
+#[derive(Debug)]
+struct TaggedPath {
+    pointer: String,
+    /// `extend` or `include`
+    tag: String,
+    path: String,
+}
+
+/// `!extend`s and `!include`s of the document, with the pointer where their content goes.
+fn tagged_paths(value: &serde_yaml::Value, pointer: String, found: &mut Vec<TaggedPath>) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            for (key, value) in map {
+                if let Some(key) = key.as_str() {
+                    let pointer = format!("{pointer}/{}", escape_pointer(key));
+                    tagged_paths(value, pointer, found);
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(seq) => {
+            for (index, value) in seq.iter().enumerate() {
+                tagged_paths(value, format!("{pointer}/{index}"), found);
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => {
+            let (tag, path) = if tagged.tag == "!extend" {
+                ("extend", extend_path(&tagged.value))
+            } else if tagged.tag == "!include" {
+                ("include", tagged.value.as_str())
+            } else {
+                return;
+            };
+            if let Some(path) = path {
+                found.push(TaggedPath {
+                    pointer,
+                    tag: tag.to_string(),
+                    path: path.to_string(),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+#[derive(Debug)]
+enum Frame {
+    Mapping {
+        pointer: Option<String>,
+        /// `None` while waiting for a key, else the pointer of the value to come,
+        /// which is unknown for complex keys
+        key: Option<Option<String>>,
+    },
+    Sequence {
+        pointer: Option<String>,
+        index: usize,
+    },
+}
+
+/// Records where every node of a document starts. Keys are preferred over values as the
+/// location of a mapping entry, so errors point to the line naming the field.
+#[derive(Debug)]
+struct LocationRecorder {
+    prefix: String,
+    stack: Vec<Frame>,
+    locations: Vec<(String, Marker)>,
+}
+
+impl LocationRecorder {
+    fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            stack: Vec::new(),
+            locations: Vec::new(),
+        }
+    }
+
+    /// Pointer of the node starting at the mark, `None` for keys and nodes under complex keys.
+    fn start_node(&mut self, scalar: Option<&str>, mark: Marker) -> Option<String> {
+        let pointer = match self.stack.last_mut() {
+            None => Some(self.prefix.clone()),
+            Some(Frame::Sequence { pointer, index }) => {
+                let child = pointer.as_ref().map(|p| format!("{p}/{index}"));
+                *index += 1;
+                child
+            }
+            Some(Frame::Mapping { pointer, key }) => match key.take() {
+                Some(child) => child,
+                None => {
+                    let child = pointer
+                        .as_ref()
+                        .zip(scalar)
+                        .map(|(p, k)| format!("{p}/{}", escape_pointer(k)));
+                    if let Some(child) = &child {
+                        self.locations.push((child.clone(), mark));
+                    }
+                    *key = Some(child);
+                    return None;
+                }
+            },
+        };
+        if let Some(pointer) = &pointer {
+            self.locations.push((pointer.clone(), mark));
+        }
+        pointer
+    }
+}
+
+impl MarkedEventReceiver for LocationRecorder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(value, ..) => {
+                self.start_node(Some(&value), mark);
+            }
+            Event::Alias(_) => {
+                self.start_node(None, mark);
+            }
+            Event::MappingStart(_, tag) => {
+                let mut pointer = self.start_node(None, mark);
+                // the arguments of an `!extend` are replaced by the extended file
+                if tag.is_some_and(|tag| tag.handle == "!" && tag.suffix == "extend") {
+                    pointer = None;
+                }
+                self.stack.push(Frame::Mapping { pointer, key: None });
+            }
+            Event::SequenceStart(..) => {
+                let pointer = self.start_node(None, mark);
+                self.stack.push(Frame::Sequence { pointer, index: 0 });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Widget;
+
+    fn temp_folder(files: &[(&str, &str)]) -> Result<PathBuf> {
+        let folder = std::env::temp_dir().join(format!("lint-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&folder)?;
+        for (name, content) in files {
+            std::fs::write(folder.join(name), content)?;
+        }
+        Ok(folder)
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_located_in_extended_files() -> Result<()> {
+        let folder = temp_folder(&[
+            (
+                "metadata.yml",
+                "id: '@someone/clock'\nmetadata:\n  displayName: !extend { path: texts.yml }\n",
+            ),
+            ("texts.yml", "es: Reloj\nen:\n  - Clock\n"),
+        ])?;
+
+        let issues = lint_resource::<Widget>(&folder, (2, 8, 3)).await?;
+        std::fs::remove_dir_all(&folder)?;
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, LintSeverity::Error);
+        assert_eq!(issues[0].pointer, "/metadata/displayName/en");
+        let location = issues[0].location.clone().unwrap();
+        assert_eq!(location.file, folder.join("texts.yml"));
+        assert_eq!((location.line, location.column), (2, 1));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resource_checks() -> Result<()> {
+        let folder = temp_folder(&[(
+            "metadata.yml",
+            r#"id: '@someone/clock'
+metadata:
+  displayName:
+    es: Reloj
+  description: A clock ${vars.size}
+  appTargetVersion: [3, 0, 0]
+settings:
+  - type: Switch
+    key: enabled
+    label: Enabled
+    defaultValue: true
+"#,
+        )])?;
+
+        let issues = lint_resource::<Widget>(&folder, (2, 8, 3)).await?;
+        std::fs::remove_dir_all(&folder)?;
+
+        let found: Vec<(LintSeverity, &str, usize)> = issues
+            .iter()
+            .map(|i| {
+                let line = i.location.as_ref().map_or(0, |l| l.line);
+                (i.severity, i.pointer.as_str(), line)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (LintSeverity::Warning, "/metadata/description", 5),
+                (LintSeverity::Error, "", 1),
+                (LintSeverity::Error, "/metadata/displayName", 3),
+                (LintSeverity::Error, "/metadata/appTargetVersion", 6),
+            ]
+        );
+        assert!(issues[1].message.ends_with("keys: enabled"));
+        Ok(())
+    }
+}
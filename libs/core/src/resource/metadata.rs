@@ -8,7 +8,7 @@ use url::Url;
 
 use crate::{
     error::Result,
-    resource::{Resource, ResourceText},
+    resource::{Resource, ResourceText, SluSignatureStatus},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -67,6 +67,9 @@ pub struct InternalResourceMetadata {
     pub written_at: DateTime<Utc>,
     /// only present for remote/downloaded resources
     pub remote: Option<Box<Resource>>,
+    /// signature of the `.slu` file the resource was loaded from, other files are unsigned
+    #[serde(skip)]
+    pub signature: SluSignatureStatus,
 }
 
 impl Default for ResourceMetadata {
@@ -1,13 +1,17 @@
 mod file;
 mod interface;
+mod lint;
 mod metadata;
 mod resource_id;
+mod signature;
 mod yaml_ext;
 
 pub use file::*;
 pub use interface::*;
+pub use lint::*;
 pub use metadata::*;
 pub use resource_id::*;
+pub use signature::*;
 pub use yaml_ext::*;
 
 pub use crate::utils::Slug;
@@ -17,6 +17,7 @@ use crate::error::Result;
     derive(ts_rs::TS),
     ts(type = "string & { __brand: 'ResourceId' }")
 )]
+#[schemars(untagged)]
 pub enum ResourceId {
     /// Development / bundled resource. Format: `@creator/name`.
     Local(String),
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+
+use super::{InternalResourceMetadata, SluAssets, SluResourceFile};
+
+/// Fields of [`super::Resource`] changed by the store, not by the publisher.
+const STORE_MANAGED_FIELDS: &[&str] = &[
+    "status",
+    "rejectedReason",
+    "reviewedAt",
+    "reviewedBy",
+    "deletedAt",
+    "attributes",
+    "stars",
+    "downloads",
+    "pendingUpdate",
+    "rejectedPendingReason",
+];
+
+/// Minisign signature of a `.slu` file, made over [`SluResourceFile::signed_payload`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SluSignature {
+    /// minisign public key of the publisher, base64 encoded
+    pub public_key: String,
+    /// minisign signature box
+    pub signature: String,
+}
+
+/// Result of checking the signature of a `.slu` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum SluSignatureStatus {
+    /// The file has no signature.
+    #[default]
+    Unsigned,
+    /// The content doesn't match the signature, it was modified after being signed.
+    Tampered,
+    /// The content was signed by the embedded publisher key, which may or may not be trusted.
+    Verified { public_key: String },
+}
+
+impl SluAssets {
+    /// SHA-256 of every asset by name, streamed so big assets are not loaded in memory
+    pub fn digests(&self) -> Result<BTreeMap<String, String>> {
+        let mut digests = BTreeMap::new();
+        for name in self.names() {
+            let mut hasher = Sha256::new();
+            self.copy_to(name, &mut hasher)?;
+            digests.insert(name.to_string(), format!("{:x}", hasher.finalize()));
+        }
+        Ok(digests)
+    }
+}
+
+impl SluResourceFile {
+    /// Bytes covered by the signature: the resource, its data and the digests of its assets.
+    /// The fields managed by the store after publishing, like stars, downloads, status or staff
+    /// attributes, are left out as they change without the publisher, as well as the metadata
+    /// only meaningful on this machine. serde_json maps are sorted, so the field order of the
+    /// document doesn't matter.
+    pub fn signed_payload(&self) -> Result<Vec<u8>> {
+        let mut resource = serde_json::to_value(&self.resource)?;
+        if let Some(resource) = resource.as_object_mut() {
+            for key in STORE_MANAGED_FIELDS {
+                resource.remove(*key);
+            }
+            let internal = serde_json::to_value(InternalResourceMetadata::default())?;
+            if let (Some(metadata), Some(internal)) = (
+                resource
+                    .get_mut("metadata")
+                    .and_then(serde_json::Value::as_object_mut),
+                internal.as_object(),
+            ) {
+                for key in internal.keys() {
+                    metadata.remove(key);
+                }
+            }
+        }
+
+        let payload = serde_json::json!({
+            "resource": resource,
+            "data": self.data.0,
+            "assets": self.assets.digests()?,
+        });
+        Ok(serde_json::to_vec(&payload)?)
+    }
+
+    /// Signs the file with the minisign secret key of the publisher.
+    pub fn sign(&mut self, secret_key: &str, password: String, public_key: &str) -> Result<()> {
+        let payload = self.signed_payload()?;
+        let signature = slu_utils::signature::sign_minisign(&payload, secret_key, password)?;
+        // signing with a key pair that doesn't match would produce a file that looks tampered
+        slu_utils::signature::verify_minisign(&payload, &signature, public_key)?;
+        self.signature = Some(SluSignature {
+            public_key: public_key.to_string(),
+            signature,
+        });
+        self.signature_status = SluSignatureStatus::Verified {
+            public_key: public_key.to_string(),
+        };
+        Ok(())
+    }
+
+    /// Checks the embedded signature against the content, updating [`Self::signature_status`].
+    pub fn verify_signature(&mut self) -> Result<&SluSignatureStatus> {
+        self.signature_status = match &self.signature {
+            None => SluSignatureStatus::Unsigned,
+            Some(signature) => {
+                let payload = self.signed_payload()?;
+                match slu_utils::signature::verify_minisign(
+                    &payload,
+                    &signature.signature,
+                    &signature.public_key,
+                ) {
+                    Ok(()) => SluSignatureStatus::Verified {
+                        public_key: signature.public_key.clone(),
+                    },
+                    Err(_) => SluSignatureStatus::Tampered,
+                }
+            }
+        };
+        Ok(&self.signature_status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::ResourceText;
+
+    /// minisign key pair only used by tests, the secret key is encrypted with `test`
+    const SECRET_KEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIGVuY3J5cHRlZCBzZWNyZXQga2V5ClJXUlRZMEl5WTBlYTFwb0pDeVdDZCt5UHVtK1pRWm92K3lTSmdWRUdWOGxFek5FVWpwY0FnQUFBQUFBQUFBQUFBQUVBQUFBQVJ0V0h0ZEZMUHhVRUlISnhGOFVSU3paSlhKZGcrVmJ5SklDeVNNZ1lNaHlnVkttd2pIYXRBcnFZSW9FNXBGbUlueTR5WEJzc3VhWkRWZ2ZtditzaWZXT2Q2WmtxUUhXZFlCVVhWRS9qTFRUY01FU1A2SDhBc080MjdWeEhkVHpuSlJMVmRyMkZTWnc9Cg==";
+    const PUBLIC_KEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXkgN0NDMzJGRDYwOUI5OTk3NQpSV1IxbWJrSjFpL0RmRzJYQWw4STFRVXhtUDdBZzYrcmJCQ0xTeUUvRWkyRnpZdjdFT1BmTmhJUQo=";
+
+    fn signed_file() -> SluResourceFile {
+        let document = r#"
+version: 2
+resource:
+  id: 1f7d5f0e-5d1a-4c1e-9d55-8e4b0b1b6f3a
+  dataId: 6c2b8a56-3f7e-4a63-a0a4-6f4f4b1bb0d1
+  creatorId: 0b0a6a7e-9f8e-4b2e-8a41-3e0c2a1d5c77
+  slug: clock
+  kind: Widget
+  metadata:
+    displayName: Clock
+    description: Shows the time
+  createdAt: 2025-01-01T00:00:00Z
+  updatedAt: 2025-01-01T00:00:00Z
+  status: Published
+  version: 1
+  stars: 0
+  downloads: 0
+data: {}
+"#;
+        let mut file: SluResourceFile = serde_yaml::from_str(document).unwrap();
+        file.sign(SECRET_KEY, "test".to_string(), PUBLIC_KEY)
+            .unwrap();
+        file
+    }
+
+    #[test]
+    fn test_store_counters_are_not_signed() -> Result<()> {
+        let mut file = signed_file();
+        file.resource.stars = 25;
+        file.resource.downloads = 1000;
+        assert!(matches!(
+            file.verify_signature()?,
+            SluSignatureStatus::Verified { .. }
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_checks_the_signature() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("slu-sign-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&folder)?;
+        let path = folder.join("clock.slu");
+
+        signed_file().store(&path).await?;
+        let mut loaded = SluResourceFile::load(&path).await?;
+        assert!(matches!(
+            loaded.signature_status,
+            SluSignatureStatus::Verified { .. }
+        ));
+
+        // e.g. edited by hand after being installed
+        loaded.data.0["html"] = "<script>steal()</script>".into();
+        loaded.store(&path).await?;
+        let loaded = SluResourceFile::load(&path).await?;
+        assert_eq!(loaded.signature_status, SluSignatureStatus::Tampered);
+
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_edited_metadata_is_tampered() -> Result<()> {
+        let mut file = signed_file();
+        file.resource.metadata.display_name = ResourceText::En("Totally Safe Clock".to_string());
+        assert_eq!(file.verify_signature()?, &SluSignatureStatus::Tampered);
+        Ok(())
+    }
+}
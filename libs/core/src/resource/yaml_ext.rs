@@ -1,20 +1,37 @@
 // the idea with this module is improve YAML with extensibility, via custom keywords
 
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
 
 use serde_path_to_error;
 use serde_yaml::{Mapping, Value};
 
 use crate::error::Result;
 
+/// Environment variables that resources can read as `${env.NAME}`, any other is left as is.
+/// Only the ones needed to locate the user folders are exposed, and only to local resources:
+/// they reveal the user name, which downloaded `.slu` files could send away (e.g. in a css `url`).
+pub const ALLOWED_ENV_VARS: &[&str] = &[
+    "USERNAME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PUBLIC",
+    "SYSTEMROOT",
+];
+
 /// Will deserialize a YAML file and parse the custom extended syntax
 pub async fn deserialize_extended_yaml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
-    let value = read_and_parse_yml(path, true, &HashMap::new()).await?;
+    let value = read_and_parse_yml(path, true, None).await?;
     serde_path_to_error::deserialize(value).map_err(|e| e.to_string().into())
 }
 
-/// Like [`deserialize_extended_yaml`] but leaves `${self.id}` literals intact so they can be
-/// resolved at runtime with the published id.
+/// Like [`deserialize_extended_yaml`] but leaves `${self.*}` and `${env.*}` literals intact so
+/// they can be resolved at runtime with the published id and on the machine of the user.
+/// `${vars.*}` are still resolved, as the `vars` blocks and `!extend` parameters are not kept.
 ///
 /// This must also apply to `!extend`ed files (bundling resolves the whole tree), so the
 /// `resolve_self: false` mode is threaded through [`resolve_extensions`] instead of only
@@ -22,34 +39,42 @@ pub async fn deserialize_extended_yaml<T: serde::de::DeserializeOwned>(path: &Pa
 pub async fn deserialize_extended_yaml_no_vars<T: serde::de::DeserializeOwned>(
     path: &Path,
 ) -> Result<T> {
-    let value = read_and_parse_yml(path, false, &HashMap::new()).await?;
+    let value = read_and_parse_yml(path, false, None).await?;
     serde_path_to_error::deserialize(value).map_err(|e| e.to_string().into())
 }
 
-/// `${self.id}` always refers to the id of the root document, never the id of whichever
-/// `!extend`ed file is currently being read. `vars` is computed once, at the root (when
-/// `inherited_vars` is empty), and then passed down unchanged through every `!extend` level -
-/// it is inherited, not recomputed per file.
+/// `${self.*}` always refers to the root document, never to whichever `!extend`ed file is
+/// currently being read. They are computed once, at the root (when `inherited_vars` is `None`),
+/// with the allowed `${env.*}`, and then passed down unchanged through every `!extend` level -
+/// they are inherited, not recomputed per file.
+///
+/// `${vars.*}` follow the same rule: the `vars` block of a file only gives defaults, for itself
+/// and the files it extends, the values inherited from the file extending it win. Only the
+/// parameters of an `!extend` override them, for the extended file.
 async fn read_and_parse_yml(
     path: &Path,
     resolve_self: bool,
-    inherited_vars: &HashMap<String, String>,
+    inherited_vars: Option<&HashMap<String, String>>,
 ) -> Result<Value> {
     let base = path.parent().ok_or("No parent directory")?.to_path_buf();
     let content = tokio::fs::read(path).await?;
-    let raw_value = slice_to_yml_value(content).await?;
-
-    if !resolve_self {
-        return resolve_extensions(&base, raw_value, resolve_self, inherited_vars).await;
-    }
+    let mut raw_value = slice_to_yml_value(content).await?;
+    let defaults = match raw_value.as_mapping_mut().and_then(|m| m.remove("vars")) {
+        Some(block) => scalar_vars("vars", block)?,
+        None => Vec::new(),
+    };
 
-    let vars = if inherited_vars.is_empty() {
-        let mut vars = HashMap::new();
-        vars.insert("self.id".to_string(), extract_self_id(&raw_value)?);
-        vars
-    } else {
-        inherited_vars.clone()
+    let mut vars = match inherited_vars {
+        Some(inherited) => inherited.clone(),
+        None if resolve_self => {
+            let mut vars = metadata_vars(&raw_value);
+            vars.insert("self.id".to_string(), extract_self_id(&raw_value)?);
+            vars.extend(env_vars());
+            vars
+        }
+        None => HashMap::new(),
     };
+    resolve_defaults(&mut vars, defaults)?;
 
     let value = resolve_extensions(&base, raw_value, resolve_self, &vars).await?;
     Ok(resolve_vars_yaml(value, &vars))
@@ -95,15 +120,159 @@ pub fn extract_self_id_slu(root: &Value) -> Result<String> {
         .ok_or_else(|| "SLU resource file is missing required 'resource.id' field".into())
 }
 
-/// Substitutes `${key}` placeholders in a string using the given variable map.
+/// Variables of a `.slu` document: `self.id` is the published id, `self.metadata.*` come from
+/// the resource data. `env.*` are not resolved, see [`ALLOWED_ENV_VARS`].
+pub fn extract_vars_slu(root: &Value) -> Result<HashMap<String, String>> {
+    let mut vars = root.get("data").map(metadata_vars).unwrap_or_default();
+    vars.insert("self.id".to_string(), extract_self_id_slu(root)?);
+    Ok(vars)
+}
+
+/// `self.metadata.*` variables, one by scalar of the `metadata` of the document, like
+/// `self.metadata.displayName.en` or `self.metadata.appTargetVersion.0`.
+fn metadata_vars(document: &Value) -> HashMap<String, String> {
+    fn flatten(prefix: String, value: &Value, vars: &mut HashMap<String, String>) {
+        match value {
+            Value::Mapping(map) => {
+                for (key, value) in map {
+                    if let Some(key) = key.as_str() {
+                        flatten(format!("{prefix}.{key}"), value, vars);
+                    }
+                }
+            }
+            Value::Sequence(seq) => {
+                for (index, value) in seq.iter().enumerate() {
+                    flatten(format!("{prefix}.{index}"), value, vars);
+                }
+            }
+            _ => {
+                if let Some(value) = scalar_to_string(value) {
+                    vars.insert(prefix, value);
+                }
+            }
+        }
+    }
+
+    let mut vars = HashMap::new();
+    if let Some(metadata) = document.get("metadata") {
+        flatten("self.metadata".to_string(), metadata, &mut vars);
+    }
+    vars
+}
+
+fn env_vars() -> HashMap<String, String> {
+    ALLOWED_ENV_VARS
+        .iter()
+        .filter_map(|name| {
+            let value = std::env::var(name).ok()?;
+            Some((format!("env.{name}"), value))
+        })
+        .collect()
+}
+
+/// Reads a mapping of scalars, like a `vars` block, as variables named `{prefix}.{key}`,
+/// in the order they are written.
+fn scalar_vars(prefix: &str, block: Value) -> Result<Vec<(String, String)>> {
+    let Value::Mapping(map) = block else {
+        return Err(format!("`{prefix}` must be a mapping").into());
+    };
+
+    let mut vars = Vec::new();
+    for (key, value) in map {
+        let key = key
+            .as_str()
+            .ok_or_else(|| format!("`{prefix}` keys must be strings"))?;
+        let value = scalar_to_string(&value)
+            .ok_or_else(|| format!("`{prefix}.{key}` must be a string, number or boolean"))?;
+        vars.push((format!("{prefix}.{key}"), value));
+    }
+    Ok(vars)
+}
+
+/// Adds the `vars` block defaults that were not inherited. They can reference each other, so
+/// they are resolved in the written order until nothing changes, failing on cycles.
+fn resolve_defaults(
+    vars: &mut HashMap<String, String>,
+    defaults: Vec<(String, String)>,
+) -> Result<()> {
+    let defaults: Vec<(String, String)> = defaults
+        .into_iter()
+        .filter(|(key, _)| !vars.contains_key(key))
+        .collect();
+    let keys: Vec<String> = defaults.iter().map(|(key, _)| key.clone()).collect();
+    vars.extend(defaults);
+
+    // a chain can't be longer than the amount of vars, so more passes mean a cycle
+    for _ in 0..=keys.len() {
+        let mut changed = false;
+        for key in &keys {
+            let value = interpolate(vars[key].clone(), vars);
+            if value != vars[key] {
+                vars.insert(key.clone(), value);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // cycles can also settle, e.g. `a: ${vars.b}` and `b: ${vars.a}` end both as `${vars.a}`
+    let cyclic: Vec<&str> = keys
+        .iter()
+        .filter(|key| {
+            let value = &vars[*key];
+            keys.iter()
+                .any(|other| value.contains(&format!("${{{other}}}")))
+        })
+        .map(String::as_str)
+        .collect();
+    if !cyclic.is_empty() {
+        return Err(format!("`{}` reference each other", cyclic.join("`, `")).into());
+    }
+    Ok(())
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Path of an `!extend`, written as `!extend base.yml` or `!extend { path: base.yml, vars: {} }`
+pub(crate) fn extend_path(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(path) => Some(path),
+        Value::Mapping(args) => args.get("path").and_then(Value::as_str),
+        _ => None,
+    }
+}
+
+/// Substitutes the `${key}` placeholders of a string using the given variable map, unknown
+/// ones are left as is. Substituted values are not scanned again.
 fn interpolate(s: String, vars: &HashMap<String, String>) -> String {
-    let mut result = s;
-    for (key, val) in vars {
-        let placeholder = format!("${{{key}}}");
-        if result.contains(&placeholder) {
-            result = result.replace(&placeholder, val);
+    if !s.contains("${") {
+        return s;
+    }
+
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let end = start + len;
+        result.push_str(&rest[..start]);
+        match vars.get(&rest[start + 2..end]) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..=end]),
         }
+        rest = &rest[end + 1..];
     }
+    result.push_str(rest);
     result
 }
 
@@ -179,11 +348,18 @@ async fn resolve_extensions(
             }
 
             if tag.tag == "!extend" {
-                if let Value::String(relative_path) = tag.value {
+                if let Some(relative_path) = extend_path(&tag.value) {
+                    let mut extended_vars = vars.clone();
+                    if let Some(params) = tag.value.get("vars") {
+                        // parameters are written in this file, so they use its variables
+                        for (key, value) in scalar_vars("vars", params.clone())? {
+                            extended_vars.insert(key, interpolate(value, vars));
+                        }
+                    }
                     let value = Box::pin(read_and_parse_yml(
                         &base.join(relative_path),
                         resolve_self,
-                        vars,
+                        Some(&extended_vars),
                     ))
                     .await?;
                     return Ok(value);
@@ -195,3 +371,298 @@ async fn resolve_extensions(
         _ => Ok(value),
     }
 }
+
+/// Recursively sorts the keys of every mapping, so the same document is always serialized
+/// the same way, whatever the order of its sources (e.g. hash maps).
+pub fn sort_yaml_keys(value: Value) -> Value {
+    match value {
+        Value::Mapping(map) => {
+            let mut entries: Vec<(Value, Value)> = map
+                .into_iter()
+                .map(|(k, v)| (k, sort_yaml_keys(v)))
+                .collect();
+            entries.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            Value::Mapping(entries.into_iter().collect())
+        }
+        Value::Sequence(seq) => Value::Sequence(seq.into_iter().map(sort_yaml_keys).collect()),
+        Value::Tagged(mut tag) => {
+            tag.value = sort_yaml_keys(tag.value);
+            Value::Tagged(tag)
+        }
+        _ => value,
+    }
+}
+
+/// Every file read to resolve the extended YAML document at `path`: the document itself,
+/// the `!include`d and `!extend`ed files and the Sass modules used by included stylesheets.
+pub async fn extended_yaml_dependencies(path: &Path) -> Result<BTreeSet<PathBuf>> {
+    let path = normalize_path(path);
+    tokio::task::spawn_blocking(move || {
+        let mut files = BTreeSet::new();
+        collect_dependencies(&path, &mut files)?;
+        Ok(files)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn collect_dependencies(path: &Path, files: &mut BTreeSet<PathBuf>) -> Result<()> {
+    if !files.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+
+    let base = path.parent().ok_or("No parent directory")?;
+    let mut pending = vec![serde_yaml::from_slice::<Value>(&std::fs::read(path)?)?];
+    while let Some(value) = pending.pop() {
+        match value {
+            Value::Mapping(map) => pending.extend(map.into_iter().map(|(_, v)| v)),
+            Value::Sequence(seq) => pending.extend(seq),
+            Value::Tagged(tag) => {
+                let relative_path = match &tag.value {
+                    Value::String(relative_path) => relative_path.as_str(),
+                    value if tag.tag == "!extend" => match extend_path(value) {
+                        Some(relative_path) => relative_path,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let file = normalize_path(&base.join(relative_path));
+                if tag.tag == "!extend" {
+                    collect_dependencies(&file, files)?;
+                } else if tag.tag == "!include" {
+                    if file
+                        .extension()
+                        .is_some_and(|ext| ext == "scss" || ext == "sass")
+                    {
+                        files.extend(sass_dependencies(&file)?);
+                    } else {
+                        files.insert(file);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Files read by grass while compiling the stylesheet, including itself.
+fn sass_dependencies(path: &Path) -> Result<Vec<PathBuf>> {
+    #[derive(Debug, Default)]
+    struct RecordingFs(Mutex<Vec<PathBuf>>);
+
+    impl grass::Fs for RecordingFs {
+        fn is_dir(&self, path: &Path) -> bool {
+            path.is_dir()
+        }
+
+        fn is_file(&self, path: &Path) -> bool {
+            path.is_file()
+        }
+
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            self.0.lock().unwrap().push(normalize_path(path));
+            std::fs::read(path)
+        }
+    }
+
+    let fs = RecordingFs::default();
+    grass::from_path(path, &grass::Options::default().fs(&fs))?;
+    Ok(fs.0.into_inner().unwrap())
+}
+
+/// Resolves `.` and `..` without touching the file system.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_yaml_keys() -> Result<()> {
+        let value: Value = serde_yaml::from_str("b: 1\na:\n  d: [{z: 1, y: 2}]\n  c: 3\n")?;
+        assert_eq!(
+            serde_yaml::to_string(&sort_yaml_keys(value))?,
+            "a:\n  c: 3\n  d:\n  - y: 2\n    z: 1\nb: 1\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extended_yaml_dependencies() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("yaml-deps-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(folder.join("styles"))?;
+        std::fs::create_dir_all(folder.join("settings"))?;
+
+        std::fs::write(
+            folder.join("metadata.yml"),
+            "id: '@me/res'\nstyles: !include styles/main.scss\nsettings: !extend settings/base.yml\n",
+        )?;
+        std::fs::write(
+            folder.join("styles/main.scss"),
+            "@use 'colors';\n.a { color: colors.$main; }\n",
+        )?;
+        std::fs::write(folder.join("styles/_colors.scss"), "$main: red;\n")?;
+        std::fs::write(
+            folder.join("settings/base.yml"),
+            "text: !include ../README.md\n",
+        )?;
+        std::fs::write(folder.join("README.md"), "hello")?;
+        std::fs::write(folder.join("unused.txt"), "not part of the resource")?;
+
+        let files = extended_yaml_dependencies(&folder.join("metadata.yml")).await?;
+        let relative: Vec<String> = files
+            .iter()
+            .map(|f| {
+                f.strip_prefix(&folder)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(
+            relative,
+            vec![
+                "README.md",
+                "metadata.yml",
+                "settings/base.yml",
+                "styles/_colors.scss",
+                "styles/main.scss",
+            ]
+        );
+
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vars_interpolation() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("yaml-vars-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&folder)?;
+        std::fs::write(
+            folder.join("metadata.yml"),
+            r#"id: '@someone/theme-red'
+metadata:
+  displayName: Red
+vars:
+  accent: red
+  size: 2
+styles:
+  base: !extend base.yml
+  small: !extend { path: base.yml, vars: { size: 1, label: "${vars.accent} small" } }
+title: "${self.metadata.displayName} (${self.id})"
+path: "${env.PATH}"
+"#,
+        )?;
+        std::fs::write(
+            folder.join("base.yml"),
+            r#"vars:
+  accent: blue
+  label: default
+css: ".a { color: ${vars.accent}; width: ${vars.size}px }"
+label: "${vars.label}"
+owner: "${self.id}"
+"#,
+        )?;
+
+        let value: serde_json::Value =
+            deserialize_extended_yaml(&folder.join("metadata.yml")).await?;
+        assert_eq!(
+            value["styles"],
+            serde_json::json!({
+                "base": {
+                    "css": ".a { color: red; width: 2px }",
+                    "label": "default",
+                    "owner": "@someone/theme-red",
+                },
+                "small": {
+                    "css": ".a { color: red; width: 1px }",
+                    "label": "red small",
+                    "owner": "@someone/theme-red",
+                },
+            })
+        );
+        assert_eq!(value["title"], "Red (@someone/theme-red)");
+        // not in the allow list
+        assert_eq!(value["path"], "${env.PATH}");
+        assert!(value.get("vars").is_none());
+
+        // bundles keep what depends on the published id or the machine of the user
+        let value: serde_json::Value =
+            deserialize_extended_yaml_no_vars(&folder.join("metadata.yml")).await?;
+        assert_eq!(
+            value["styles"]["small"]["css"],
+            ".a { color: red; width: 1px }"
+        );
+        assert_eq!(value["styles"]["small"]["owner"], "${self.id}");
+        assert_eq!(value["title"], "${self.metadata.displayName} (${self.id})");
+
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chained_vars_are_deterministic() -> Result<()> {
+        let folder = std::env::temp_dir().join(format!("yaml-chain-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&folder)?;
+        let path = folder.join("metadata.yml");
+
+        // written out of order too, so it doesn't depend on the order of the block
+        std::fs::write(
+            &path,
+            r#"id: '@someone/chain'
+vars:
+  c: "${vars.b}-z"
+  a: x
+  b: "${vars.a}-y"
+out: "${vars.c}"
+"#,
+        )?;
+        for _ in 0..50 {
+            let value: serde_json::Value = deserialize_extended_yaml(&path).await?;
+            assert_eq!(value["out"], "x-y-z");
+        }
+
+        std::fs::write(
+            &path,
+            r#"id: '@someone/chain'
+vars:
+  a: "${vars.b}"
+  b: "${vars.a}"
+out: "${vars.a}"
+"#,
+        )?;
+        assert!(deserialize_extended_yaml::<serde_json::Value>(&path)
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_slu_files_dont_read_the_environment() -> Result<()> {
+        let root: Value = serde_yaml::from_str(
+            "resource:\n  id: 1f7d5f0e-5d1a-4c1e-9d55-8e4b0b1b6f3a\ndata:\n  metadata:\n    displayName: Clock\n",
+        )?;
+        let vars = extract_vars_slu(&root)?;
+        assert_eq!(vars["self.metadata.displayName"], "Clock");
+        assert!(vars.keys().all(|key| !key.starts_with("env.")));
+
+        let value = resolve_vars_yaml(Value::String("url(?u=${env.USERNAME})".into()), &vars);
+        assert_eq!(value, Value::String("url(?u=${env.USERNAME})".into()));
+        Ok(())
+    }
+}
@@ -16,6 +16,120 @@ impl TwmPlugin {
             }),
         }
     }
+
+    /// `master_count` windows on a column taking `master_ratio` (0.1 - 0.9) of the width,
+    /// the rest of the windows are placed on a second column.
+    pub fn master_stack(master_count: usize, master_ratio: f32) -> Self {
+        let master_count = master_count.max(1);
+        let ratio = master_ratio.clamp(0.1, 0.9);
+        Self {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Horizontal,
+                children: vec![
+                    TwmPluginNode {
+                        kind: TwmNodeKind::Vertical,
+                        priority: 1,
+                        grow_factor: ratio,
+                        children: (1..=master_count as u32).map(TwmPluginNode::slot).collect(),
+                        ..Default::default()
+                    },
+                    TwmPluginNode {
+                        kind: TwmNodeKind::Vertical,
+                        priority: 2,
+                        grow_factor: 1.0 - ratio,
+                        children: vec![TwmPluginNode::template(TwmPluginNode::slot(1))],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Each new window takes half of the space of the previous one, alternating
+    /// horizontal and vertical splits towards the bottom right corner.
+    pub fn dwindle() -> Self {
+        Self {
+            structure: Some(TwmPluginNode::fibonacci(0, 2, false)),
+        }
+    }
+
+    /// Same as [`TwmPlugin::dwindle`] but rotating the split side, so windows are
+    /// placed in a spiral (right, down, left, up).
+    pub fn spiral() -> Self {
+        Self {
+            structure: Some(TwmPluginNode::fibonacci(0, 4, true)),
+        }
+    }
+
+    /// The first window is centered taking `master_ratio` (0.1 - 0.9) of the width,
+    /// next windows are placed alternating between a right and a left column.
+    pub fn centered_master(master_ratio: f32) -> Self {
+        let ratio = master_ratio.clamp(0.1, 0.9);
+        // columns grow taking turns, the right one first as it has a higher priority.
+        let side = || vec![TwmPluginNode::template(TwmPluginNode::slot(1))];
+
+        Self {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Horizontal,
+                children: vec![
+                    TwmPluginNode {
+                        kind: TwmNodeKind::Vertical,
+                        priority: 3,
+                        grow_factor: (1.0 - ratio) / 2.0,
+                        children: side(),
+                        ..Default::default()
+                    },
+                    TwmPluginNode {
+                        priority: 1,
+                        grow_factor: ratio,
+                        ..Default::default()
+                    },
+                    TwmPluginNode {
+                        kind: TwmNodeKind::Vertical,
+                        priority: 2,
+                        grow_factor: (1.0 - ratio) / 2.0,
+                        children: side(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Windows are placed row by row on `columns` columns.
+    pub fn grid(columns: usize) -> Self {
+        // columns grow taking turns, so each new window opens the next cell of the row.
+        let children = (1..=columns.max(1) as u32)
+            .map(|priority| TwmPluginNode {
+                kind: TwmNodeKind::Vertical,
+                priority,
+                children: vec![TwmPluginNode::template(TwmPluginNode::slot(1))],
+                ..Default::default()
+            })
+            .collect();
+
+        Self {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Horizontal,
+                children,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Each window opens a new column of `column_width` (0.1 - 1.0) of the monitor width,
+    /// columns that don't fit are placed past the monitor edge until focused.
+    pub fn scrollable(column_width: f32) -> Self {
+        Self {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Scrollable,
+                column_width: column_width.clamp(0.1, 1.0),
+                ..Default::default()
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -27,6 +141,12 @@ pub struct TwmPluginNode {
     pub kind: TwmNodeKind,
     /// Lifetime of the node
     pub lifetime: TwmNodeLifetime,
+    /// The node is not created with the layout, it is used as template to create a new
+    /// `Temporal` child of its parent each time the layout runs out of room for a window.
+    pub template: bool,
+    /// Only for templates, nodes created from the innermost template of the node
+    /// will use this node as template again, so the layout can be nested indefinitely.
+    pub recursive: bool,
     /// Order in how the tree will be traversed (1 = first, 2 = second, etc.)
     pub priority: u32,
     /// How much of the remaining space this node will take
@@ -40,6 +160,54 @@ pub struct TwmPluginNode {
     pub max_stack_size: Option<usize>,
     /// When to add new windows to the stack
     pub stack_policy: TwmStackPolicy,
+    /// Width of each column relative to the node width (multiplied by the column grow factor).\
+    /// This field is ignored for non-scrollable nodes
+    pub column_width: f32,
+}
+
+impl TwmPluginNode {
+    fn slot(priority: u32) -> Self {
+        Self {
+            priority,
+            ..Default::default()
+        }
+    }
+
+    /// Makes the node a template, see [`TwmPluginNode::template`].
+    fn template(node: Self) -> Self {
+        Self {
+            template: true,
+            ..node
+        }
+    }
+
+    /// Node of `depth` of the chain, the templates of depths `1..=period` repeat indefinitely.
+    fn fibonacci(depth: usize, period: usize, spiral: bool) -> Self {
+        let window = Self::slot(1);
+        let mut children = vec![window];
+        if depth < period {
+            let rest = Self {
+                priority: 2,
+                recursive: depth == 0,
+                ..Self::template(Self::fibonacci(depth + 1, period, spiral))
+            };
+            if spiral && depth % 4 >= 2 {
+                children.insert(0, rest);
+            } else {
+                children.push(rest);
+            }
+        }
+
+        Self {
+            kind: if depth.is_multiple_of(2) {
+                TwmNodeKind::Horizontal
+            } else {
+                TwmNodeKind::Vertical
+            },
+            children,
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for TwmPluginNode {
@@ -47,12 +215,15 @@ impl Default for TwmPluginNode {
         Self {
             kind: TwmNodeKind::Leaf,
             lifetime: Default::default(),
+            template: false,
+            recursive: false,
             priority: 1,
             grow_factor: 1.0,
             condition: None,
             children: Vec::new(),
             max_stack_size: Some(3),
             stack_policy: Default::default(),
+            column_width: 0.5,
         }
     }
 }
@@ -69,6 +240,8 @@ pub enum TwmNodeKind {
     Vertical,
     /// node will grow on x-axis
     Horizontal,
+    /// node will grow on x-axis past the monitor edge, each new window opens a new column
+    Scrollable,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -126,14 +299,40 @@ pub enum TwmCondition {
 #[serde(rename_all = "kebab-case")]
 pub enum Operand {
     TilingWindows,
+    FloatingWindows,
     IsReindexing,
+    /// Width in physical pixels of the monitor owning the workspace
+    MonitorWidth,
+    /// Height in physical pixels of the monitor owning the workspace
+    MonitorHeight,
+    /// Monitor width divided by its height, ex: `1.77` for 16:9, `2.37` for 21:9
+    AspectRatio,
+    /// True if the monitor is taller than wide (rotated monitors)
+    IsPortrait,
+    /// Position of the workspace on the monitor, starting at 0 (row by row)
+    WorkspaceIndex,
+    /// Name of the workspace, `null` if the workspace has no name
+    WorkspaceName,
 }
 
 impl Operand {
     fn resolve(&self, ctx: &TwmConditionContext) -> serde_json::Value {
+        let env = &ctx.environment;
         match self {
             Operand::TilingWindows => ctx.tiling_windows.into(),
+            Operand::FloatingWindows => ctx.floating_windows.into(),
             Operand::IsReindexing => ctx.is_reindexing.into(),
+            Operand::MonitorWidth => env.monitor_width.into(),
+            Operand::MonitorHeight => env.monitor_height.into(),
+            Operand::AspectRatio => {
+                if env.monitor_height == 0 {
+                    return 0.into();
+                }
+                (env.monitor_width as f64 / env.monitor_height as f64).into()
+            }
+            Operand::IsPortrait => (env.monitor_height > env.monitor_width).into(),
+            Operand::WorkspaceIndex => env.workspace_index.into(),
+            Operand::WorkspaceName => env.workspace_name.clone().into(),
         }
     }
 }
@@ -181,7 +380,19 @@ impl Comparator {
 #[derive(Debug, Default, Clone)]
 pub struct TwmConditionContext {
     pub tiling_windows: usize,
+    pub floating_windows: usize,
     pub is_reindexing: bool,
+    pub environment: TwmEnvironment,
+}
+
+/// Information about where a layout lives, this doesn't change with the windows on it,
+/// only when the monitor or the workspace changes.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TwmEnvironment {
+    pub monitor_width: i32,
+    pub monitor_height: i32,
+    pub workspace_index: usize,
+    pub workspace_name: Option<String>,
 }
 
 impl TwmCondition {
@@ -197,3 +408,176 @@ impl TwmCondition {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{Border, TwmRuntimeTree, WindowManagerSettings},
+        Rect,
+    };
+
+    fn compare(left: Operand, op: Comparator, right: serde_json::Value) -> TwmCondition {
+        TwmCondition::Compare { left, op, right }
+    }
+
+    fn context(width: i32, height: i32) -> TwmConditionContext {
+        TwmConditionContext {
+            environment: TwmEnvironment {
+                monitor_width: width,
+                monitor_height: height,
+                workspace_index: 1,
+                workspace_name: Some("code".to_string()),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_monitor_operands() {
+        let ultrawide = context(3440, 1440);
+        let rotated = context(1080, 1920);
+
+        let is_ultrawide = compare(Operand::AspectRatio, Comparator::Ge, 2.0.into());
+        assert!(is_ultrawide.evaluate(&ultrawide));
+        assert!(!is_ultrawide.evaluate(&rotated));
+
+        let is_portrait = compare(Operand::IsPortrait, Comparator::Eq, true.into());
+        assert!(!is_portrait.evaluate(&ultrawide));
+        assert!(is_portrait.evaluate(&rotated));
+
+        let wide_enough = compare(Operand::MonitorWidth, Comparator::Gt, 2560.into());
+        assert!(wide_enough.evaluate(&ultrawide));
+    }
+
+    #[test]
+    fn test_workspace_operands() {
+        let ctx = context(1920, 1080);
+        assert!(compare(Operand::WorkspaceIndex, Comparator::Eq, 1.into()).evaluate(&ctx));
+        assert!(compare(Operand::WorkspaceName, Comparator::Eq, "code".into()).evaluate(&ctx));
+
+        let unnamed = TwmConditionContext::default();
+        assert!(compare(
+            Operand::WorkspaceName,
+            Comparator::Eq,
+            serde_json::Value::Null
+        )
+        .evaluate(&unnamed));
+    }
+
+    fn layout_tree(plugin: &TwmPlugin, windows: isize) -> TwmRuntimeTree {
+        let mut tree = TwmRuntimeTree::from_plugin(plugin);
+        for window in 1..=windows {
+            assert!(
+                tree.add_to_tiled(window).is_empty(),
+                "window went to floating"
+            );
+        }
+        tree
+    }
+
+    fn layout_rects(plugin: &TwmPlugin, windows: isize) -> std::collections::HashMap<isize, Rect> {
+        tree_rects(&mut layout_tree(plugin, windows))
+    }
+
+    fn tree_rects(tree: &mut TwmRuntimeTree) -> std::collections::HashMap<isize, Rect> {
+        let settings = WindowManagerSettings {
+            workspace_gap: 0,
+            workspace_padding: 0,
+            border: Border {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let area = Rect {
+            left: 0,
+            top: 0,
+            right: 1000,
+            bottom: 1000,
+        };
+        tree.compute_rects(&area, &settings, 1.0)
+    }
+
+    #[test]
+    fn test_master_stack_preset() {
+        let rects = layout_rects(&TwmPlugin::master_stack(1, 0.75), 3);
+        assert_eq!(rects[&1].width(), 750);
+        assert_eq!(rects[&1].height(), 1000);
+        assert_eq!(rects[&2].left, 750);
+        assert_eq!(rects[&3].top, 500);
+
+        // only the master window, so it takes the whole monitor
+        let rects = layout_rects(&TwmPlugin::master_stack(1, 0.75), 1);
+        assert_eq!(rects[&1].width(), 1000);
+    }
+
+    #[test]
+    fn test_fibonacci_presets() {
+        let rects = layout_rects(&TwmPlugin::dwindle(), 3);
+        assert_eq!(rects[&1].width(), 500);
+        assert_eq!(rects[&2].top, 0);
+        assert_eq!(rects[&3].top, 500);
+
+        let rects = layout_rects(&TwmPlugin::spiral(), 4);
+        // third window goes to the right and the fourth one on the left of it
+        assert!(rects[&4].left < rects[&3].left);
+
+        // the chain keeps nesting, each window gets its own space
+        for plugin in [TwmPlugin::dwindle(), TwmPlugin::spiral()] {
+            let rects = layout_rects(&plugin, 12);
+            let distinct: std::collections::HashSet<_> = rects
+                .values()
+                .map(|r| (r.left, r.top, r.right, r.bottom))
+                .collect();
+            assert_eq!(distinct.len(), 12);
+            assert!(rects.values().all(|r| r.width() > 0 && r.height() > 0));
+        }
+    }
+
+    #[test]
+    fn test_presets_regrow_after_removing_windows() {
+        let mut tree = layout_tree(&TwmPlugin::master_stack(1, 0.75), 3);
+        tree.remove_window(&2);
+        assert!(tree.check_invariants().is_ok());
+        assert!(tree.add_to_tiled(4).is_empty());
+        let rects = tree_rects(&mut tree);
+        assert_eq!((rects[&3].left, rects[&3].top), (750, 0));
+        assert_eq!((rects[&4].left, rects[&4].top), (750, 500));
+
+        // the emptied column grows again
+        let mut tree = layout_tree(&TwmPlugin::centered_master(0.5), 5);
+        tree.remove_window(&2);
+        tree.remove_window(&4);
+        assert!(tree.add_to_tiled(6).is_empty());
+        assert!(tree.check_invariants().is_ok());
+        let rects = tree_rects(&mut tree);
+        assert_eq!((rects[&6].left, rects[&6].top), (750, 0));
+        assert_eq!(rects[&6].height(), 1000);
+    }
+
+    #[test]
+    fn test_centered_master_preset() {
+        let rects = layout_rects(&TwmPlugin::centered_master(0.5), 5);
+        assert_eq!(rects[&1].left, 250);
+        assert_eq!(rects[&1].width(), 500);
+        assert_eq!((rects[&2].left, rects[&2].top), (750, 0));
+        assert_eq!((rects[&3].left, rects[&3].top), (0, 0));
+        assert_eq!((rects[&4].left, rects[&4].top), (750, 500));
+        assert_eq!((rects[&5].left, rects[&5].top), (0, 500));
+    }
+
+    #[test]
+    fn test_grid_preset_fills_row_by_row() {
+        let mut tree = layout_tree(&TwmPlugin::grid(2), 3);
+        let rects = tree_rects(&mut tree);
+        assert_eq!((rects[&1].left, rects[&1].top), (0, 0));
+        assert_eq!((rects[&2].left, rects[&2].top), (500, 0));
+        assert_eq!((rects[&3].left, rects[&3].top), (0, 500));
+        assert_eq!(rects[&2].height(), 1000);
+
+        // reindexing keeps the cells
+        tree.reindex_windows();
+        assert_eq!(tree_rects(&mut tree), rects);
+    }
+}
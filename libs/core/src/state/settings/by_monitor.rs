@@ -3,7 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::{
-    resource::WidgetId,
+    rect::Rect,
+    resource::{PluginId, WidgetId},
     state::{by_widget::GenericWidgetSettings, WorkspaceId},
 };
 
@@ -38,6 +39,8 @@ pub struct MonitorConfiguration {
     pub wallpaper_collection: Option<uuid::Uuid>,
     /// dictionary of settings by workspace on this monitor
     pub by_workspace: HashMap<WorkspaceId, WorkspaceConfiguration>,
+    /// Window manager settings of this monitor, overridden by the ones of each workspace.
+    pub wm: WindowManagerOverrides,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
@@ -47,4 +50,24 @@ pub struct WorkspaceConfiguration {
     /// Id of the wallpaper collection to use in this workspace.\
     /// If not set, the monitor's wallpaper collection will be used.
     pub wallpaper_collection: Option<uuid::Uuid>,
+    /// Window manager settings of this workspace.
+    pub wm: WindowManagerOverrides,
+}
+
+/// Window manager settings that replace the global ones, unset fields are inherited
+/// following the chain workspace → monitor → global.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct WindowManagerOverrides {
+    /// Id of the window manager layout plugin to use.
+    pub layout: Option<PluginId>,
+    /// gap between containers
+    pub workspace_gap: Option<u32>,
+    /// workspace padding
+    pub workspace_padding: Option<u32>,
+    /// workspace margin
+    pub workspace_margin: Option<Rect>,
+    /// pause the tiling, windows are left where they are
+    pub paused: Option<bool>,
 }
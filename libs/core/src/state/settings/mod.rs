@@ -3,8 +3,10 @@ pub mod by_monitor;
 pub mod by_theme;
 pub mod by_wallpaper;
 pub mod by_widget;
+pub mod resource_trust;
 pub mod settings_by_app;
 pub mod shortcuts;
+pub mod workspace_templates;
 
 pub use settings_by_app::*;
 
@@ -25,9 +27,14 @@ use crate::{
     rect::Rect,
     resource::{IconPackId, PluginId, ThemeId, WallpaperId},
     state::{
-        by_monitor::MonitorConfiguration, by_theme::ThemeSettings,
-        by_wallpaper::WallpaperInstanceSettings, by_widget::SettingsByWidget,
+        by_monitor::{MonitorConfiguration, WindowManagerOverrides},
+        by_theme::ThemeSettings,
+        by_wallpaper::WallpaperInstanceSettings,
+        by_widget::SettingsByWidget,
+        resource_trust::ResourceTrustSettings,
         shortcuts::SluShortcutsSettings,
+        workspace_templates::WorkspaceTemplate,
+        GridEdgePolicy, OverviewOrder, WorkspaceId,
     },
 };
 
@@ -455,6 +462,20 @@ pub enum StartOfWeek {
     Saturday,
 }
 
+// ========================== Virtual Desktops ==============================
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct VirtualDesktopsSettings {
+    /// what happens when switching past the edge of the workspaces grid
+    pub edge_policy: GridEdgePolicy,
+    /// let each row of the grid have its own number of workspaces
+    pub ragged_rows: bool,
+    /// order of the workspaces when switching by index
+    pub overview_order: OverviewOrder,
+}
+
 // ======================== Final Settings Struct ===============================
 #[serde_alias(SnakeCase)]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -503,6 +524,12 @@ pub struct Settings {
     pub by_wallpaper: HashMap<WallpaperId, WallpaperInstanceSettings>,
     /// list of wallpaper collections
     pub wallpaper_collections: Vec<WallpaperCollection>,
+    /// workspaces grid settings
+    pub virtual_desktops: VirtualDesktopsSettings,
+    /// workspaces that can be created via `slu vd create-from-template <name>`
+    pub workspace_templates: Vec<WorkspaceTemplate>,
+    /// trusted publishers and policy for signed `.slu` resources
+    pub resource_trust: ResourceTrustSettings,
     /// Performance options
     pub performance_mode: PerformanceModeSettings,
     /// enable or disable hardware acceleration
@@ -542,6 +569,9 @@ impl Default for Settings {
             by_theme: HashMap::new(),
             by_wallpaper: HashMap::new(),
             wallpaper_collections: Vec::new(),
+            virtual_desktops: VirtualDesktopsSettings::default(),
+            workspace_templates: Vec::new(),
+            resource_trust: ResourceTrustSettings::default(),
             hardware_acceleration: true,
             unstable_optimizations: false,
             polling_interval: 3,
@@ -626,6 +656,9 @@ impl Settings {
 
         self.by_app.prepare();
         self.by_widget.sanitize();
+        for template in &mut self.workspace_templates {
+            template.prepare();
+        }
 
         self.polling_interval = self.polling_interval.max(1);
         Ok(())
@@ -740,6 +773,54 @@ impl Settings {
             .get(monitor_id)
             .is_none_or(|monitor_config| monitor_config.by_widget.is_widget_enabled(widget_id))
     }
+
+    /// Overrides of the workspace and its monitor, in precedence order
+    fn wm_overrides(
+        &self,
+        monitor_id: &MonitorId,
+        workspace_id: &WorkspaceId,
+    ) -> impl Iterator<Item = &WindowManagerOverrides> {
+        let monitor = self.monitors_v3.get(monitor_id);
+        let workspace = monitor.and_then(|m| m.by_workspace.get(workspace_id));
+        workspace
+            .map(|w| &w.wm)
+            .into_iter()
+            .chain(monitor.map(|m| &m.wm))
+    }
+
+    /// Window manager settings of the workspace, with the overrides of the workspace and its
+    /// monitor applied. `default_layout` is the layout the workspace should use.
+    pub fn wm_settings_for(
+        &self,
+        monitor_id: &MonitorId,
+        workspace_id: &WorkspaceId,
+    ) -> WindowManagerSettings {
+        let mut settings = self.by_widget.wm.clone();
+        // applied from the weakest to the strongest override
+        let overrides: Vec<_> = self.wm_overrides(monitor_id, workspace_id).collect();
+        for overrides in overrides.into_iter().rev() {
+            if let Some(layout) = overrides.layout.as_ref().filter(|id| id.is_valid()) {
+                settings.default_layout = layout.clone();
+            }
+            if let Some(gap) = overrides.workspace_gap {
+                settings.workspace_gap = gap;
+            }
+            if let Some(padding) = overrides.workspace_padding {
+                settings.workspace_padding = padding;
+            }
+            if let Some(margin) = &overrides.workspace_margin {
+                settings.workspace_margin = margin.clone();
+            }
+        }
+        settings
+    }
+
+    /// Whether the tiling is paused on the workspace by its settings or the ones of its monitor
+    pub fn is_wm_paused_on(&self, monitor_id: &MonitorId, workspace_id: &WorkspaceId) -> bool {
+        self.wm_overrides(monitor_id, workspace_id)
+            .find_map(|overrides| overrides.paused)
+            .unwrap_or(false)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -793,3 +874,44 @@ impl From<PerformanceMode> for u8 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wm_overrides_chain() {
+        let monitor_id = MonitorId::from("monitor");
+        let workspace_id = WorkspaceId::from("workspace".to_string());
+
+        let mut settings = Settings::default();
+        settings.by_widget.wm.workspace_gap = 10;
+        settings.by_widget.wm.workspace_padding = 10;
+
+        let monitor = settings.monitors_v3.entry(monitor_id.clone()).or_default();
+        monitor.wm.workspace_gap = Some(4);
+        monitor.wm.workspace_padding = Some(6);
+        monitor.wm.paused = Some(true);
+        let workspace = monitor
+            .by_workspace
+            .entry(workspace_id.clone())
+            .or_default();
+        workspace.wm.workspace_gap = Some(0);
+        workspace.wm.layout = Some("@user/layout".into());
+        workspace.wm.paused = Some(false);
+
+        let resolved = settings.wm_settings_for(&monitor_id, &workspace_id);
+        assert_eq!(resolved.workspace_gap, 0);
+        assert_eq!(resolved.workspace_padding, 6);
+        assert_eq!(resolved.default_layout, "@user/layout".into());
+        assert!(!settings.is_wm_paused_on(&monitor_id, &workspace_id));
+
+        // other workspaces of the monitor only get the monitor overrides
+        let other = WorkspaceId::from("other".to_string());
+        assert_eq!(
+            settings.wm_settings_for(&monitor_id, &other).workspace_gap,
+            4
+        );
+        assert!(settings.is_wm_paused_on(&monitor_id, &other));
+    }
+}
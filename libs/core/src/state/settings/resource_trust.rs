@@ -0,0 +1,136 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    resource::{ResourceKind, SluResourceFile, SluSignatureStatus},
+};
+
+/// Publisher whose signed resources are trusted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedPublisher {
+    /// name shown to the user
+    pub name: String,
+    /// minisign public key, base64 encoded
+    pub public_key: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct ResourceTrustSettings {
+    /// publishers whose signatures are trusted
+    pub trusted_publishers: Vec<TrustedPublisher>,
+    /// refuse to install or load widgets not signed by a trusted publisher, except the bundled ones
+    pub refuse_unsigned_widgets: bool,
+}
+
+impl ResourceTrustSettings {
+    /// Trusted publisher that signed the file, if any
+    pub fn publisher_of(&self, status: &SluSignatureStatus) -> Option<&TrustedPublisher> {
+        match status {
+            SluSignatureStatus::Verified { public_key } => self
+                .trusted_publishers
+                .iter()
+                .find(|p| p.public_key.trim() == public_key.trim()),
+            _ => None,
+        }
+    }
+
+    /// Checks that the file can be installed. Tampered files are always refused.
+    pub fn check(&self, file: &SluResourceFile) -> Result<()> {
+        self.check_status(
+            &file.resource.kind,
+            &file.resource.slug,
+            &file.signature_status,
+        )
+    }
+
+    /// Same as [`Self::check`] for an installed resource, with the signature status of the
+    /// file it was loaded from, so resources copied by hand follow the policy too.
+    pub fn check_status(
+        &self,
+        kind: &ResourceKind,
+        name: impl std::fmt::Display,
+        status: &SluSignatureStatus,
+    ) -> Result<()> {
+        if *status == SluSignatureStatus::Tampered {
+            return Err(format!("Resource {name} was modified after being signed").into());
+        }
+
+        if self.refuse_unsigned_widgets
+            && *kind == ResourceKind::Widget
+            && self.publisher_of(status).is_none()
+        {
+            return Err(format!("Widget {name} is not signed by a trusted publisher").into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::SluSignature;
+
+    fn widget_file() -> SluResourceFile {
+        let document = serde_json::json!({
+            "version": 2,
+            "resource": {
+                "id": "1f7d5f0e-5d1a-4c1e-9d55-8e4b0b1b6f3a",
+                "dataId": "6c2b8a56-3f7e-4a63-a0a4-6f4f4b1bb0d1",
+                "creatorId": "0b0a6a7e-9f8e-4b2e-8a41-3e0c2a1d5c77",
+                "slug": "clock",
+                "kind": "Widget",
+                "metadata": {},
+                "createdAt": "2025-01-01T00:00:00Z",
+                "updatedAt": "2025-01-01T00:00:00Z",
+                "status": "Published",
+                "version": 1,
+                "stars": 0,
+                "downloads": 0,
+            },
+            "data": {},
+        });
+        serde_json::from_value(document).unwrap()
+    }
+
+    #[test]
+    fn test_unsigned_widgets_policy() -> Result<()> {
+        let mut file = widget_file();
+        assert_eq!(file.verify_signature()?, &SluSignatureStatus::Unsigned);
+
+        let mut settings = ResourceTrustSettings::default();
+        assert!(settings.check(&file).is_ok());
+        settings.refuse_unsigned_widgets = true;
+        assert!(settings.check(&file).is_err());
+
+        // only widgets are affected by the policy
+        file.resource.kind = ResourceKind::Theme;
+        assert!(settings.check(&file).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_files_are_refused() -> Result<()> {
+        let mut file = widget_file();
+        file.signature = Some(SluSignature {
+            public_key: "key".to_string(),
+            signature: "forged".to_string(),
+        });
+        assert_eq!(file.verify_signature()?, &SluSignatureStatus::Tampered);
+
+        let settings = ResourceTrustSettings {
+            trusted_publishers: vec![TrustedPublisher {
+                name: "Publisher".to_string(),
+                public_key: "key".to_string(),
+            }],
+            refuse_unsigned_widgets: false,
+        };
+        assert!(settings.publisher_of(&file.signature_status).is_none());
+        assert!(settings.check(&file).is_err());
+        Ok(())
+    }
+}
@@ -19,6 +19,13 @@ pub enum AppExtraFlag {
     /// Unmanage this app in the wm.
     #[serde(alias = "unmanage", alias = "wm-unmanage")]
     WmUnmanage,
+    /// Windows launched from this app (e.g. a terminal) take its place in the wm,
+    /// the app comes back when they are closed.
+    #[serde(alias = "swallow", alias = "wm-swallow")]
+    WmSwallow,
+    /// Never let this app take the place of the app that launched it.
+    #[serde(alias = "no-swallow", alias = "wm-no-swallow")]
+    WmNoSwallow,
     /// Pin this app in all the virtual desktops in the wm.
     #[serde(alias = "pinned", alias = "vd-pinned")]
     VdPinned,
@@ -87,7 +94,32 @@ pub struct AppIdentifierCache {
 }
 
 impl AppIdentifier {
-    fn prepare(&mut self) {
+    /// Creates a single rule identifier, already prepared to be matched.
+    pub fn new(
+        kind: AppIdentifierType,
+        id: impl Into<String>,
+        matching_strategy: MatchingStrategy,
+    ) -> Self {
+        let mut identifier = Self {
+            id: id.into(),
+            kind,
+            matching_strategy,
+            negation: false,
+            and: Vec::new(),
+            or: Vec::new(),
+            cache: AppIdentifierCache::default(),
+        };
+        identifier.prepare();
+        identifier
+    }
+
+    /// Adds a rule that must also match for this identifier to match.
+    pub fn with_and(mut self, other: AppIdentifier) -> Self {
+        self.and.push(other);
+        self
+    }
+
+    pub fn prepare(&mut self) {
         if matches!(self.matching_strategy, MatchingStrategy::Regex) {
             let result = Regex::new(&self.id);
             if let Ok(re) = result {
@@ -109,6 +141,14 @@ impl AppIdentifier {
         self.cache.lower_id.as_deref().unwrap()
     }
 
+    /// Same as [`AppsConfigurationList::search`] but for a single identifier.\
+    /// Safety: will panic if the identifier was not prepared before
+    pub fn is_match(&self, title: &str, class: &str, exe: &str, path: &str) -> bool {
+        let normalized_path = path.to_lowercase().replace("\\", "/");
+        let normalized_exe = exe.to_lowercase();
+        self.validate(title, class, &normalized_exe, &normalized_path)
+    }
+
     /// Safety: will panic if cache was not performed before
     fn validate(&self, title: &str, class: &str, exe: &str, path: &str) -> bool {
         let rule_value = match self.kind {
@@ -166,6 +206,8 @@ pub struct AppConfig {
     pub bound_monitor: Option<usize>,
     /// workspace index that the app should be bound to
     pub bound_workspace: Option<usize>,
+    /// named scratchpad where the windows of the app are sent when opened
+    pub scratchpad: Option<String>,
     /// app identifier
     pub identifier: AppIdentifier,
     /// extra specific options/settings for the app
@@ -277,6 +319,7 @@ mod tests {
             category: None,
             bound_monitor: None,
             bound_workspace: None,
+            scratchpad: None,
             identifier: AppIdentifier {
                 id: "Windows\\SystemApps".to_string(),
                 kind: AppIdentifierType::Path,
@@ -323,6 +366,7 @@ mod tests {
             category: None,
             bound_monitor: None,
             bound_workspace: None,
+            scratchpad: None,
             identifier: AppIdentifier {
                 id: "Windows\\SystemApps".to_string(),
                 kind: AppIdentifierType::Path,
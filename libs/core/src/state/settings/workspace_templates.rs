@@ -0,0 +1,53 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::{by_monitor::WorkspaceConfiguration, twm::TwmReservation, AppIdentifier},
+    system_state::RelaunchArguments,
+};
+
+/// Workspace created on demand with its own configuration, launching and arranging its apps.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceTemplate {
+    /// name of the template, also used as name of the created workspaces
+    pub name: String,
+    /// react-icon icon name
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// wallpaper collection and window manager settings of the created workspaces
+    #[serde(default)]
+    pub config: WorkspaceConfiguration,
+    /// apps launched once the workspace is created
+    #[serde(default)]
+    pub apps: Vec<WorkspaceTemplateApp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceTemplateApp {
+    /// program, path or uri to launch
+    pub program: String,
+    #[serde(default)]
+    pub args: Option<RelaunchArguments>,
+    /// used to recognize the window of the app once launched
+    pub identifier: AppIdentifier,
+    /// Leaf of the layout receiving the window, by its position on the layout starting at 0.\
+    /// If not set, the window is added to the layout as any other window.
+    #[serde(default)]
+    pub node: Option<usize>,
+    /// Where the window is placed on `node`: a side splits it and `Stack` stacks the window on it.\
+    /// `Float` floats the window, no node is needed for it.
+    #[serde(default)]
+    pub side: Option<TwmReservation>,
+}
+
+impl WorkspaceTemplate {
+    pub fn prepare(&mut self) {
+        for app in &mut self.apps {
+            app.identifier.prepare();
+        }
+    }
+}
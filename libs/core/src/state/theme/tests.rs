@@ -12,3 +12,27 @@ async fn test_compatibility_with_older_schemas() -> Result<()> {
         .map_err(|e| format!("v2.3.12: {e}"))?;
     Ok(())
 }
+
+#[test]
+fn test_bundles_are_reproducible() -> Result<()> {
+    let bundle = |widgets: &[&str]| {
+        let mut theme = Theme::default();
+        for widget in widgets {
+            theme
+                .styles
+                .insert((*widget).into(), format!(".{widget} {{}}"));
+        }
+        theme.metadata.internal.path = PathBuf::from("C:/Users/me/themes/mine");
+        theme.metadata.internal.written_at = chrono::Utc::now();
+        theme.to_bundle_yaml()
+    };
+
+    let bundled = bundle(&["@seelen/weg", "@seelen/fancy-toolbar", "@seelen/wm"])?;
+    assert_eq!(
+        bundled,
+        bundle(&["@seelen/wm", "@seelen/weg", "@seelen/fancy-toolbar"])?
+    );
+    assert!(!bundled.contains("writtenAt"));
+    assert!(!bundled.contains("C:/Users/me"));
+    Ok(())
+}
@@ -20,41 +20,42 @@ impl WidgetSettingsDeclarationList {
     /// Checks if there are duplicate keys in the settings declaration.\
     /// Reserved keys like "enabled" and "$instances" are also checked.
     pub fn there_are_duplicates(&self) -> bool {
+        !self.duplicated_keys().is_empty()
+    }
+
+    /// Keys declared more than once, or using a reserved key like "enabled" or "$instances".
+    pub fn duplicated_keys(&self) -> Vec<&str> {
         let mut seen: HashSet<&str> = HashSet::new();
+        let mut duplicated = Vec::new();
 
         // Reserved keys that cannot be used
         seen.insert("enabled");
         seen.insert("$instances");
 
         for definition in &self.0 {
-            if Self::collect_keys_recursive(definition, &mut seen) {
-                return true;
-            }
+            Self::collect_keys_recursive(definition, &mut seen, &mut duplicated);
         }
-        false
+        duplicated
     }
 
     fn collect_keys_recursive<'a>(
         definition: &'a WidgetConfigDefinition,
         seen: &mut HashSet<&'a str>,
-    ) -> bool {
+        duplicated: &mut Vec<&'a str>,
+    ) {
         match definition {
             WidgetConfigDefinition::Group(group) => {
                 for item in &group.group.items {
-                    if Self::collect_keys_recursive(item, seen) {
-                        return true;
-                    }
+                    Self::collect_keys_recursive(item, seen, duplicated);
                 }
             }
             WidgetConfigDefinition::Item(item) => {
                 let key = item.get_key();
-                if seen.contains(key) {
-                    return true;
+                if !seen.insert(key) && !duplicated.contains(&key) {
+                    duplicated.push(key);
                 }
-                seen.insert(key);
             }
         }
-        false
     }
 }
 
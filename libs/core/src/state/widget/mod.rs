@@ -102,8 +102,13 @@ impl SluResource for Widget {
     }
 
     fn validate(&self) -> Result<()> {
-        if self.settings.there_are_duplicates() {
-            return Err("Widget settings declaration have duplicated keys".into());
+        let duplicated = self.settings.duplicated_keys();
+        if !duplicated.is_empty() {
+            return Err(format!(
+                "Widget settings declaration have duplicated or reserved keys: {}",
+                duplicated.join(", ")
+            )
+            .into());
         }
         for plugin in &self.plugins {
             plugin.validate()?
@@ -0,0 +1,214 @@
+use crate::state::{twm::TwmNodeKind, NodeId, TwmRuntimeTree};
+
+/// Min and max share that can be set to a node, so siblings never disappear.
+const SHARE_RANGE: (f32, f32) = (0.05, 0.95);
+
+impl TwmRuntimeTree {
+    fn is_split_container(&self, node_id: NodeId) -> bool {
+        self.nodes
+            .get(&node_id)
+            .is_some_and(|n| matches!(n.kind, TwmNodeKind::Horizontal | TwmNodeKind::Vertical))
+    }
+
+    /// Containers of the subtree of `node_id` (included) or only `node_id` if not `recursive`.
+    fn containers_of(&self, node_id: NodeId, recursive: bool) -> Vec<NodeId> {
+        let mut found = Vec::new();
+        let mut stack = vec![node_id];
+        while let Some(id) = stack.pop() {
+            if !self.is_split_container(id) {
+                continue;
+            }
+            found.push(id);
+            if recursive {
+                stack.extend(self.nodes[&id].children.iter().copied());
+            }
+        }
+        found
+    }
+
+    /// Sets the share (0.0 - 1.0) of `node_id` on the nearest `kind` container where it has
+    /// siblings with windows, the rest of space is given to the siblings keeping their proportions.
+    /// If `node_id` is not a direct child of that container the share is set to its ancestor.
+    ///
+    /// Returns `false` if there is no such container.
+    pub fn set_share(&mut self, node_id: NodeId, kind: TwmNodeKind, share: f32) -> bool {
+        let share = share.clamp(SHARE_RANGE.0, SHARE_RANGE.1);
+
+        let mut current_id = node_id;
+        loop {
+            let Some(parent_id) = self.nodes.get(&current_id).and_then(|n| n.parent) else {
+                return false;
+            };
+            let parent = &self.nodes[&parent_id];
+            let siblings: Vec<NodeId> = parent
+                .children
+                .iter()
+                .copied()
+                .filter(|&c| c != current_id && self.has_any_windows(c))
+                .collect();
+
+            if parent.kind != kind || siblings.is_empty() {
+                current_id = parent_id;
+                continue;
+            }
+
+            let siblings_total: f32 = siblings.iter().map(|id| self.nodes[id].grow_factor).sum();
+            let total = siblings_total + self.nodes[&current_id].grow_factor;
+            let total = if total > 0.0 {
+                total
+            } else {
+                siblings.len() as f32 + 1.0
+            };
+
+            self.nodes.get_mut(&current_id).unwrap().grow_factor = total * share;
+            let remaining = total * (1.0 - share);
+            for sibling_id in &siblings {
+                let sibling = self.nodes.get_mut(sibling_id).unwrap();
+                sibling.grow_factor = if siblings_total > 0.0 {
+                    remaining * sibling.grow_factor / siblings_total
+                } else {
+                    remaining / siblings.len() as f32
+                };
+            }
+            return true;
+        }
+    }
+
+    /// Gives the same size to all the children of the container.
+    pub fn equalize(&mut self, container_id: NodeId, recursive: bool) {
+        for id in self.containers_of(container_id, recursive) {
+            let children = self.nodes[&id].children.clone();
+            for child_id in children {
+                if let Some(child) = self.nodes.get_mut(&child_id) {
+                    child.grow_factor = 1.0;
+                }
+            }
+        }
+    }
+
+    /// Switches the container between `Horizontal` and `Vertical`, rotating it 90 degrees.
+    /// Rotating clockwise a vertical split puts the top child on the right, counter clockwise
+    /// a horizontal split puts the left child on the bottom.
+    pub fn rotate(&mut self, container_id: NodeId, clockwise: bool, recursive: bool) {
+        for id in self.containers_of(container_id, recursive) {
+            let node = self.nodes.get_mut(&id).unwrap();
+            let was_horizontal = node.kind == TwmNodeKind::Horizontal;
+            node.kind = if was_horizontal {
+                TwmNodeKind::Vertical
+            } else {
+                TwmNodeKind::Horizontal
+            };
+            if clockwise != was_horizontal {
+                node.children.reverse();
+            }
+        }
+    }
+
+    /// Mirrors the container, reversing the order of its children.
+    /// Priorities are kept, so new windows still fill the same slots.
+    pub fn flip(&mut self, container_id: NodeId, recursive: bool) {
+        for id in self.containers_of(container_id, recursive) {
+            self.nodes.get_mut(&id).unwrap().children.reverse();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{
+            twm::{TwmPlugin, TwmPluginNode},
+            Border, WindowManagerSettings,
+        },
+        Rect,
+    };
+
+    fn area() -> Rect {
+        Rect {
+            left: 0,
+            top: 0,
+            right: 1000,
+            bottom: 1000,
+        }
+    }
+
+    fn settings() -> WindowManagerSettings {
+        WindowManagerSettings {
+            workspace_gap: 0,
+            workspace_padding: 0,
+            border: Border {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn columns(count: usize) -> TwmRuntimeTree {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Horizontal,
+                children: (0..count)
+                    .map(|idx| TwmPluginNode {
+                        priority: idx as u32 + 1,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+        });
+        for window in 1..=count as isize {
+            tree.add_to_tiled(window);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_set_share() {
+        let mut tree = columns(3);
+        let node_id = tree.node_of_window(&1).unwrap();
+        assert!(tree.set_share(node_id, TwmNodeKind::Horizontal, 0.5));
+
+        let rects = tree.compute_rects(&area(), &settings(), 1.0);
+        assert_eq!(rects[&1].width(), 500);
+        assert_eq!(rects[&2].width(), 250);
+        assert_eq!(rects[&3].width(), 250);
+
+        // there is no vertical container
+        assert!(!tree.set_share(node_id, TwmNodeKind::Vertical, 0.5));
+    }
+
+    #[test]
+    fn test_equalize() {
+        let mut tree = columns(2);
+        let node_id = tree.node_of_window(&1).unwrap();
+        tree.set_share(node_id, TwmNodeKind::Horizontal, 0.8);
+        tree.equalize(tree.root, false);
+
+        let rects = tree.compute_rects(&area(), &settings(), 1.0);
+        assert_eq!(rects[&1].width(), 500);
+        assert_eq!(rects[&2].width(), 500);
+    }
+
+    #[test]
+    fn test_rotate_and_flip() {
+        let mut tree = columns(2);
+        let root = tree.root;
+
+        tree.rotate(root, true, false);
+        assert_eq!(tree.nodes[&root].kind, TwmNodeKind::Vertical);
+        let rects = tree.compute_rects(&area(), &settings(), 1.0);
+        assert_eq!((rects[&1].top, rects[&2].top), (0, 500));
+
+        // clockwise again: top child goes to the right
+        tree.rotate(root, true, false);
+        let rects = tree.compute_rects(&area(), &settings(), 1.0);
+        assert_eq!((rects[&1].left, rects[&2].left), (500, 0));
+
+        tree.flip(root, false);
+        let rects = tree.compute_rects(&area(), &settings(), 1.0);
+        assert_eq!((rects[&1].left, rects[&2].left), (0, 500));
+        assert!(tree.check_invariants().is_ok());
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use crate::{
+    state::{twm::TwmNodeKind, NodeId, TwmRuntimeTree, WindowId, WindowManagerSettings},
+    Rect,
+};
+
+/// Spacing values resolved to physical pixels, shared by every node of a layout pass.
+#[derive(Debug, Clone, Copy)]
+struct LayoutSpacing {
+    gap: i32,
+    border: i32,
+}
+
+impl TwmRuntimeTree {
+    /// Computes the rect of every tiled window in the tree, without touching any OS api.
+    ///
+    /// Mirrors the flex layout used by the window manager webview:
+    /// - `work_area` is shrunk by `workspace_padding` + `workspace_margin`.
+    /// - Horizontal/Vertical containers split their space between the children that
+    ///   have windows, separated by `workspace_gap` and weighted by `grow_factor`.
+    ///   A container with a single non-empty child ignores the grow factor.
+    /// - Scrollable containers place their columns left to right, each one `column_width` times
+    ///   `grow_factor` of the container width, shifted by the `scroll_offset` of the viewport.
+    ///   Columns outside of the viewport get rects outside of the work area.
+    ///   The gap is split between adjacent columns, except on the edges of the viewport.
+    /// - Leaf and Stack nodes hand their rect to their windows (all windows of a stack share it),
+    ///   shrunk by the border size if borders are enabled.
+    ///
+    /// Children are placed in declaration order, pixels left by integer rounding are given
+    /// one by one following the children priority.
+    ///
+    /// `scale_factor` is the monitor dpi scale, settings values are on logical pixels.
+    pub fn compute_rects(
+        &self,
+        work_area: &Rect,
+        settings: &WindowManagerSettings,
+        scale_factor: f64,
+    ) -> HashMap<WindowId, Rect> {
+        let scale = |value: f64| (value * scale_factor).round() as i32;
+
+        let padding = scale(settings.workspace_padding as f64);
+        let margin = &settings.workspace_margin;
+        let area = Rect {
+            left: work_area.left + padding + scale(margin.left as f64),
+            top: work_area.top + padding + scale(margin.top as f64),
+            right: work_area.right - padding - scale(margin.right as f64),
+            bottom: work_area.bottom - padding - scale(margin.bottom as f64),
+        };
+
+        let spacing = LayoutSpacing {
+            gap: scale(settings.workspace_gap as f64),
+            border: if settings.border.enabled {
+                scale(settings.border.width + settings.border.offset)
+            } else {
+                0
+            },
+        };
+
+        let mut rects = HashMap::new();
+        if self.nodes.contains_key(&self.root) {
+            self.compute_node_rects(self.root, area, spacing, &mut rects);
+        }
+        rects
+    }
+
+    fn compute_node_rects(
+        &self,
+        node_id: NodeId,
+        area: Rect,
+        spacing: LayoutSpacing,
+        rects: &mut HashMap<WindowId, Rect>,
+    ) {
+        let Some(node) = self.nodes.get(&node_id) else {
+            return;
+        };
+
+        match node.kind {
+            TwmNodeKind::Leaf | TwmNodeKind::Stack => {
+                let inner = Rect {
+                    left: area.left + spacing.border,
+                    top: area.top + spacing.border,
+                    right: (area.right - spacing.border).max(area.left + spacing.border),
+                    bottom: (area.bottom - spacing.border).max(area.top + spacing.border),
+                };
+                for window in &node.windows {
+                    rects.insert(*window, inner.clone());
+                }
+            }
+            TwmNodeKind::Horizontal | TwmNodeKind::Vertical => {
+                let children: Vec<NodeId> = node
+                    .children
+                    .iter()
+                    .copied()
+                    .filter(|&c| self.nodes.contains_key(&c) && self.has_any_windows(c))
+                    .collect();
+                if children.is_empty() {
+                    return;
+                }
+
+                let is_horizontal = node.kind == TwmNodeKind::Horizontal;
+                let length = if is_horizontal {
+                    area.width()
+                } else {
+                    area.height()
+                };
+                let gaps = spacing.gap * (children.len() as i32 - 1);
+                let available = (length - gaps).max(0);
+
+                let sizes = self.split_by_grow_factor(&children, available);
+
+                let mut cursor = if is_horizontal { area.left } else { area.top };
+                for (child_id, size) in children.into_iter().zip(sizes) {
+                    let child_area = if is_horizontal {
+                        Rect {
+                            left: cursor,
+                            top: area.top,
+                            right: cursor + size,
+                            bottom: area.bottom,
+                        }
+                    } else {
+                        Rect {
+                            left: area.left,
+                            top: cursor,
+                            right: area.right,
+                            bottom: cursor + size,
+                        }
+                    };
+                    cursor += size + spacing.gap;
+                    self.compute_node_rects(child_id, child_area, spacing, rects);
+                }
+            }
+            TwmNodeKind::Scrollable => {
+                let width = area.width() as f32;
+                let offset = node.scroll_offset;
+                let viewport_end = offset + 1.0;
+                let half_gap = spacing.gap / 2;
+
+                let spans = self.column_spans(node_id);
+                let strip_end = spans.last().map_or(0.0, |&(_, _, end)| end);
+                for (child_id, start, end) in spans {
+                    let on_edge = |edge: f32| {
+                        [0.0, offset, viewport_end, strip_end]
+                            .iter()
+                            .any(|e| (edge - e).abs() < 0.001)
+                    };
+                    let mut left = area.left + ((start - offset) * width).round() as i32;
+                    let mut right = area.left + ((end - offset) * width).round() as i32;
+                    if !on_edge(start) {
+                        left += half_gap;
+                    }
+                    if !on_edge(end) {
+                        right -= spacing.gap - half_gap;
+                    }
+
+                    let child_area = Rect {
+                        left,
+                        top: area.top,
+                        right: right.max(left),
+                        bottom: area.bottom,
+                    };
+                    self.compute_node_rects(child_id, child_area, spacing, rects);
+                }
+            }
+        }
+    }
+
+    /// Splits `available` pixels between `children` proportionally to their grow factor.
+    fn split_by_grow_factor(&self, children: &[NodeId], available: i32) -> Vec<i32> {
+        let mut factors: Vec<f64> = if children.len() == 1 {
+            vec![1.0]
+        } else {
+            children
+                .iter()
+                .map(|id| self.nodes[id].grow_factor.max(0.0) as f64)
+                .collect()
+        };
+
+        let mut total: f64 = factors.iter().sum();
+        if total <= 0.0 {
+            factors.iter_mut().for_each(|f| *f = 1.0);
+            total = factors.len() as f64;
+        }
+
+        let mut sizes: Vec<i32> = factors
+            .iter()
+            .map(|f| (available as f64 * f / total).floor() as i32)
+            .collect();
+
+        let mut by_priority: Vec<usize> = (0..children.len()).collect();
+        by_priority.sort_by_key(|&idx| self.nodes[&children[idx]].priority);
+
+        let mut remainder = available - sizes.iter().sum::<i32>();
+        for idx in by_priority.into_iter().cycle() {
+            if remainder <= 0 {
+                break;
+            }
+            sizes[idx] += 1;
+            remainder -= 1;
+        }
+
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{
+        twm::{TwmPlugin, TwmPluginNode},
+        Border,
+    };
+
+    fn settings(gap: u32, padding: u32) -> WindowManagerSettings {
+        WindowManagerSettings {
+            workspace_gap: gap,
+            workspace_padding: padding,
+            border: Border {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+        Rect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    fn tree_of(kind: TwmNodeKind, children: Vec<TwmPluginNode>) -> TwmRuntimeTree {
+        TwmRuntimeTree::from_plugin(&TwmPlugin {
+            structure: Some(TwmPluginNode {
+                kind,
+                children,
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn leaf(grow_factor: f32, priority: u32) -> TwmPluginNode {
+        TwmPluginNode {
+            grow_factor,
+            priority,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_horizontal_split_with_gap_and_padding() {
+        let mut tree = tree_of(TwmNodeKind::Horizontal, vec![leaf(1.0, 1), leaf(1.0, 2)]);
+        tree.add_to_tiled(1);
+        tree.add_to_tiled(2);
+
+        let rects = tree.compute_rects(&rect(0, 0, 1000, 500), &settings(10, 10), 1.0);
+        assert_eq!(rects[&1], rect(10, 10, 495, 490));
+        assert_eq!(rects[&2], rect(505, 10, 990, 490));
+    }
+
+    #[test]
+    fn test_grow_factor_and_empty_siblings() {
+        let mut tree = tree_of(
+            TwmNodeKind::Vertical,
+            vec![leaf(3.0, 1), leaf(1.0, 2), leaf(1.0, 3)],
+        );
+        tree.add_to_tiled(1);
+        tree.add_to_tiled(2);
+
+        // third leaf is empty so it takes no space
+        let rects = tree.compute_rects(&rect(0, 0, 400, 400), &settings(0, 0), 1.0);
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[&1], rect(0, 0, 400, 300));
+        assert_eq!(rects[&2], rect(0, 300, 400, 400));
+    }
+
+    #[test]
+    fn test_single_child_ignores_grow_factor() {
+        let mut tree = tree_of(TwmNodeKind::Horizontal, vec![leaf(0.2, 1), leaf(1.0, 2)]);
+        tree.add_to_tiled(1);
+
+        let rects = tree.compute_rects(&rect(0, 0, 300, 200), &settings(10, 0), 1.0);
+        assert_eq!(rects[&1], rect(0, 0, 300, 200));
+    }
+
+    #[test]
+    fn test_remainder_follows_priority() {
+        let mut tree = tree_of(
+            TwmNodeKind::Horizontal,
+            vec![leaf(1.0, 3), leaf(1.0, 1), leaf(1.0, 2)],
+        );
+        for window in 1..=3 {
+            tree.add_to_tiled(window);
+        }
+
+        // 100px / 3 = 33 with 1px left, given to the highest priority node (second child)
+        let rects = tree.compute_rects(&rect(0, 0, 100, 100), &settings(0, 0), 1.0);
+        let mut widths: Vec<(i32, i32)> = rects.values().map(|r| (r.left, r.width())).collect();
+        widths.sort();
+        assert_eq!(widths, vec![(0, 33), (33, 34), (67, 33)]);
+    }
+
+    #[test]
+    fn test_stack_windows_share_rect_and_scale() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::monocle());
+        tree.add_to_tiled(1);
+        tree.add_to_tiled(2);
+
+        let mut settings = settings(0, 10);
+        settings.border = Border {
+            enabled: true,
+            width: 2.0,
+            offset: 0.0,
+        };
+
+        let rects = tree.compute_rects(&rect(0, 0, 1000, 1000), &settings, 1.5);
+        assert_eq!(rects[&1], rect(18, 18, 982, 982));
+        assert_eq!(rects[&1], rects[&2]);
+    }
+}
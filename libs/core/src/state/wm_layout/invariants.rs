@@ -0,0 +1,445 @@
+use std::collections::HashSet;
+
+use crate::{
+    error::Result,
+    state::{twm::TwmNodeKind, TwmRuntimeTree, WindowLocation},
+};
+
+impl TwmRuntimeTree {
+    /// Verifies the structural consistency of the tree, returning the first broken rule:
+    /// - parent/child links are reciprocal and every node is reachable from the root exactly once.
+    /// - containers have no windows and leaves/stacks have no children.
+    /// - a window is on a single node and `window_map` agrees with the nodes.
+    /// - leaves hold at most one window and stacks at most `max_stack_size`.
+    /// - scrollable nodes are not scrolled past their last column.
+    /// - windows that swallowed another one are on the tree or swallowed themselves.
+    pub fn check_invariants(&self) -> Result<()> {
+        if self.nodes.is_empty() {
+            return self.check_window_map(&HashSet::new());
+        }
+
+        let root = self
+            .nodes
+            .get(&self.root)
+            .ok_or_else(|| format!("root node {} doesn't exist", self.root))?;
+        if root.parent.is_some() {
+            return Err(format!("root node {} has a parent", self.root).into());
+        }
+
+        let mut visited = HashSet::new();
+        let mut tiled = HashSet::new();
+        let mut stack = vec![self.root];
+        while let Some(node_id) = stack.pop() {
+            if !visited.insert(node_id) {
+                return Err(format!("node {node_id} is reachable more than once").into());
+            }
+
+            let node = self
+                .nodes
+                .get(&node_id)
+                .ok_or_else(|| format!("node {node_id} doesn't exist"))?;
+            if node.id != node_id {
+                return Err(format!("node {node_id} is stored with id {}", node.id).into());
+            }
+            if node.id >= self.next_id {
+                return Err(format!("node {node_id} is not below next id {}", self.next_id).into());
+            }
+
+            for child_id in &node.children {
+                let child = self
+                    .nodes
+                    .get(child_id)
+                    .ok_or_else(|| format!("child {child_id} of node {node_id} doesn't exist"))?;
+                if child.parent != Some(node_id) {
+                    return Err(format!(
+                        "child {child_id} of node {node_id} has parent {:?}",
+                        child.parent
+                    )
+                    .into());
+                }
+                stack.push(*child_id);
+            }
+
+            match node.kind {
+                TwmNodeKind::Horizontal | TwmNodeKind::Vertical | TwmNodeKind::Scrollable => {
+                    if !node.windows.is_empty() {
+                        return Err(format!("container {node_id} has windows").into());
+                    }
+                }
+                TwmNodeKind::Leaf | TwmNodeKind::Stack => {
+                    if !node.children.is_empty() {
+                        return Err(format!("node {node_id} has windows and children").into());
+                    }
+                }
+            }
+
+            if node.kind == TwmNodeKind::Leaf && node.windows.len() > 1 {
+                return Err(format!("leaf {node_id} has {} windows", node.windows.len()).into());
+            }
+            if node.kind == TwmNodeKind::Stack {
+                if let Some(max) = node.max_stack_size {
+                    if node.windows.len() > max {
+                        return Err(format!(
+                            "stack {node_id} has {} windows, max is {max}",
+                            node.windows.len()
+                        )
+                        .into());
+                    }
+                }
+            }
+
+            if node.kind == TwmNodeKind::Scrollable {
+                let max = self.max_scroll_offset(node_id);
+                if !(0.0..=max).contains(&node.scroll_offset) {
+                    return Err(format!(
+                        "scrollable {node_id} is scrolled to {}, max is {max}",
+                        node.scroll_offset
+                    )
+                    .into());
+                }
+            }
+
+            if let Some(active) = node.active_window {
+                if !node.windows.contains(&active) {
+                    return Err(format!("active window {active} is not on node {node_id}").into());
+                }
+            }
+
+            for window in &node.windows {
+                if !tiled.insert(*window) {
+                    return Err(format!("window {window} is duplicated").into());
+                }
+                match self.window_map.get(window) {
+                    Some(WindowLocation::Tiled(id, _)) if *id == node_id => {}
+                    location => {
+                        return Err(format!(
+                            "window {window} is on node {node_id} but mapped to {location:?}"
+                        )
+                        .into())
+                    }
+                }
+            }
+        }
+
+        if visited.len() != self.nodes.len() {
+            return Err(format!(
+                "{} nodes are not reachable from the root",
+                self.nodes.len() - visited.len()
+            )
+            .into());
+        }
+
+        self.check_window_map(&tiled)
+    }
+
+    /// every tiled window on `window_map` should be on a node
+    fn check_window_map(&self, tiled: &HashSet<isize>) -> Result<()> {
+        for (window, original) in &self.swallowed {
+            let swallowed = self.swallowed.values().any(|w| w == window);
+            if !self.contains(window) && !swallowed {
+                return Err(
+                    format!("window {window} swallowed {original} but it is not there").into(),
+                );
+            }
+        }
+        for (window, location) in &self.window_map {
+            if let WindowLocation::Tiled(node_id, _) = location {
+                if !tiled.contains(window) {
+                    return Err(format!(
+                        "window {window} is mapped to node {node_id} but it is not there"
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::state::{
+        twm::{TwmPlugin, TwmReservation},
+        NodeId, WindowId,
+    };
+
+    const MAX_WINDOWS: WindowId = 16;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        AddTiled(WindowId),
+        AddFloating(WindowId),
+        Remove(usize),
+        Swap(usize, usize),
+        Split(usize, TwmReservation, WindowId),
+        Stack(usize, WindowId),
+        Reindex,
+        Normalize,
+        ResetSizes,
+        SetShare(usize, f32),
+        Rotate(usize, bool),
+        Flip(usize),
+        ScrollTo(usize),
+        Swallow(usize, WindowId),
+    }
+
+    fn layouts() -> Vec<TwmPlugin> {
+        vec![
+            TwmPlugin::default(),
+            TwmPlugin::monocle(),
+            TwmPlugin::master_stack(2, 0.6),
+            TwmPlugin::dwindle(),
+            TwmPlugin::spiral(),
+            TwmPlugin::centered_master(0.5),
+            TwmPlugin::grid(3),
+            TwmPlugin::scrollable(0.6),
+        ]
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        let window = 1..=MAX_WINDOWS;
+        let side = prop_oneof![
+            Just(TwmReservation::Left),
+            Just(TwmReservation::Right),
+            Just(TwmReservation::Top),
+            Just(TwmReservation::Bottom),
+        ];
+        prop_oneof![
+            4 => window.clone().prop_map(Op::AddTiled),
+            1 => window.clone().prop_map(Op::AddFloating),
+            3 => any::<usize>().prop_map(Op::Remove),
+            2 => (any::<usize>(), any::<usize>()).prop_map(|(a, b)| Op::Swap(a, b)),
+            2 => (any::<usize>(), side, window.clone()).prop_map(|(n, s, w)| Op::Split(n, s, w)),
+            1 => (any::<usize>(), window).prop_map(|(n, w)| Op::Stack(n, w)),
+            1 => Just(Op::Reindex),
+            1 => Just(Op::Normalize),
+            1 => Just(Op::ResetSizes),
+            1 => (any::<usize>(), 0.0f32..1.0).prop_map(|(n, s)| Op::SetShare(n, s)),
+            1 => (any::<usize>(), any::<bool>()).prop_map(|(n, c)| Op::Rotate(n, c)),
+            1 => any::<usize>().prop_map(Op::Flip),
+            1 => any::<usize>().prop_map(Op::ScrollTo),
+            1 => (any::<usize>(), 1..=MAX_WINDOWS).prop_map(|(n, w)| Op::Swallow(n, w)),
+        ]
+    }
+
+    fn pick<T: Copy>(items: &[T], idx: usize) -> Option<T> {
+        if items.is_empty() {
+            return None;
+        }
+        Some(items[idx % items.len()])
+    }
+
+    fn windows_of(tree: &TwmRuntimeTree) -> Vec<WindowId> {
+        let mut windows: Vec<WindowId> = tree.window_map.keys().copied().collect();
+        windows.sort();
+        windows
+    }
+
+    fn node_ids(tree: &TwmRuntimeTree) -> Vec<NodeId> {
+        tree.iter().map(|n| n.id).collect()
+    }
+
+    fn tiled_of(tree: &TwmRuntimeTree) -> Vec<WindowId> {
+        windows_of(tree)
+            .into_iter()
+            .filter(|w| tree.is_tiled(w))
+            .collect()
+    }
+
+    /// Applies the operation the same way the window manager does, skipping invalid ones
+    /// (like adding a window that is already on the tree).
+    fn apply(tree: &mut TwmRuntimeTree, op: &Op) {
+        let add_residuals = |tree: &mut TwmRuntimeTree, residuals: Vec<WindowId>| {
+            for w in residuals {
+                tree.add_to_floating(w);
+            }
+        };
+
+        match *op {
+            Op::AddTiled(window) if !tree.contains(&window) => {
+                let residuals = tree.add_to_tiled(window);
+                add_residuals(tree, residuals);
+            }
+            Op::AddFloating(window) if !tree.contains(&window) => {
+                tree.add_to_floating(window);
+            }
+            Op::Remove(idx) => {
+                if let Some(window) = pick(&windows_of(tree), idx) {
+                    let residuals = tree.remove_window(&window);
+                    add_residuals(tree, residuals);
+                }
+            }
+            Op::Swap(a, b) => {
+                let tiled = tiled_of(tree);
+                if let (Some(a), Some(b)) = (pick(&tiled, a), pick(&tiled, b)) {
+                    tree.swap_nodes_by_windows(a, b);
+                }
+            }
+            Op::Split(idx, side, window) if !tree.contains(&window) => {
+                if let Some(target) = pick(&tiled_of(tree), idx) {
+                    let node_id = tree.node_of_window(&target).unwrap();
+                    if !tree.split_node_for_reservation(node_id, side, window) {
+                        let residuals = tree.add_to_tiled(window);
+                        add_residuals(tree, residuals);
+                    }
+                }
+            }
+            Op::Stack(idx, window) if !tree.contains(&window) => {
+                if let Some(node_id) = pick(&node_ids(tree), idx) {
+                    if !tree.stack_window(node_id, window) {
+                        let residuals = tree.add_to_tiled(window);
+                        add_residuals(tree, residuals);
+                    }
+                }
+            }
+            Op::Reindex => {
+                let residuals = tree.reindex_windows();
+                add_residuals(tree, residuals);
+            }
+            Op::Normalize => tree.normalize(),
+            Op::ResetSizes => tree.reset_sizes(),
+            Op::SetShare(idx, share) => {
+                if let Some(node_id) = pick(&node_ids(tree), idx) {
+                    tree.set_share(node_id, TwmNodeKind::Horizontal, share);
+                }
+            }
+            Op::Rotate(idx, clockwise) => {
+                if let Some(node_id) = pick(&node_ids(tree), idx) {
+                    tree.rotate(node_id, clockwise, true);
+                }
+            }
+            Op::Flip(idx) => {
+                if let Some(node_id) = pick(&node_ids(tree), idx) {
+                    tree.flip(node_id, true);
+                }
+            }
+            Op::ScrollTo(idx) => {
+                if let Some(window) = pick(&tiled_of(tree), idx) {
+                    tree.scroll_to_window(&window);
+                }
+            }
+            Op::Swallow(idx, window) if !tree.contains(&window) => {
+                if let Some(original) = pick(&tiled_of(tree), idx) {
+                    tree.swallow(original, window);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn random_operations_keep_tree_consistent(
+            layout in 0..layouts().len(),
+            ops in prop::collection::vec(op(), 1..64),
+        ) {
+            let mut tree = TwmRuntimeTree::from_plugin(&layouts()[layout]);
+            prop_assert!(tree.check_invariants().is_ok());
+
+            for (step, op) in ops.iter().enumerate() {
+                let before = windows_of(&tree);
+                apply(&mut tree, op);
+
+                if let Err(err) = tree.check_invariants() {
+                    return Err(TestCaseError::fail(format!("step {step} ({op:?}): {err}")));
+                }
+
+                // windows are never lost by the tree operations, only by removing or swallowing them
+                let removed = match op {
+                    Op::Remove(idx) => pick(&before, *idx),
+                    Op::Swallow(_, window) => tree.swallowed_by(window),
+                    _ => None,
+                };
+                let after = windows_of(&tree);
+                let lost: Vec<&WindowId> = before
+                    .iter()
+                    .filter(|w| Some(**w) != removed && !after.contains(w))
+                    .collect();
+                prop_assert!(lost.is_empty(), "step {} ({:?}) lost {:?}", step, op, lost);
+            }
+        }
+    }
+
+    #[test]
+    fn test_detects_broken_links() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::master_stack(1, 0.5));
+        tree.add_to_tiled(1);
+        tree.add_to_tiled(2);
+        assert!(tree.check_invariants().is_ok());
+
+        let node_id = tree.node_of_window(&1).unwrap();
+        tree.nodes.get_mut(&node_id).unwrap().parent = None;
+        assert!(tree.check_invariants().is_err());
+    }
+
+    #[test]
+    fn test_detects_window_map_disagreement() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::master_stack(1, 0.5));
+        tree.add_to_tiled(1);
+        tree.add_to_tiled(2);
+
+        let node_id = tree.node_of_window(&1).unwrap();
+        tree.nodes.get_mut(&node_id).unwrap().windows.clear();
+        assert!(tree.check_invariants().is_err());
+    }
+
+    #[test]
+    fn test_swaps_respect_stack_limits() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::centered_master(0.5));
+        for window in 1..=10 {
+            tree.add_to_tiled(window);
+        }
+
+        let windows = windows_of(&tree);
+        for a in &windows {
+            for b in &windows {
+                let mut swapped = tree.clone();
+                swapped.swap_nodes_by_windows(*a, *b);
+                assert!(swapped.check_invariants().is_ok(), "swap {a} <-> {b}");
+                assert_eq!(windows_of(&swapped), windows);
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_collapses_chains_of_single_child_containers() {
+        use crate::state::twm::{TwmNodeKind, TwmNodeLifetime, TwmPluginNode};
+
+        let container = |kind, children| TwmPluginNode {
+            kind,
+            children,
+            ..Default::default()
+        };
+        let temporal = TwmPluginNode {
+            lifetime: TwmNodeLifetime::Temporal,
+            priority: 2,
+            ..Default::default()
+        };
+        let plugin = TwmPlugin {
+            structure: Some(container(
+                TwmNodeKind::Horizontal,
+                vec![container(
+                    TwmNodeKind::Vertical,
+                    vec![container(
+                        TwmNodeKind::Horizontal,
+                        vec![TwmPluginNode::default(), temporal],
+                    )],
+                )],
+            )),
+        };
+
+        // collapse order depends on the hash map iteration, so try a few trees
+        for _ in 0..32 {
+            let mut tree = TwmRuntimeTree::from_plugin(&plugin);
+            tree.add_to_tiled(1);
+            tree.add_to_tiled(2);
+            tree.remove_window(&2);
+            assert!(tree.check_invariants().is_ok());
+            assert_eq!(tree.nodes.len(), 1);
+            assert!(tree.is_tiled(&1));
+        }
+    }
+}
@@ -1,10 +1,22 @@
+mod arrange;
+mod geometry;
+mod invariants;
+mod placement;
+mod scroll;
+mod snapshot;
+mod swallow;
+mod tabs;
+
+pub use snapshot::*;
+pub use tabs::*;
+
 use std::collections::HashMap;
 
 use crate::{
     state::{
         twm::{
-            TwmCondition, TwmConditionContext, TwmNodeKind, TwmNodeLifetime, TwmPlugin,
-            TwmPluginNode, TwmReservation, TwmStackPolicy,
+            TwmCondition, TwmConditionContext, TwmEnvironment, TwmNodeKind, TwmNodeLifetime,
+            TwmPlugin, TwmPluginNode, TwmReservation, TwmStackPolicy,
         },
         WorkspaceId,
     },
@@ -54,6 +66,11 @@ pub struct TwmRuntimeTree {
     pub nodes: HashMap<NodeId, TwmRuntimeNode>,
     #[serde(skip)]
     pub window_map: HashMap<WindowId, WindowLocation>,
+    #[serde(skip)]
+    pub environment: TwmEnvironment,
+    /// Windows that took the place of another one, as `swallower -> original`.
+    #[serde(skip)]
+    pub swallowed: HashMap<WindowId, WindowId>,
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +86,8 @@ impl TwmRuntimeTree {
             root: 0,
             nodes: HashMap::new(),
             window_map: HashMap::new(),
+            environment: TwmEnvironment::default(),
+            swallowed: HashMap::new(),
         }
     }
 
@@ -89,13 +108,20 @@ impl TwmRuntimeTree {
     pub fn from_plugin(tree: &TwmPlugin) -> Self {
         let mut runtime = Self::new();
         if let Some(root) = &tree.structure {
-            let root_id = runtime.insert_plugin_node(root, None);
+            let root_id = runtime.insert_plugin_node(root, None, None);
             runtime.root = root_id;
         }
         runtime
     }
 
-    fn insert_plugin_node(&mut self, node: &TwmPluginNode, parent: Option<NodeId>) -> NodeId {
+    /// Inserts the node and its children, template children are not inserted but kept as
+    /// the template of the node, see [`TwmNodeTemplate`].
+    fn insert_plugin_node(
+        &mut self,
+        node: &TwmPluginNode,
+        parent: Option<NodeId>,
+        recursion: Option<&TwmPluginNode>,
+    ) -> NodeId {
         let mut runtime_node = TwmRuntimeNode::from_plugin(node);
         runtime_node.parent = parent;
 
@@ -103,16 +129,107 @@ impl TwmRuntimeTree {
         runtime_node.id = id;
         self.nodes.insert(id, runtime_node);
 
-        let child_ids: Vec<NodeId> = node
-            .children
-            .iter()
-            .map(|child| self.insert_plugin_node(child, Some(id)))
-            .collect();
+        let mut child_ids = Vec::new();
+        let mut template = None;
+        for child in &node.children {
+            if child.template {
+                // only the first template is used
+                if template.is_none() {
+                    template = Some((child, child_ids.len()));
+                }
+                continue;
+            }
+            child_ids.push(self.insert_plugin_node(child, Some(id), recursion));
+        }
 
-        self.nodes.get_mut(&id).unwrap().children = child_ids;
+        let runtime_node = self.nodes.get_mut(&id).unwrap();
+        if runtime_node.is_container() {
+            runtime_node.template = template.map(|(child, idx)| {
+                Box::new(TwmNodeTemplate {
+                    node: child.clone(),
+                    before: child_ids.get(idx).copied(),
+                    recursion: recursion.or(child.recursive.then_some(child)).cloned(),
+                })
+            });
+        }
+        runtime_node.children = child_ids;
         id
     }
 
+    /// Creates a new child of the container from its template, placed before the sibling
+    /// declared after the template. All the nodes of the instance are `Temporal`.
+    fn instantiate_template(&mut self, container_id: NodeId) -> Option<NodeId> {
+        let template = self.nodes.get(&container_id)?.template.clone()?;
+        let instance_id = self.insert_plugin_node(
+            &template.node,
+            Some(container_id),
+            template.recursion.as_ref(),
+        );
+
+        let mut pending = vec![instance_id];
+        while let Some(node_id) = pending.pop() {
+            let node = self.nodes.get_mut(&node_id).unwrap();
+            node.lifetime = TwmNodeLifetime::Temporal;
+            pending.extend(node.children.iter().copied());
+        }
+
+        let instance = self.nodes.get_mut(&instance_id).unwrap();
+        instance.is_instance = true;
+        // the innermost template of a recursive chain starts it over inside of its instances
+        if instance.is_container() && instance.template.is_none() {
+            instance.template = template.recursion.as_ref().map(|first| {
+                Box::new(TwmNodeTemplate {
+                    node: first.clone(),
+                    before: None,
+                    recursion: Some(first.clone()),
+                })
+            });
+        }
+
+        let container = self.nodes.get_mut(&container_id).unwrap();
+        let position = template
+            .before
+            .and_then(|before| container.children.iter().position(|c| *c == before))
+            .unwrap_or(container.children.len());
+        container.children.insert(position, instance_id);
+        Some(instance_id)
+    }
+
+    /// Removes the node and all of its descendants, windows on them should be removed first.
+    fn remove_subtree(&mut self, node_id: NodeId) {
+        let Some(node) = self.nodes.remove(&node_id) else {
+            return;
+        };
+        if let Some(parent) = node.parent.and_then(|id| self.nodes.get_mut(&id)) {
+            parent.children.retain(|c| *c != node_id);
+        }
+        for child in node.children {
+            self.remove_subtree(child);
+        }
+    }
+
+    /// Closest template instance containing the node (itself included), `None` for declared nodes.
+    fn instance_of(&self, node_id: NodeId) -> Option<NodeId> {
+        let mut current = Some(node_id);
+        while let Some(node) = current.and_then(|id| self.nodes.get(&id)) {
+            if node.is_instance {
+                return Some(node.id);
+            }
+            current = node.parent;
+        }
+        None
+    }
+
+    fn count_windows(&self, node_id: NodeId) -> usize {
+        let node = &self.nodes[&node_id];
+        node.windows.len()
+            + node
+                .children
+                .iter()
+                .map(|&c| self.count_windows(c))
+                .sum::<usize>()
+    }
+
     pub fn contains(&self, window_id: &WindowId) -> bool {
         self.window_map.contains_key(window_id)
     }
@@ -126,29 +243,51 @@ impl TwmRuntimeTree {
     }
 
     pub fn reset_sizes(&mut self) {
-        for node in self {
+        for node in self.iter_mut() {
             node.rect = None;
             node.grow_factor = node.initial_grow_factor;
         }
+        self.clamp_scroll_offsets();
+    }
+
+    /// Updates the monitor/workspace information used by node conditions.
+    /// Returns residual windows that should be added to floating, if any.
+    pub fn set_environment(&mut self, environment: TwmEnvironment) -> Vec<WindowId> {
+        if self.environment == environment {
+            return Vec::new();
+        }
+        self.environment = environment;
+        self.reindex_windows()
     }
 
     // TODO: consider cached counters if condition eval becomes hot path
     fn get_context(&self) -> TwmConditionContext {
         let mut tiling_windows = 0;
+        let mut floating_windows = 0;
         for window in self.window_map.values() {
-            if let WindowLocation::Tiled(..) = window {
-                tiling_windows += 1;
+            match window {
+                WindowLocation::Tiled(..) => tiling_windows += 1,
+                WindowLocation::Floating => floating_windows += 1,
             }
         }
         TwmConditionContext {
             tiling_windows,
+            floating_windows,
             is_reindexing: false,
+            environment: self.environment.clone(),
         }
     }
 
     /// returns true if the window was added, false in case of overflow
     fn try_add_window(&mut self, window_id: WindowId, ctx: &TwmConditionContext) -> bool {
-        if let Some(node_id) = self.iter().find(|n| n.accepts_windows(ctx)).map(|n| n.id) {
+        // declared nodes first, then instances in creation order (ids only grow), so windows
+        // are placed back in the same order the templates were instantiated for them.
+        if let Some(node_id) = self
+            .iter()
+            .filter(|n| n.accepts_windows(ctx))
+            .min_by_key(|n| self.instance_of(n.id))
+            .map(|n| n.id)
+        {
             let node = self.nodes.get_mut(&node_id).unwrap();
             node.windows.push(window_id);
             node.active_window = Some(window_id);
@@ -174,9 +313,60 @@ impl TwmRuntimeTree {
             return true;
         }
 
+        if self.try_grow(window_id, ctx) {
+            return true;
+        }
+
+        if let Some(node_id) = self
+            .iter()
+            .find(|n| n.accepts_new_columns(ctx))
+            .map(|n| n.id)
+        {
+            self.push_column(node_id, window_id);
+            return true;
+        }
+
         false
     }
 
+    /// Instantiates a template for the window, on the container with less windows so
+    /// containers growing side by side take turns and nested ones grow inwards.
+    fn try_grow(&mut self, window_id: WindowId, ctx: &TwmConditionContext) -> bool {
+        let Some(container_id) = self
+            .iter()
+            .filter(|n| n.accepts_new_instances(ctx))
+            .min_by_key(|n| self.count_windows(n.id))
+            .map(|n| n.id)
+        else {
+            return false;
+        };
+
+        let Some(instance_id) = self.instantiate_template(container_id) else {
+            return false;
+        };
+        let leaf = TwmTreeIter {
+            tree: self,
+            stack: vec![instance_id],
+        }
+        .find(|n| n.accepts_windows(ctx))
+        .map(|n| n.id);
+
+        let Some(node_id) = leaf else {
+            // the template has no room for windows
+            self.remove_subtree(instance_id);
+            return false;
+        };
+
+        let node = self.nodes.get_mut(&node_id).unwrap();
+        node.windows.push(window_id);
+        node.active_window = Some(window_id);
+        self.window_map.insert(
+            window_id,
+            WindowLocation::Tiled(node_id, std::time::SystemTime::now()),
+        );
+        true
+    }
+
     pub fn drain_tiled(&mut self) -> Vec<WindowId> {
         let mut drained = Vec::new();
         for node in self.iter_mut() {
@@ -207,6 +397,9 @@ impl TwmRuntimeTree {
         //   C) Collapse H/V containers with exactly 1 child → parent absorbs it.
         //
         // We repeat until none of the three steps produces a change.
+        // Scrollable nodes are never removed nor collapsed, having no columns is a normal state.
+        // Containers with a template are never collapsed as they keep growing, and the declared
+        // ones are never removed as they create their children on demand.
         loop {
             let mut changed = false;
 
@@ -233,6 +426,7 @@ impl TwmRuntimeTree {
                 .extract_if(|_, n| {
                     matches!(n.kind, TwmNodeKind::Horizontal | TwmNodeKind::Vertical)
                         && n.children.is_empty()
+                        && !n.grows_on_demand()
                 })
                 .map(|(id, _)| id)
                 .collect();
@@ -252,12 +446,18 @@ impl TwmRuntimeTree {
                 .filter(|(_, n)| {
                     matches!(n.kind, TwmNodeKind::Horizontal | TwmNodeKind::Vertical)
                         && n.children.len() == 1
+                        && n.template.is_none()
                 })
                 .map(|(id, n)| (*id, n.children[0]))
                 .collect();
             if !to_collapse.is_empty() {
                 changed = true;
                 for (parent_id, child_id) in to_collapse {
+                    // the parent was absorbed earlier in this pass (chain of single-child containers),
+                    // removing its child now would leave a dangling reference, next pass handles it.
+                    if !self.nodes.contains_key(&parent_id) {
+                        continue;
+                    }
                     let Some(child) = self.nodes.remove(&child_id) else {
                         continue; // already absorbed earlier in this pass
                     };
@@ -273,9 +473,13 @@ impl TwmRuntimeTree {
                         parent.condition = child.condition;
                         parent.max_stack_size = child.max_stack_size;
                         parent.stack_policy = child.stack_policy;
+                        parent.column_width = child.column_width;
+                        parent.scroll_offset = child.scroll_offset;
                         parent.windows = child.windows;
                         parent.active_window = child.active_window;
                         parent.children = child.children;
+                        parent.template = child.template;
+                        parent.is_instance |= child.is_instance;
                         (parent.children.clone(), parent.windows.clone())
                     };
 
@@ -299,6 +503,8 @@ impl TwmRuntimeTree {
                 break;
             }
         }
+
+        self.clamp_scroll_offsets();
     }
 
     /// reindexes windows to handle logical condition like `managed < 4` and returns residual windows
@@ -329,10 +535,9 @@ impl TwmRuntimeTree {
             .collect();
         drained.sort_by_key(|(_, t)| *t);
 
-        let mut ctx = TwmConditionContext {
-            tiling_windows: 0,
-            is_reindexing: true,
-        };
+        let mut ctx = self.get_context();
+        ctx.tiling_windows = 0;
+        ctx.is_reindexing = true;
         let mut overflow = Vec::new();
         for (window, _) in drained {
             if self.try_add_window(window, &ctx) {
@@ -342,6 +547,8 @@ impl TwmRuntimeTree {
             }
         }
 
+        // overflowed windows can leave scrollable nodes with less columns
+        self.clamp_scroll_offsets();
         overflow
     }
 
@@ -359,6 +566,12 @@ impl TwmRuntimeTree {
     }
 
     pub fn remove_window(&mut self, window_id: &WindowId) -> Vec<isize> {
+        if self.release_swallowed(window_id) {
+            return Vec::new();
+        }
+        // a swallowed window closed, the window that took its place just keeps it
+        self.swallowed.retain(|_, original| original != window_id);
+
         let Some(location) = self.window_map.remove(window_id) else {
             return Vec::new();
         };
@@ -411,7 +624,7 @@ impl TwmRuntimeTree {
             TwmNodeKind::Leaf | TwmNodeKind::Stack => {
                 node.active_window.or_else(|| node.windows.first().copied())
             }
-            TwmNodeKind::Horizontal | TwmNodeKind::Vertical => {
+            TwmNodeKind::Horizontal | TwmNodeKind::Vertical | TwmNodeKind::Scrollable => {
                 let mut children = node.children.clone();
                 children.sort_by_key(|id| self.nodes[id].priority);
                 children.iter().find_map(|&c| self.face_of_node(c))
@@ -444,6 +657,26 @@ impl TwmRuntimeTree {
         let na = unsafe { &mut *ptr }.get_mut(&node_a).unwrap();
         let nb = unsafe { &mut *ptr }.get_mut(&node_b).unwrap();
 
+        let fits = |node: &TwmRuntimeNode, windows: usize| {
+            node.max_stack_size.is_none_or(|max| windows <= max)
+        };
+        if !fits(na, nb.windows.len()) || !fits(nb, na.windows.len()) {
+            // the stacks don't fit in each other, so only the two windows are exchanged
+            for (node, old, new) in [(&mut *na, a, b), (&mut *nb, b, a)] {
+                for window in node.windows.iter_mut().filter(|w| **w == old) {
+                    *window = new;
+                }
+                if node.active_window == Some(old) {
+                    node.active_window = Some(new);
+                }
+            }
+            self.window_map
+                .insert(a, WindowLocation::Tiled(node_b, time_b));
+            self.window_map
+                .insert(b, WindowLocation::Tiled(node_a, time_a));
+            return;
+        }
+
         std::mem::swap(&mut na.kind, &mut nb.kind);
         std::mem::swap(&mut na.windows, &mut nb.windows);
         std::mem::swap(&mut na.active_window, &mut nb.active_window);
@@ -473,6 +706,41 @@ impl TwmRuntimeTree {
             .map(|(id, _)| id)
     }
 
+    /// Adds `window_id` to the stack of `node_id`, converting it into a manual stack if it is a leaf.
+    /// Returns `false` if the node doesn't exist, is a container or its stack is full (caller falls back).
+    pub fn stack_window(&mut self, node_id: NodeId, window_id: WindowId) -> bool {
+        let Some(node) = self.nodes.get_mut(&node_id) else {
+            return false;
+        };
+        match node.kind {
+            TwmNodeKind::Leaf => {
+                node.kind = TwmNodeKind::Stack;
+                node.stack_policy = TwmStackPolicy::Manual;
+                // manual stacks are sized by the user
+                node.max_stack_size = None;
+            }
+            TwmNodeKind::Stack => {
+                if node
+                    .max_stack_size
+                    .is_some_and(|max| node.windows.len() >= max)
+                {
+                    return false;
+                }
+            }
+            TwmNodeKind::Horizontal | TwmNodeKind::Vertical | TwmNodeKind::Scrollable => {
+                return false
+            }
+        }
+
+        node.windows.push(window_id);
+        node.active_window = Some(window_id);
+        self.window_map.insert(
+            window_id,
+            WindowLocation::Tiled(node_id, std::time::SystemTime::now()),
+        );
+        true
+    }
+
     /// Splits `node_id` by inserting a new intermediate Horizontal (Left/Right)
     /// or Vertical (Top/Bottom) container, then places `new_window` in a freshly
     /// created sibling Leaf on the requested side.
@@ -511,10 +779,14 @@ impl TwmRuntimeTree {
             condition: None,
             max_stack_size: None,
             stack_policy: TwmStackPolicy::Manual,
+            column_width: 0.5,
             grow_factor: 1.0,
+            scroll_offset: 0.0,
             windows: vec![new_window],
             active_window: Some(new_window),
             rect: None,
+            template: None,
+            is_instance: false,
         };
 
         let children = match side {
@@ -532,10 +804,14 @@ impl TwmRuntimeTree {
             condition: None,
             max_stack_size: None,
             stack_policy: TwmStackPolicy::Manual,
+            column_width: 0.5,
             grow_factor,
+            scroll_offset: 0.0,
             windows: vec![],
             active_window: None,
             rect: None,
+            template: None,
+            is_instance: false,
         };
 
         self.nodes.get_mut(&focused_node_id).unwrap().parent = Some(container_id);
@@ -661,13 +937,34 @@ pub struct TwmRuntimeNode {
     pub condition: Option<TwmCondition>,
     pub max_stack_size: Option<usize>,
     pub stack_policy: TwmStackPolicy,
+    pub column_width: f32,
 
     // Runtime-only
     pub grow_factor: f32,
+    /// Viewport position of scrollable nodes, in widths of the node (1.0 = scrolled one full width).
+    pub scroll_offset: f32,
     pub windows: Vec<WindowId>,
     pub active_window: Option<WindowId>,
     /// Inner rect (DWM visible bounds, no shadow). Must match `Window::inner_rect()` at all callsites.
     pub rect: Option<Rect>,
+    #[serde(skip)]
+    pub template: Option<Box<TwmNodeTemplate>>,
+    /// Whether the node was created from the template of its parent.
+    #[serde(skip)]
+    pub is_instance: bool,
+}
+
+/// Growth rule of a container, made from its template plugin child.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmNodeTemplate {
+    /// Node instantiated each time the layout runs out of room.
+    pub node: TwmPluginNode,
+    /// Child declared after the template, instances are placed before it. `None` places them last.
+    pub before: Option<NodeId>,
+    /// First template of a recursive chain, the innermost instances start the chain over.
+    pub recursion: Option<TwmPluginNode>,
 }
 
 impl TwmRuntimeNode {
@@ -684,12 +981,38 @@ impl TwmRuntimeNode {
             condition: node.condition.clone(),
             max_stack_size: node.max_stack_size,
             stack_policy: node.stack_policy,
+            column_width: node.column_width,
+            scroll_offset: 0.0,
             windows: Vec::new(),
             active_window: None,
             rect: None,
+            template: None,
+            is_instance: false,
         }
     }
 
+    fn is_container(&self) -> bool {
+        matches!(
+            self.kind,
+            TwmNodeKind::Horizontal | TwmNodeKind::Vertical | TwmNodeKind::Scrollable
+        )
+    }
+
+    /// Declared containers with a template, their children come and go with the windows.
+    fn grows_on_demand(&self) -> bool {
+        self.template.is_some() && !self.is_instance
+    }
+
+    fn accepts_new_instances(&self, ctx: &TwmConditionContext) -> bool {
+        let Some(template) = &self.template else {
+            return false;
+        };
+        [&self.condition, &template.node.condition]
+            .into_iter()
+            .flatten()
+            .all(|cond| cond.evaluate(ctx))
+    }
+
     fn accepts_windows(&self, ctx: &TwmConditionContext) -> bool {
         // 1. condition check (DSL rule)
         if let Some(cond) = &self.condition {
@@ -708,11 +1031,19 @@ impl TwmRuntimeNode {
                         None => true, // unlimited stack
                     }
             }
-            // these never accept directly
-            TwmNodeKind::Vertical | TwmNodeKind::Horizontal => false,
+            // these never accept directly, scrollable nodes only open new columns as last resort
+            TwmNodeKind::Vertical | TwmNodeKind::Horizontal | TwmNodeKind::Scrollable => false,
         }
     }
 
+    fn accepts_new_columns(&self, ctx: &TwmConditionContext) -> bool {
+        self.kind == TwmNodeKind::Scrollable
+            && self
+                .condition
+                .as_ref()
+                .is_none_or(|cond| cond.evaluate(ctx))
+    }
+
     fn accepts_windows_on_overflow(&self, ctx: &TwmConditionContext) -> bool {
         match self.kind {
             TwmNodeKind::Stack => {
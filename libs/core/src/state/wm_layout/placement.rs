@@ -0,0 +1,73 @@
+use crate::state::{
+    twm::{TwmNodeKind, TwmReservation},
+    NodeId, TwmRuntimeTree, WindowId, WindowLocation,
+};
+
+impl TwmRuntimeTree {
+    /// Leaf or stack node at the given position of the traversal, starting at 0.
+    pub fn nth_leaf(&self, index: usize) -> Option<NodeId> {
+        self.iter()
+            .filter(|n| matches!(n.kind, TwmNodeKind::Leaf | TwmNodeKind::Stack))
+            .nth(index)
+            .map(|n| n.id)
+    }
+
+    /// Places the window on the leaf at `index` (see [`Self::nth_leaf`]). Empty leaves just
+    /// receive it, otherwise a side splits the leaf and `Stack` or no side stacks the window on it.\
+    /// Returns `false` if the leaf doesn't exist, the window can't be placed there or `side` is
+    /// `Float` (caller falls back).
+    pub fn place_window(
+        &mut self,
+        window_id: WindowId,
+        index: usize,
+        side: Option<TwmReservation>,
+    ) -> bool {
+        if self.contains(&window_id) || side == Some(TwmReservation::Float) {
+            return false;
+        }
+        let Some(node_id) = self.nth_leaf(index) else {
+            return false;
+        };
+
+        let node = self.nodes.get_mut(&node_id).unwrap();
+        if node.windows.is_empty() {
+            node.windows.push(window_id);
+            node.active_window = Some(window_id);
+            self.window_map.insert(
+                window_id,
+                WindowLocation::Tiled(node_id, std::time::SystemTime::now()),
+            );
+            return true;
+        }
+
+        match side {
+            None | Some(TwmReservation::Stack) => self.stack_window(node_id, window_id),
+            Some(side) => self.split_node_for_reservation(node_id, side, window_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::twm::TwmPlugin;
+
+    #[test]
+    fn test_place_window_on_leaf() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::master_stack(2, 0.5));
+        let second = tree.nth_leaf(1).unwrap();
+
+        assert!(tree.place_window(1, 1, None));
+        assert_eq!(tree.node_of_window(&1), Some(second));
+
+        // occupied leaves are split on the given side
+        assert!(tree.place_window(2, 1, Some(TwmReservation::Bottom)));
+        assert_eq!(tree.node_of_window(&1), Some(second));
+        assert_ne!(tree.node_of_window(&2), Some(second));
+        assert!(tree.check_invariants().is_ok());
+
+        assert!(!tree.place_window(3, 100, None));
+        assert!(!tree.place_window(3, 0, Some(TwmReservation::Float)));
+        assert!(!tree.place_window(1, 0, None));
+    }
+}
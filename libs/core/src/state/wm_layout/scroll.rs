@@ -0,0 +1,282 @@
+use std::collections::HashSet;
+
+use crate::state::{
+    twm::{TwmNodeKind, TwmNodeLifetime, TwmPluginNode},
+    NodeId, TwmRuntimeNode, TwmRuntimeTree, WindowId, WindowLocation,
+};
+
+/// Tolerance used to decide if a column is inside the viewport, avoids hiding columns
+/// touching the viewport edge because of float imprecision.
+const VIEWPORT_EPSILON: f32 = 0.001;
+
+impl TwmRuntimeTree {
+    /// Columns of a scrollable node with windows, as `(child, start, end)` in widths of the node.
+    pub(super) fn column_spans(&self, node_id: NodeId) -> Vec<(NodeId, f32, f32)> {
+        let Some(node) = self.nodes.get(&node_id) else {
+            return Vec::new();
+        };
+
+        let mut cursor = 0.0;
+        node.children
+            .iter()
+            .copied()
+            .filter(|&c| self.nodes.contains_key(&c) && self.has_any_windows(c))
+            .map(|child_id| {
+                let width = node.column_width * self.nodes[&child_id].grow_factor.max(0.0);
+                let span = (child_id, cursor, cursor + width);
+                cursor += width;
+                span
+            })
+            .collect()
+    }
+
+    /// Max offset of a scrollable node, so the last column is never scrolled past the right edge.
+    pub(super) fn max_scroll_offset(&self, node_id: NodeId) -> f32 {
+        let end = self
+            .column_spans(node_id)
+            .last()
+            .map_or(0.0, |&(_, _, end)| end);
+        (end - 1.0).max(0.0)
+    }
+
+    /// Opens a new column at the end of the scrollable node with `window_id` on it.
+    pub(super) fn push_column(&mut self, node_id: NodeId, window_id: WindowId) {
+        let priority = self.nodes[&node_id]
+            .children
+            .iter()
+            .filter_map(|c| self.nodes.get(c))
+            .map(|c| c.priority)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let column_id = self.generate_id();
+        let mut column = TwmRuntimeNode::from_plugin(&TwmPluginNode {
+            lifetime: TwmNodeLifetime::Temporal,
+            priority,
+            ..Default::default()
+        });
+        column.id = column_id;
+        column.parent = Some(node_id);
+        column.windows.push(window_id);
+        column.active_window = Some(window_id);
+
+        self.nodes.insert(column_id, column);
+        self.nodes
+            .get_mut(&node_id)
+            .unwrap()
+            .children
+            .push(column_id);
+        self.window_map.insert(
+            window_id,
+            WindowLocation::Tiled(column_id, std::time::SystemTime::now()),
+        );
+    }
+
+    /// Keeps the viewports inside of their strips, needed after columns are closed or shrunk.
+    pub fn clamp_scroll_offsets(&mut self) {
+        let scrollables: Vec<NodeId> = self
+            .nodes
+            .values()
+            .filter(|n| n.kind == TwmNodeKind::Scrollable)
+            .map(|n| n.id)
+            .collect();
+        for node_id in scrollables {
+            let max = self.max_scroll_offset(node_id);
+            let node = self.nodes.get_mut(&node_id).unwrap();
+            node.scroll_offset = node.scroll_offset.clamp(0.0, max);
+        }
+    }
+
+    /// Scrolls every scrollable ancestor of the window the minimum needed to show its column.
+    /// Returns `true` if any viewport moved.
+    pub fn scroll_to_window(&mut self, window_id: &WindowId) -> bool {
+        let Some(mut current_id) = self.node_of_window(window_id) else {
+            return false;
+        };
+
+        let mut changed = false;
+        while let Some(parent_id) = self.nodes.get(&current_id).and_then(|n| n.parent) {
+            if self.nodes[&parent_id].kind == TwmNodeKind::Scrollable {
+                let spans = self.column_spans(parent_id);
+                let max = self.max_scroll_offset(parent_id);
+                if let Some(&(_, start, end)) = spans.iter().find(|(id, ..)| *id == current_id) {
+                    let node = self.nodes.get_mut(&parent_id).unwrap();
+                    let offset = node.scroll_offset;
+                    let wanted = if start < offset || end - start >= 1.0 {
+                        start
+                    } else if end > offset + 1.0 {
+                        end - 1.0
+                    } else {
+                        offset
+                    }
+                    .clamp(0.0, max);
+
+                    if (wanted - offset).abs() > f32::EPSILON {
+                        node.scroll_offset = wanted;
+                        changed = true;
+                    }
+                }
+            }
+            current_id = parent_id;
+        }
+        changed
+    }
+
+    /// Windows on columns that are completely outside of the viewport of their scrollable node.
+    pub fn windows_out_of_viewport(&self) -> HashSet<WindowId> {
+        let mut hidden = HashSet::new();
+        for node in self.nodes.values() {
+            if node.kind != TwmNodeKind::Scrollable {
+                continue;
+            }
+            let viewport = (node.scroll_offset, node.scroll_offset + 1.0);
+            for (column_id, start, end) in self.column_spans(node.id) {
+                if end <= viewport.0 + VIEWPORT_EPSILON || start >= viewport.1 - VIEWPORT_EPSILON {
+                    self.collect_windows(column_id, &mut hidden);
+                }
+            }
+        }
+        hidden
+    }
+
+    fn collect_windows(&self, node_id: NodeId, windows: &mut HashSet<WindowId>) {
+        let Some(node) = self.nodes.get(&node_id) else {
+            return;
+        };
+        windows.extend(node.windows.iter().copied());
+        for &child_id in &node.children {
+            self.collect_windows(child_id, windows);
+        }
+    }
+
+    /// Face window of the next (`forward`) or previous column of the nearest scrollable ancestor
+    /// having one, visible or not. Returns `None` if the window is not inside a scrollable node
+    /// or it is already on the first/last column.
+    pub fn scroll_neighbour(&self, window_id: &WindowId, forward: bool) -> Option<WindowId> {
+        let mut current_id = self.node_of_window(window_id)?;
+        while let Some(parent_id) = self.nodes.get(&current_id).and_then(|n| n.parent) {
+            if self.nodes[&parent_id].kind == TwmNodeKind::Scrollable {
+                let columns: Vec<NodeId> = self
+                    .column_spans(parent_id)
+                    .into_iter()
+                    .map(|(id, ..)| id)
+                    .collect();
+                let neighbour = columns
+                    .iter()
+                    .position(|&id| id == current_id)
+                    .and_then(|idx| match forward {
+                        true => columns.get(idx + 1),
+                        false => idx.checked_sub(1).and_then(|idx| columns.get(idx)),
+                    });
+                if let Some(&neighbour_id) = neighbour {
+                    return self.face_of_node(neighbour_id);
+                }
+            }
+            current_id = parent_id;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::{twm::TwmPlugin, Border, WindowManagerSettings},
+        Rect,
+    };
+
+    fn scrollable(column_width: f32) -> TwmRuntimeTree {
+        TwmRuntimeTree::from_plugin(&TwmPlugin {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Scrollable,
+                column_width,
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn settings(gap: u32) -> WindowManagerSettings {
+        WindowManagerSettings {
+            workspace_gap: gap,
+            workspace_padding: 0,
+            border: Border {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn area() -> Rect {
+        Rect {
+            left: 0,
+            top: 0,
+            right: 1000,
+            bottom: 500,
+        }
+    }
+
+    #[test]
+    fn test_windows_open_new_columns() {
+        let mut tree = scrollable(0.5);
+        for window in 1..=4 {
+            assert!(tree.add_to_tiled(window).is_empty());
+        }
+        assert_eq!(tree.nodes[&tree.root].children.len(), 4);
+        assert!(tree.check_invariants().is_ok());
+
+        let rects = tree.compute_rects(&area(), &settings(0), 1.0);
+        let lefts: Vec<i32> = (1..=4).map(|w| rects[&w].left).collect();
+        assert_eq!(lefts, vec![0, 500, 1000, 1500]);
+        assert_eq!(tree.windows_out_of_viewport(), HashSet::from([3, 4]));
+
+        // closing a window removes its column
+        tree.remove_window(&2);
+        assert_eq!(tree.nodes[&tree.root].children.len(), 3);
+        assert!(tree.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_scroll_to_window() {
+        let mut tree = scrollable(0.5);
+        for window in 1..=4 {
+            tree.add_to_tiled(window);
+        }
+
+        // already visible
+        assert!(!tree.scroll_to_window(&2));
+        // scrolls the minimum needed, window 3 ends on the right edge
+        assert!(tree.scroll_to_window(&3));
+        assert_eq!(tree.nodes[&tree.root].scroll_offset, 0.5);
+        assert_eq!(tree.windows_out_of_viewport(), HashSet::from([1, 4]));
+
+        let rects = tree.compute_rects(&area(), &settings(10), 1.0);
+        assert_eq!((rects[&2].left, rects[&2].right), (0, 495));
+        assert_eq!((rects[&3].left, rects[&3].right), (505, 1000));
+
+        // going back shows the window on the left edge
+        assert!(tree.scroll_to_window(&1));
+        assert_eq!(tree.nodes[&tree.root].scroll_offset, 0.0);
+
+        // the viewport is clamped once the last columns are closed
+        tree.scroll_to_window(&4);
+        tree.remove_window(&4);
+        tree.remove_window(&3);
+        assert_eq!(tree.nodes[&tree.root].scroll_offset, 0.0);
+    }
+
+    #[test]
+    fn test_scroll_neighbour_ignores_viewport() {
+        let mut tree = scrollable(0.75);
+        for window in 1..=3 {
+            tree.add_to_tiled(window);
+        }
+
+        assert_eq!(tree.scroll_neighbour(&1, true), Some(2));
+        assert_eq!(tree.scroll_neighbour(&3, false), Some(2));
+        assert_eq!(tree.scroll_neighbour(&3, true), None);
+        assert_eq!(tree.scroll_neighbour(&1, false), None);
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::Result,
+    resource::PluginId,
+    state::{
+        twm::{TwmCondition, TwmNodeKind, TwmNodeLifetime, TwmStackPolicy},
+        AppIdentifier, AppIdentifierType, MatchingStrategy, NodeId, TwmNodeTemplate,
+        TwmRuntimeNode, TwmRuntimeTree, WindowId, WindowLocation, WorkspaceId,
+    },
+};
+
+/// Saved layouts of the window manager, windows are stored by app identity instead of
+/// by handle so snapshots can be restored after a restart.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmLayoutSnapshot {
+    pub workspaces: HashMap<WorkspaceId, TwmTreeSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmTreeSnapshot {
+    /// Layout plugin the tree was created from, snapshots of another layout should be discarded.
+    pub layout: Option<PluginId>,
+    pub root: NodeId,
+    pub nodes: HashMap<NodeId, TwmSnapshotNode>,
+    pub floating: Vec<TwmWindowIdentity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmSnapshotNode {
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    pub kind: TwmNodeKind,
+    pub lifetime: TwmNodeLifetime,
+    pub priority: u32,
+    pub initial_grow_factor: f32,
+    pub grow_factor: f32,
+    pub condition: Option<TwmCondition>,
+    pub max_stack_size: Option<usize>,
+    pub stack_policy: TwmStackPolicy,
+    #[serde(default)]
+    pub column_width: f32,
+    #[serde(default)]
+    pub scroll_offset: f32,
+    pub windows: Vec<TwmWindowIdentity>,
+    /// Index on `windows` of the active window of the stack
+    pub active_window: Option<usize>,
+    #[serde(default)]
+    pub template: Option<Box<TwmNodeTemplate>>,
+    #[serde(default)]
+    pub is_instance: bool,
+}
+
+/// Stable identity of a window, the identifier matches the executable and class of the app,
+/// the title is only used to choose between several windows of the same app.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmWindowIdentity {
+    pub identifier: AppIdentifier,
+    pub title: String,
+}
+
+/// Information of a live window, used to take and restore snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwmWindowInfo {
+    pub id: WindowId,
+    pub title: String,
+    pub class: String,
+    pub exe: String,
+    pub path: String,
+}
+
+impl TwmWindowIdentity {
+    pub fn from_window(window: &TwmWindowInfo) -> Self {
+        let identifier = AppIdentifier::new(
+            AppIdentifierType::Exe,
+            &window.exe,
+            MatchingStrategy::Equals,
+        )
+        .with_and(AppIdentifier::new(
+            AppIdentifierType::Class,
+            &window.class,
+            MatchingStrategy::Equals,
+        ));
+        Self {
+            identifier,
+            title: window.title.clone(),
+        }
+    }
+
+    fn is_match(&self, window: &TwmWindowInfo) -> bool {
+        self.identifier
+            .is_match(&window.title, &window.class, &window.exe, &window.path)
+    }
+
+    /// Takes the best unclaimed window matching this identity, preferring the same title.
+    fn claim(
+        &self,
+        windows: &[TwmWindowInfo],
+        claimed: &mut HashSet<WindowId>,
+    ) -> Option<WindowId> {
+        let mut candidates = windows
+            .iter()
+            .filter(|w| !claimed.contains(&w.id) && self.is_match(w));
+        let first = candidates.next()?;
+        let best = std::iter::once(first)
+            .chain(candidates)
+            .find(|w| w.title == self.title)
+            .unwrap_or(first);
+        claimed.insert(best.id);
+        Some(best.id)
+    }
+}
+
+impl TwmTreeSnapshot {
+    /// Checks that the snapshot describes a single tree, as files on disk can be edited or corrupted.
+    pub fn is_valid(&self) -> bool {
+        if !self.nodes.contains_key(&self.root) {
+            return self.nodes.is_empty();
+        }
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![(self.root, None)];
+        while let Some((node_id, parent)) = stack.pop() {
+            let Some(node) = self.nodes.get(&node_id) else {
+                return false;
+            };
+            if node.parent != parent || !visited.insert(node_id) {
+                return false;
+            }
+            stack.extend(node.children.iter().map(|&child| (child, Some(node_id))));
+        }
+        visited.len() == self.nodes.len()
+    }
+}
+
+impl TwmRuntimeTree {
+    /// Creates a snapshot of the tree, `resolve` gives the info of the windows,
+    /// windows without info are not included.
+    pub fn snapshot(&self, resolve: impl Fn(WindowId) -> Option<TwmWindowInfo>) -> TwmTreeSnapshot {
+        let identity = |w: &WindowId| resolve(*w).map(|info| TwmWindowIdentity::from_window(&info));
+
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(id, node)| {
+                let mut windows = Vec::new();
+                let mut active_window = None;
+                for window in &node.windows {
+                    if let Some(identity) = identity(window) {
+                        if node.active_window == Some(*window) {
+                            active_window = Some(windows.len());
+                        }
+                        windows.push(identity);
+                    }
+                }
+
+                let snapshot_node = TwmSnapshotNode {
+                    parent: node.parent,
+                    children: node.children.clone(),
+                    kind: node.kind,
+                    lifetime: node.lifetime,
+                    priority: node.priority,
+                    initial_grow_factor: node.initial_grow_factor,
+                    grow_factor: node.grow_factor,
+                    condition: node.condition.clone(),
+                    max_stack_size: node.max_stack_size,
+                    stack_policy: node.stack_policy,
+                    column_width: node.column_width,
+                    scroll_offset: node.scroll_offset,
+                    windows,
+                    active_window,
+                    template: node.template.clone(),
+                    is_instance: node.is_instance,
+                };
+                (*id, snapshot_node)
+            })
+            .collect();
+
+        let mut floating: Vec<WindowId> = self
+            .window_map
+            .iter()
+            .filter(|(_, location)| matches!(location, WindowLocation::Floating))
+            .map(|(id, _)| *id)
+            .collect();
+        floating.sort();
+
+        TwmTreeSnapshot {
+            layout: None,
+            root: self.root,
+            nodes,
+            floating: floating.iter().filter_map(identity).collect(),
+        }
+    }
+
+    /// Recreates the tree of the snapshot, re-slotting `windows` matching the saved identities
+    /// into their previous nodes. Returns the tree and the windows that were not on the snapshot,
+    /// these should be added as usual via [`TwmRuntimeTree::add_to_tiled`].
+    ///
+    /// The snapshot should be validated first via [`TwmTreeSnapshot::is_valid`], the restored
+    /// tree is also checked via [`TwmRuntimeTree::check_invariants`] as the saved nodes could
+    /// have been edited, on error the caller should keep its fresh tree.
+    pub fn from_snapshot(
+        snapshot: &TwmTreeSnapshot,
+        windows: &[TwmWindowInfo],
+    ) -> Result<(Self, Vec<WindowId>)> {
+        let mut tree = Self::new();
+        tree.root = snapshot.root;
+        tree.next_id = snapshot.nodes.keys().max().map_or(1, |max| max + 1);
+
+        for (id, node) in &snapshot.nodes {
+            tree.nodes.insert(
+                *id,
+                TwmRuntimeNode {
+                    id: *id,
+                    parent: node.parent,
+                    children: node.children.clone(),
+                    kind: node.kind,
+                    lifetime: node.lifetime,
+                    priority: node.priority,
+                    initial_grow_factor: node.initial_grow_factor,
+                    condition: node.condition.clone(),
+                    max_stack_size: node.max_stack_size,
+                    stack_policy: node.stack_policy,
+                    column_width: node.column_width,
+                    grow_factor: node.grow_factor,
+                    scroll_offset: node.scroll_offset,
+                    windows: Vec::new(),
+                    active_window: None,
+                    rect: None,
+                    template: node.template.clone(),
+                    is_instance: node.is_instance,
+                },
+            );
+        }
+
+        // deserialized identifiers are not prepared
+        let prepared = |identity: &TwmWindowIdentity| {
+            let mut identity = identity.clone();
+            identity.identifier.prepare();
+            identity
+        };
+
+        let mut claimed = HashSet::new();
+        // restored windows keep the traversal order as insertion time, so later reindexes
+        // don't shuffle them.
+        let mut time = std::time::SystemTime::now();
+        let node_ids: Vec<NodeId> = tree.iter().map(|n| n.id).collect();
+        for node_id in node_ids {
+            let saved = &snapshot.nodes[&node_id];
+            let mut node_windows = Vec::new();
+            let mut active_window = None;
+            for (idx, identity) in saved.windows.iter().enumerate() {
+                let Some(window_id) = prepared(identity).claim(windows, &mut claimed) else {
+                    continue;
+                };
+                if saved.active_window == Some(idx) {
+                    active_window = Some(window_id);
+                }
+                node_windows.push(window_id);
+                tree.window_map
+                    .insert(window_id, WindowLocation::Tiled(node_id, time));
+                time += std::time::Duration::from_nanos(1);
+            }
+
+            let node = tree.nodes.get_mut(&node_id).unwrap();
+            node.active_window = active_window.or_else(|| node_windows.first().copied());
+            node.windows = node_windows;
+        }
+
+        for identity in &snapshot.floating {
+            if let Some(window_id) = prepared(identity).claim(windows, &mut claimed) {
+                tree.add_to_floating(window_id);
+            }
+        }
+
+        tree.normalize();
+        tree.check_invariants()?;
+
+        let remaining = windows
+            .iter()
+            .filter(|w| !claimed.contains(&w.id))
+            .map(|w| w.id)
+            .collect();
+        Ok((tree, remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::twm::{TwmPlugin, TwmPluginNode};
+
+    fn info(id: WindowId, exe: &str, title: &str) -> TwmWindowInfo {
+        TwmWindowInfo {
+            id,
+            title: title.to_string(),
+            class: "Window".to_string(),
+            exe: exe.to_string(),
+            path: format!("C:\\Apps\\{exe}"),
+        }
+    }
+
+    fn two_columns() -> TwmRuntimeTree {
+        TwmRuntimeTree::from_plugin(&TwmPlugin {
+            structure: Some(TwmPluginNode {
+                kind: TwmNodeKind::Horizontal,
+                children: vec![
+                    TwmPluginNode::default(),
+                    TwmPluginNode {
+                        kind: TwmNodeKind::Stack,
+                        priority: 2,
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }),
+        })
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip_with_new_handles() {
+        let before = [
+            info(1, "code.exe", "main.rs"),
+            info(2, "firefox.exe", "Docs"),
+            info(3, "firefox.exe", "Mail"),
+            info(4, "calc.exe", "Calculator"),
+        ];
+
+        let mut tree = two_columns();
+        for window in 1..=3 {
+            tree.add_to_tiled(window);
+        }
+        tree.add_to_floating(4);
+        let left = tree.node_of_window(&1).unwrap();
+        tree.nodes.get_mut(&left).unwrap().grow_factor = 2.5;
+
+        let snapshot = tree.snapshot(|id| before.iter().find(|w| w.id == id).cloned());
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: TwmTreeSnapshot = serde_json::from_str(&json).unwrap();
+        assert!(snapshot.is_valid());
+
+        // after a restart windows have new handles and come in another order
+        let after = [
+            info(30, "firefox.exe", "Mail"),
+            info(40, "calc.exe", "Calculator"),
+            info(10, "code.exe", "main.rs"),
+            info(20, "firefox.exe", "Docs"),
+            info(50, "notepad.exe", "Notes"),
+        ];
+        let (restored, remaining) = TwmRuntimeTree::from_snapshot(&snapshot, &after).unwrap();
+
+        assert_eq!(remaining, vec![50]);
+        assert_eq!(restored.node_of_window(&10), Some(left));
+        assert_eq!(restored.nodes[&left].grow_factor, 2.5);
+        assert!(restored.is_floating(&40));
+
+        let stack = restored.node_of_window(&20).unwrap();
+        assert_eq!(restored.nodes[&stack].windows, vec![20, 30]);
+        assert_eq!(restored.nodes[&stack].active_window, Some(30));
+    }
+
+    #[test]
+    fn test_invalid_snapshot() {
+        let mut tree = two_columns();
+        tree.add_to_tiled(1);
+        let mut snapshot = tree.snapshot(|id| Some(info(id, "app.exe", "")));
+        assert!(snapshot.is_valid());
+
+        let root = snapshot.root;
+        let child = snapshot.nodes[&root].children[0];
+        snapshot.nodes.get_mut(&child).unwrap().children.push(root);
+        assert!(!snapshot.is_valid());
+    }
+
+    #[test]
+    fn test_broken_snapshot_is_not_restored() {
+        let windows = [
+            info(1, "code.exe", "main.rs"),
+            info(2, "code.exe", "lib.rs"),
+        ];
+        let mut tree = two_columns();
+        tree.add_to_tiled(1);
+        tree.add_to_tiled(2);
+        let snapshot = tree.snapshot(|id| windows.iter().find(|w| w.id == id).cloned());
+        assert!(TwmRuntimeTree::from_snapshot(&snapshot, &windows).is_ok());
+
+        let stack = tree.node_of_window(&2).unwrap();
+        let identity = snapshot.nodes[&stack].windows[0].clone();
+
+        // a leaf holding several windows
+        let mut broken = snapshot.clone();
+        let leaf = tree.node_of_window(&1).unwrap();
+        broken.nodes.get_mut(&leaf).unwrap().windows.push(identity);
+        assert!(broken.is_valid());
+        assert!(TwmRuntimeTree::from_snapshot(&broken, &windows).is_err());
+
+        // a stack over its max size
+        let mut broken = snapshot.clone();
+        broken.nodes.get_mut(&stack).unwrap().max_stack_size = Some(0);
+        assert!(TwmRuntimeTree::from_snapshot(&broken, &windows).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::state::{TwmRuntimeTree, WindowId, WindowLocation};
+
+impl TwmRuntimeTree {
+    /// Puts the new window in the place of the tiled `original` (e.g. a terminal), which leaves
+    /// the tree and comes back to the same place once the new window is removed.
+    /// Returns `false` if `original` is not tiled or the new window is already on the tree or
+    /// taking part in another swallow.
+    pub fn swallow(&mut self, original: WindowId, window_id: WindowId) -> bool {
+        let is_swallowing = self.swallowed.contains_key(&window_id)
+            || self.swallowed.values().any(|w| *w == window_id);
+        if self.contains(&window_id) || is_swallowing || !self.replace_window(original, window_id) {
+            return false;
+        }
+
+        if let Some(node_id) = self.node_of_window(&window_id) {
+            self.nodes.get_mut(&node_id).unwrap().active_window = Some(window_id);
+        }
+        self.swallowed.insert(window_id, original);
+        true
+    }
+
+    /// Window that was swallowed by the given one, if any.
+    pub fn swallowed_by(&self, window_id: &WindowId) -> Option<WindowId> {
+        self.swallowed.get(window_id).copied()
+    }
+
+    /// Gives back the place of a swallowing window to the window it swallowed.
+    /// Returns `true` if the original window is on the tree again, the swallowing window
+    /// is not managed anymore in that case.
+    pub(super) fn release_swallowed(&mut self, window_id: &WindowId) -> bool {
+        let Some(original) = self.swallowed.remove(window_id) else {
+            return false;
+        };
+        // the original could be added back by the user while it was swallowed
+        if !self.contains(&original) && self.replace_window(*window_id, original) {
+            return true;
+        }
+
+        // the place can't be given back (e.g. the swallowing window is floating now), so the
+        // windows swallowed down the chain can't come back either
+        let mut hidden = original;
+        while !self.contains(&hidden) {
+            match self.swallowed.remove(&hidden) {
+                Some(next) => hidden = next,
+                None => break,
+            }
+        }
+        false
+    }
+
+    /// Replaces the tiled `old` window by `new` keeping its position on the node.
+    fn replace_window(&mut self, old: WindowId, new: WindowId) -> bool {
+        let Some(WindowLocation::Tiled(node_id, time)) = self.window_map.get(&old).cloned() else {
+            return false;
+        };
+
+        let node = self.nodes.get_mut(&node_id).unwrap();
+        for window in node.windows.iter_mut().filter(|w| **w == old) {
+            *window = new;
+        }
+        if node.active_window == Some(old) {
+            node.active_window = Some(new);
+        }
+
+        self.window_map.remove(&old);
+        self.window_map
+            .insert(new, WindowLocation::Tiled(node_id, time));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{twm::TwmPlugin, TwmRuntimeTree};
+
+    #[test]
+    fn test_swallowed_window_comes_back_in_place() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::master_stack(1, 0.5));
+        for window in 1..=3 {
+            tree.add_to_tiled(window);
+        }
+        let node_id = tree.node_of_window(&2).unwrap();
+
+        assert!(tree.swallow(2, 10));
+        assert!(!tree.contains(&2));
+        assert_eq!(tree.node_of_window(&10), Some(node_id));
+        assert_eq!(tree.swallowed_by(&10), Some(2));
+        assert!(tree.check_invariants().is_ok());
+
+        // swallowing is nested, the innermost window is released first
+        assert!(tree.swallow(10, 11));
+        assert!(tree.remove_window(&11).is_empty());
+        assert_eq!(tree.node_of_window(&10), Some(node_id));
+
+        assert!(tree.remove_window(&10).is_empty());
+        assert_eq!(tree.node_of_window(&2), Some(node_id));
+        assert!(tree.swallowed.is_empty());
+        assert!(tree.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_closing_the_swallowed_window_keeps_the_new_one() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::master_stack(1, 0.5));
+        tree.add_to_tiled(1);
+        tree.add_to_floating(2);
+
+        // only tiled windows can be swallowed
+        assert!(!tree.swallow(2, 10));
+        assert!(!tree.swallow(1, 2));
+
+        assert!(tree.swallow(1, 10));
+        // a hidden window can't swallow again, that would break the chain
+        tree.add_to_tiled(3);
+        assert!(!tree.swallow(3, 1));
+        tree.remove_window(&1);
+        assert!(tree.swallowed.is_empty());
+        assert!(tree.is_tiled(&10));
+    }
+
+    #[test]
+    fn test_floating_swallower_drops_the_chain() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::master_stack(1, 0.5));
+        tree.add_to_tiled(1);
+        assert!(tree.swallow(1, 10));
+        assert!(tree.swallow(10, 11));
+        assert!(tree.swallow(11, 12));
+
+        // 11 is added back as floating, so 10 has no place to come back to
+        tree.add_to_floating(11);
+        tree.remove_window(&12);
+        assert!(tree.check_invariants().is_ok());
+        tree.remove_window(&11);
+        assert!(tree.swallowed.is_empty());
+        assert!(tree.check_invariants().is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::state::{
+    twm::{TwmNodeKind, TwmNodeLifetime, TwmPluginNode, TwmReservation},
+    NodeId, TwmRuntimeNode, TwmRuntimeTree, WindowId, WindowLocation,
+};
+
+/// Tab bar of a stack node, tabs are in the same order as the windows of the stack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmStackTabs {
+    pub node: NodeId,
+    pub tabs: Vec<TwmStackTab>,
+    /// Index on `tabs` of the active window of the stack
+    pub active: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TwmStackTab {
+    pub window: WindowId,
+    pub title: String,
+    /// Path of the icon (custom relaunch icon or the executable)
+    pub icon: Option<String>,
+    pub umid: Option<String>,
+}
+
+impl TwmRuntimeTree {
+    /// Tab bars of the stacks of the tree in traversal order, `describe` gives the info of each window.
+    pub fn stack_tabs(&self, describe: impl Fn(WindowId) -> TwmStackTab) -> Vec<TwmStackTabs> {
+        self.iter()
+            .filter(|n| n.kind == TwmNodeKind::Stack && !n.windows.is_empty())
+            .map(|node| TwmStackTabs {
+                node: node.id,
+                tabs: node.windows.iter().map(|w| describe(*w)).collect(),
+                active: node
+                    .active_window
+                    .and_then(|active| node.windows.iter().position(|w| *w == active)),
+            })
+            .collect()
+    }
+
+    /// Moves the tab of the window one position to the start (`forward` false) or the end of its stack.
+    /// Returns `false` if the window is not on a stack or its tab is already on that edge.
+    pub fn move_stack_tab(&mut self, window_id: &WindowId, forward: bool) -> bool {
+        let Some(node) = self
+            .node_of_window(window_id)
+            .and_then(|id| self.nodes.get_mut(&id))
+        else {
+            return false;
+        };
+        if node.kind != TwmNodeKind::Stack {
+            return false;
+        }
+
+        let Some(idx) = node.windows.iter().position(|w| w == window_id) else {
+            return false;
+        };
+        let target = match forward {
+            true => idx + 1,
+            false => match idx.checked_sub(1) {
+                Some(target) => target,
+                None => return false,
+            },
+        };
+        if target >= node.windows.len() {
+            return false;
+        }
+        node.windows.swap(idx, target);
+        true
+    }
+
+    /// Takes the window out of its stack into a new leaf placed at `side` of the stack,
+    /// as a sibling if the parent container already splits on that axis.
+    /// Returns `false` if the window is not on a stack or it is the only window of the stack.
+    pub fn pull_stack_tab(&mut self, window_id: &WindowId, side: TwmReservation) -> bool {
+        let Some(node_id) = self.node_of_window(window_id) else {
+            return false;
+        };
+        let kind = match side {
+            TwmReservation::Left | TwmReservation::Right => TwmNodeKind::Horizontal,
+            TwmReservation::Top | TwmReservation::Bottom => TwmNodeKind::Vertical,
+            _ => return false,
+        };
+
+        let node = self.nodes.get_mut(&node_id).unwrap();
+        if node.kind != TwmNodeKind::Stack || node.windows.len() < 2 {
+            return false;
+        }
+
+        let idx = node.windows.iter().position(|w| w == window_id).unwrap();
+        node.windows.remove(idx);
+        if node.active_window == Some(*window_id) {
+            node.active_window = node
+                .windows
+                .get(idx)
+                .or_else(|| node.windows.last())
+                .copied();
+        }
+        let parent_id = node.parent;
+        let priority = node.priority;
+        self.window_map.remove(window_id);
+
+        let parent_matches = parent_id.is_some_and(|id| self.nodes[&id].kind == kind);
+        let Some(parent_id) = parent_id.filter(|_| parent_matches) else {
+            return self.split_node_for_reservation(node_id, side, *window_id);
+        };
+
+        let leaf_id = self.generate_id();
+        let mut leaf = TwmRuntimeNode::from_plugin(&TwmPluginNode {
+            lifetime: TwmNodeLifetime::Temporal,
+            priority,
+            ..Default::default()
+        });
+        leaf.id = leaf_id;
+        leaf.parent = Some(parent_id);
+        leaf.windows.push(*window_id);
+        leaf.active_window = Some(*window_id);
+        self.nodes.insert(leaf_id, leaf);
+
+        let parent = self.nodes.get_mut(&parent_id).unwrap();
+        let position = parent.children.iter().position(|c| *c == node_id).unwrap();
+        let position = match side {
+            TwmReservation::Left | TwmReservation::Top => position,
+            _ => position + 1,
+        };
+        parent.children.insert(position, leaf_id);
+
+        self.window_map.insert(
+            *window_id,
+            WindowLocation::Tiled(leaf_id, std::time::SystemTime::now()),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::twm::TwmPlugin;
+
+    fn describe(window: WindowId) -> TwmStackTab {
+        TwmStackTab {
+            window,
+            title: format!("window {window}"),
+            icon: None,
+            umid: None,
+        }
+    }
+
+    fn windows_of_node(tree: &TwmRuntimeTree, window: WindowId) -> Vec<WindowId> {
+        let node_id = tree.node_of_window(&window).unwrap();
+        tree.nodes[&node_id].windows.clone()
+    }
+
+    #[test]
+    fn test_stack_tabs_and_move() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::monocle());
+        for window in 1..=3 {
+            tree.add_to_tiled(window);
+        }
+
+        let stacks = tree.stack_tabs(describe);
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].tabs.len(), 3);
+        assert_eq!(stacks[0].tabs[0].title, "window 1");
+        assert_eq!(stacks[0].active, Some(2));
+
+        assert!(tree.move_stack_tab(&3, false));
+        assert_eq!(windows_of_node(&tree, 1), vec![1, 3, 2]);
+        // the active window is kept, only its index changes
+        assert_eq!(tree.stack_tabs(describe)[0].active, Some(1));
+
+        assert!(!tree.move_stack_tab(&1, false));
+        assert!(tree.move_stack_tab(&1, true));
+        assert_eq!(windows_of_node(&tree, 1), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_pull_stack_tab() {
+        let mut tree = TwmRuntimeTree::from_plugin(&TwmPlugin::monocle());
+        for window in 1..=3 {
+            tree.add_to_tiled(window);
+        }
+
+        // the root stack has no parent, so it is split
+        assert!(tree.pull_stack_tab(&2, TwmReservation::Right));
+        assert_eq!(tree.nodes[&tree.root].kind, TwmNodeKind::Horizontal);
+        assert_eq!(windows_of_node(&tree, 1), vec![1, 3]);
+        assert_eq!(windows_of_node(&tree, 2), vec![2]);
+        assert!(tree.check_invariants().is_ok());
+
+        // the parent already splits horizontally, so the new leaf is a sibling
+        assert!(tree.pull_stack_tab(&1, TwmReservation::Left));
+        let children = &tree.nodes[&tree.root].children;
+        assert_eq!(children.len(), 3);
+        assert_eq!(tree.face_of_node(children[0]), Some(1));
+        assert_eq!(tree.face_of_node(children[2]), Some(2));
+        assert!(tree.check_invariants().is_ok());
+
+        // the last window of a stack can't be pulled
+        assert!(!tree.pull_stack_tab(&3, TwmReservation::Right));
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{DesktopWorkspace, VirtualDesktopMonitor, VirtualDesktops, WorkspaceId};
+
+/// Remembered windows of a previous session waiting to be claimed, per workspace.
+/// The oldest ones are dropped past this limit so apps never opened again don't pile up.
+pub const MAX_PENDING_ASSIGNMENTS: usize = 32;
+
+/// Identity of a window that survives its handle, so it can be recognized after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct WindowIdentity {
+    /// path of the executable of the window
+    pub path: String,
+    /// application user model id of the window, if any
+    pub umid: Option<String>,
+    pub class: String,
+    pub title: String,
+}
+
+impl WindowIdentity {
+    /// `None` if both identities belong to different apps, otherwise how much they look alike.
+    /// Titles only break ties, as most apps change them all the time.
+    pub fn similarity(&self, other: &Self) -> Option<u8> {
+        if !self.path.eq_ignore_ascii_case(&other.path)
+            || self.umid != other.umid
+            || self.class != other.class
+        {
+            return None;
+        }
+        Some(if self.title == other.title { 2 } else { 1 })
+    }
+}
+
+/// A window remembered as part of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct WindowAssignment {
+    /// handle of the window, `None` if the window is from a previous session and
+    /// is waiting for a matching window to appear
+    pub window: Option<isize>,
+    pub identity: WindowIdentity,
+}
+
+impl DesktopWorkspace {
+    /// Assignments from a previous session not claimed yet
+    pub fn pending_assignments(&self) -> impl Iterator<Item = &WindowAssignment> {
+        self.assignments.iter().filter(|a| a.window.is_none())
+    }
+}
+
+impl VirtualDesktopMonitor {
+    /// Best pending assignment matching the identity, as `(similarity, workspace, index)`
+    pub fn find_assignment(&self, identity: &WindowIdentity) -> Option<(u8, WorkspaceId, usize)> {
+        let mut best: Option<(u8, WorkspaceId, usize)> = None;
+        for workspace in self.workspaces.rows().iter().flatten() {
+            for (idx, assignment) in workspace.assignments.iter().enumerate() {
+                if assignment.window.is_some() {
+                    continue;
+                }
+                let Some(score) = assignment.identity.similarity(identity) else {
+                    continue;
+                };
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_score, ..)| score > *best_score)
+                {
+                    best = Some((score, workspace.id.clone(), idx));
+                }
+            }
+        }
+        best
+    }
+
+    /// Claims the pending assignment found by [`Self::find_assignment`] for the window
+    pub fn claim_assignment(&mut self, workspace_id: &WorkspaceId, idx: usize, window: isize) {
+        if let Some(workspace) = self.workspaces.get_by_id_mut(workspace_id) {
+            if let Some(assignment) = workspace.assignments.get_mut(idx) {
+                assignment.window = Some(window);
+            }
+        }
+    }
+}
+
+impl VirtualDesktops {
+    /// Turns the assignments of the windows that no longer exist (or whose handle is now used
+    /// by another app) into pending ones, dropping those windows from the workspaces.
+    pub fn release_assignments<F>(&mut self, is_same_window: F)
+    where
+        F: Fn(isize, &WindowIdentity) -> bool,
+    {
+        for monitor in self.monitors.values_mut() {
+            for workspace in monitor.workspaces.rows_mut().iter_mut().flatten() {
+                let mut released = HashSet::new();
+                for assignment in &mut workspace.assignments {
+                    let Some(window) = assignment.window else {
+                        continue;
+                    };
+                    if !is_same_window(window, &assignment.identity) {
+                        released.insert(window);
+                        assignment.window = None;
+                    }
+                }
+                workspace.windows.retain(|w| !released.contains(w));
+            }
+        }
+    }
+
+    /// Syncs the assignments with the windows currently on each workspace, to be called before
+    /// persisting the state. Windows without known identity keep the one already stored.
+    pub fn refresh_assignments<F>(&mut self, identity_of: F)
+    where
+        F: Fn(isize) -> Option<WindowIdentity>,
+    {
+        let mut known = HashMap::new();
+        for monitor in self.monitors.values() {
+            for workspace in monitor.workspaces.rows().iter().flatten() {
+                for assignment in &workspace.assignments {
+                    if let Some(window) = assignment.window {
+                        known.insert(window, assignment.identity.clone());
+                    }
+                }
+            }
+        }
+
+        for monitor in self.monitors.values_mut() {
+            for workspace in monitor.workspaces.rows_mut().iter_mut().flatten() {
+                // closed or moved windows are forgotten, their new workspace remembers them
+                workspace.assignments.retain(|a| a.window.is_none());
+                for window in &workspace.windows {
+                    let identity = identity_of(*window).or_else(|| known.get(window).cloned());
+                    if let Some(identity) = identity {
+                        workspace.assignments.push(WindowAssignment {
+                            window: Some(*window),
+                            identity,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    pub(super) fn sanitize_assignments(&mut self) {
+        for monitor in self.monitors.values_mut() {
+            for workspace in monitor.workspaces.rows_mut().iter_mut().flatten() {
+                let mut seen = HashSet::new();
+                workspace.assignments.retain(|a| match a.window {
+                    Some(window) => seen.insert(window),
+                    None => true,
+                });
+
+                let pending = workspace.pending_assignments().count();
+                let mut to_drop = pending.saturating_sub(MAX_PENDING_ASSIGNMENTS);
+                workspace.assignments.retain(|a| {
+                    if to_drop > 0 && a.window.is_none() {
+                        to_drop -= 1;
+                        return false;
+                    }
+                    true
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Result, system_state::MonitorId};
+
+    fn identity(path: &str, title: &str) -> WindowIdentity {
+        WindowIdentity {
+            path: path.to_string(),
+            umid: None,
+            class: "Window".to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pending_assignments_are_claimed_by_best_match() -> Result<()> {
+        let mut monitor = VirtualDesktopMonitor::create();
+        let first = monitor.active_workspace_id().clone();
+        let second = monitor.ensure_workspace_at(0, 1, false)?;
+        for (workspace_id, title) in [(&first, "notes.txt"), (&second, "todo.txt")] {
+            let workspace = monitor.workspaces.get_by_id_mut(workspace_id).unwrap();
+            workspace.assignments.push(WindowAssignment {
+                window: None,
+                identity: identity(r"C:\Windows\notepad.exe", title),
+            });
+        }
+
+        let found = monitor.find_assignment(&identity(r"c:\windows\NOTEPAD.EXE", "todo.txt"));
+        let (score, workspace_id, idx) = found.unwrap();
+        assert_eq!((score, &workspace_id), (2, &second));
+
+        monitor.claim_assignment(&workspace_id, idx, 10);
+        let found = monitor.find_assignment(&identity(r"C:\Windows\notepad.exe", "todo.txt"));
+        assert_eq!(found.map(|(_, id, _)| id), Some(first));
+        assert!(monitor
+            .find_assignment(&identity(r"C:\Windows\explorer.exe", "todo.txt"))
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_release_and_refresh_assignments() {
+        let mut monitor = VirtualDesktopMonitor::create();
+        let workspace = monitor.active_workspace_mut();
+        workspace.windows = vec![1, 2];
+        workspace.assignments = vec![
+            WindowAssignment {
+                window: Some(1),
+                identity: identity("a.exe", "a"),
+            },
+            WindowAssignment {
+                window: Some(2),
+                identity: identity("b.exe", "b"),
+            },
+        ];
+
+        let monitor_id = MonitorId::from("monitor");
+        let mut state = VirtualDesktops::default();
+        state.monitors.insert(monitor_id.clone(), monitor);
+        // the handle 2 is now used by another app
+        state.release_assignments(|window, identity| window == 1 && identity.path == "a.exe");
+
+        let workspace = state.monitors[&monitor_id].active_workspace();
+        assert_eq!(workspace.windows, vec![1]);
+        assert_eq!(workspace.pending_assignments().count(), 1);
+
+        state.refresh_assignments(|window| (window == 1).then(|| identity("a.exe", "a - edited")));
+        let workspace = state.monitors[&monitor_id].active_workspace();
+        assert_eq!(workspace.assignments.len(), 2);
+        assert!(workspace
+            .assignments
+            .iter()
+            .any(|a| a.window == Some(1) && a.identity.title == "a - edited"));
+    }
+}
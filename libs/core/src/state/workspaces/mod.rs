@@ -1,9 +1,18 @@
+mod assignments;
+mod navigation;
+
 use std::collections::{HashMap, HashSet};
 
 use uuid::Uuid;
 
 use crate::{error::Result, identifier_impl, resource::WallpaperId, system_state::MonitorId};
 
+pub use assignments::*;
+pub use navigation::*;
+
+/// Rows and columns that can be created on demand, so a typo doesn't create thousands of workspaces.
+pub const MAX_GRID_LENGTH: usize = 32;
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
 #[serde(default, rename_all = "camelCase")]
@@ -13,17 +22,27 @@ pub struct VirtualDesktops {
     pub monitors: HashMap<MonitorId, VirtualDesktopMonitor>,
     /// pinned windows will be not affected by switching workspaces
     pub pinned: Vec<isize>,
+    /// named scratchpads, their windows are hidden and out of any workspace until toggled
+    pub scratchpads: HashMap<String, DesktopWorkspace>,
     /// true if the system is currently switching workspaces
     pub switching: bool,
 }
 
 impl VirtualDesktops {
-    pub fn sanitize(&mut self) {
+    /// `ragged_rows` keeps the rows with their own length instead of padding them
+    pub fn sanitize(&mut self, ragged_rows: bool) {
         let mut seen = HashSet::new();
         self.pinned.retain(|x| seen.insert(*x));
+        for scratchpad in self.scratchpads.values_mut() {
+            scratchpad.windows.retain(|x| seen.insert(*x));
+        }
 
         for monitor in self.monitors.values_mut() {
-            monitor.sanitize();
+            if ragged_rows {
+                monitor.sanitize_ragged();
+            } else {
+                monitor.sanitize();
+            }
             /* for workspace in &mut monitor.workspaces {
                 workspace.windows.retain(|x| seen.insert(*x));
             } */
@@ -33,6 +52,7 @@ impl VirtualDesktops {
                 }
             }
         }
+        self.sanitize_assignments();
     }
 }
 
@@ -55,7 +75,15 @@ impl VirtualDesktopMonitor {
 
     pub fn sanitize(&mut self) {
         self.workspaces.sanitize();
+        self.ensure_active_workspace();
+    }
+
+    pub fn sanitize_ragged(&mut self) {
+        self.workspaces.sanitize_ragged();
+        self.ensure_active_workspace();
+    }
 
+    fn ensure_active_workspace(&mut self) {
         if !self.workspaces.contains(&self.active_workspace) {
             self.active_workspace = self.workspaces.0[0][0].id.clone();
         }
@@ -125,7 +153,10 @@ impl VirtualDesktopMonitor {
     ///   moving all windows from the removed cell to the side row in the same column
     /// - If the removed workspace was current, switches to the side one
     /// - Fails if the grid is already 1x1
+    ///
+    /// Rows left short by the ragged mode are padded first.
     pub fn remove_workspace(&mut self, workspace_id: &WorkspaceId) -> Result<()> {
+        self.workspaces.sanitize();
         if self.workspaces.column_count() <= 1 {
             return self.remove_workspace_row(workspace_id);
         }
@@ -142,6 +173,8 @@ impl VirtualDesktopMonitor {
         for row in &mut self.workspaces.0 {
             let windows = row[col_idx].windows.clone();
             row[col_to_move].windows.extend(windows);
+            let assignments = row[col_idx].assignments.clone();
+            row[col_to_move].assignments.extend(assignments);
 
             if row[col_idx].id == self.active_workspace {
                 new_active = Some(row[col_to_move].id.clone());
@@ -178,6 +211,10 @@ impl VirtualDesktopMonitor {
             self.workspaces.0[row_to_move][col_idx]
                 .windows
                 .extend(windows);
+            let assignments = self.workspaces.0[row_idx][col_idx].assignments.clone();
+            self.workspaces.0[row_to_move][col_idx]
+                .assignments
+                .extend(assignments);
 
             if self.workspaces.0[row_idx][col_idx].id == self.active_workspace {
                 new_active = Some(self.workspaces.0[row_to_move][col_idx].id.clone());
@@ -194,6 +231,34 @@ impl VirtualDesktopMonitor {
         Ok(())
     }
 
+    /// Id of the workspace at the given cell, adding the missing rows and columns to reach it,
+    /// on ragged grids the columns are only added to the target row.
+    /// The active workspace is not changed.
+    pub fn ensure_workspace_at(
+        &mut self,
+        row: usize,
+        col: usize,
+        ragged: bool,
+    ) -> Result<WorkspaceId> {
+        if row >= MAX_GRID_LENGTH || col >= MAX_GRID_LENGTH {
+            return Err(format!(
+                "Workspace grids are limited to {MAX_GRID_LENGTH} rows and columns"
+            )
+            .into());
+        }
+        while self.workspaces.row_count() <= row {
+            self.workspaces.add_row();
+        }
+        while self.workspaces.0[row].len() <= col {
+            if ragged {
+                self.workspaces.0[row].push(DesktopWorkspace::create());
+            } else {
+                self.workspaces.add_column();
+            }
+        }
+        Ok(self.workspaces.0[row][col].id.clone())
+    }
+
     /// Rename a workspace by id
     pub fn rename_workspace(
         &mut self,
@@ -234,8 +299,9 @@ impl WorkspaceGrid {
         self.0.len()
     }
 
+    /// Length of the longest row, all rows have it unless the grid is ragged
     pub fn column_count(&self) -> usize {
-        self.0[0].len()
+        self.0.iter().map(|row| row.len()).max().unwrap_or(0)
     }
 
     /// Ensures the grid has at least one row/column (1x1 minimum) and that
@@ -297,6 +363,16 @@ impl WorkspaceGrid {
         self.0.get(row)?.get(col)
     }
 
+    /// First workspace with the given name, ignoring case
+    pub fn get_by_name(&self, name: &str) -> Option<&DesktopWorkspace> {
+        self.0.iter().flatten().find(|workspace| {
+            workspace
+                .name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        })
+    }
+
     pub fn get_by_window_id(&self, window_id: isize) -> Option<&DesktopWorkspace> {
         for row in &self.0 {
             for workspace in row {
@@ -352,6 +428,9 @@ pub struct DesktopWorkspace {
     pub wallpaper: Option<WallpaperId>,
     #[serde(default)]
     pub windows: Vec<isize>,
+    /// identities of the windows of the workspace, used to bring them back after restarts
+    #[serde(default)]
+    pub assignments: Vec<WindowAssignment>,
 }
 
 impl DesktopWorkspace {
@@ -362,6 +441,7 @@ impl DesktopWorkspace {
             icon: None,
             wallpaper: None,
             windows: Vec::new(),
+            assignments: Vec::new(),
         }
     }
 }
@@ -371,3 +451,71 @@ impl DesktopWorkspace {
 pub struct WorkspaceId(pub String);
 
 identifier_impl!(WorkspaceId, String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_workspace_at_grows_the_grid() -> Result<()> {
+        let mut monitor = VirtualDesktopMonitor::create();
+        let active = monitor.active_workspace_id().clone();
+
+        let id = monitor.ensure_workspace_at(1, 2, false)?;
+        assert_eq!(monitor.workspaces.row_count(), 2);
+        assert_eq!(monitor.workspaces.column_count(), 3);
+        assert_eq!(monitor.workspaces.position(&id), Some((1, 2)));
+        assert_eq!(monitor.active_workspace_id(), &active);
+
+        // existing cells are reused
+        assert_eq!(monitor.ensure_workspace_at(1, 2, false)?, id);
+        assert!(monitor
+            .ensure_workspace_at(MAX_GRID_LENGTH, 0, false)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensure_workspace_at_keeps_ragged_rows() -> Result<()> {
+        let mut monitor = VirtualDesktopMonitor::create();
+        let id = monitor.ensure_workspace_at(1, 2, true)?;
+        assert_eq!(monitor.workspaces.position(&id), Some((1, 2)));
+        assert_eq!(monitor.workspaces.0[0].len(), 1);
+        assert_eq!(monitor.workspaces.0[1].len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_workspace_pads_ragged_rows() -> Result<()> {
+        // rows of 3 and 1 workspaces, left by the ragged mode
+        let mut monitor = VirtualDesktopMonitor::create();
+        let first = monitor.active_workspace_id().clone();
+        monitor.add_workspace_row();
+        monitor.set_active_workspace(&first)?;
+        monitor.add_workspace_to_row();
+        let last = monitor.add_workspace_to_row();
+        monitor.active_workspace_mut().windows.push(1);
+
+        monitor.remove_workspace(&last)?;
+        assert!(monitor.workspaces.rows().iter().all(|row| row.len() == 2));
+        assert_eq!(monitor.workspaces.row_count(), 2);
+        assert_eq!(monitor.workspaces.get(0, 1).unwrap().windows, vec![1]);
+        assert_eq!(
+            monitor.active_workspace_id(),
+            &monitor.workspaces.0[0][1].id
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_by_name_ignores_case() -> Result<()> {
+        let mut monitor = VirtualDesktopMonitor::create();
+        let id = monitor.ensure_workspace_at(0, 1, false)?;
+        monitor.rename_workspace(&id, Some("Code".to_string()))?;
+
+        let found = monitor.workspaces.get_by_name("code").map(|w| &w.id);
+        assert_eq!(found, Some(&id));
+        assert!(monitor.workspaces.get_by_name("web").is_none());
+        Ok(())
+    }
+}
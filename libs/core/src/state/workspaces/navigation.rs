@@ -0,0 +1,317 @@
+use super::{DesktopWorkspace, VirtualDesktopMonitor, WorkspaceGrid, WorkspaceId};
+use crate::error::Result;
+
+/// What happens when moving past the edge of the workspace grid.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), ts(repr(enum = name)))]
+pub enum GridEdgePolicy {
+    /// Nothing happens.
+    #[default]
+    Stop,
+    /// Continues from the other side of the row or column.
+    Wrap,
+    /// Creates a new workspace on that side, only for right and down.
+    Create,
+    /// Goes to the active workspace of the next monitor on that side.
+    NextMonitor,
+}
+
+/// Order of the workspaces when switching by index.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), derive(ts_rs::TS))]
+#[cfg_attr(all(feature = "gen-binds", not(feature = "salvo")), ts(repr(enum = name)))]
+pub enum OverviewOrder {
+    /// Only the workspaces on the active row.
+    #[default]
+    ActiveRow,
+    /// The whole grid, row by row.
+    RowMajor,
+    /// The whole grid, column by column.
+    ColumnMajor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridNeighbor {
+    Workspace(WorkspaceId),
+    /// The edge was reached and there is nothing to do.
+    Edge,
+    /// The edge was reached and the next monitor on that side should be used.
+    NextMonitor,
+}
+
+impl WorkspaceGrid {
+    /// Whether the rows have different lengths
+    pub fn is_ragged(&self) -> bool {
+        self.0.iter().any(|row| row.len() != self.0[0].len())
+    }
+
+    /// Like [`Self::sanitize`] but keeping the rows with their own length
+    pub fn sanitize_ragged(&mut self) {
+        self.0.retain(|row| !row.is_empty());
+        if self.0.is_empty() {
+            self.0.push(vec![DesktopWorkspace::create()]);
+        }
+    }
+
+    /// Workspaces flattened in the order used by index based switching
+    pub fn overview(&self, order: OverviewOrder, active_row: usize) -> Vec<&DesktopWorkspace> {
+        match order {
+            OverviewOrder::ActiveRow => self
+                .0
+                .get(active_row)
+                .map(|row| row.iter().collect())
+                .unwrap_or_default(),
+            OverviewOrder::RowMajor => self.0.iter().flatten().collect(),
+            OverviewOrder::ColumnMajor => (0..self.column_count())
+                .flat_map(|col| self.0.iter().filter_map(move |row| row.get(col)))
+                .collect(),
+        }
+    }
+}
+
+impl VirtualDesktopMonitor {
+    fn active_position(&self) -> (usize, usize) {
+        self.workspaces
+            .position(self.active_workspace_id())
+            .expect("Active workspace doesn't exist in the grid")
+    }
+
+    /// Id of the workspace at the index of the overview
+    pub fn workspace_at_index(&self, index: usize, order: OverviewOrder) -> Option<WorkspaceId> {
+        let (active_row, _) = self.active_position();
+        self.workspaces
+            .overview(order, active_row)
+            .get(index)
+            .map(|w| w.id.clone())
+    }
+
+    /// Workspace next to the active one in the given direction. Moving to a shorter row lands
+    /// on its last workspace.
+    ///
+    /// With [`GridEdgePolicy::Create`] the missing workspace is added, on ragged grids only to
+    /// the active row.
+    pub fn neighbor(
+        &mut self,
+        direction: GridDirection,
+        policy: GridEdgePolicy,
+        ragged: bool,
+    ) -> GridNeighbor {
+        let (row, col) = self.active_position();
+        let rows = self.workspaces.row_count();
+        let row_len = |grid: &WorkspaceGrid, row: usize| grid.0[row].len();
+
+        let target = match direction {
+            GridDirection::Left if col > 0 => Some((row, col - 1)),
+            GridDirection::Right if col + 1 < row_len(&self.workspaces, row) => {
+                Some((row, col + 1))
+            }
+            GridDirection::Up if row > 0 => Some((row - 1, col)),
+            GridDirection::Down if row + 1 < rows => Some((row + 1, col)),
+            _ => None,
+        };
+
+        let (row, col) = match (target, policy) {
+            (Some(target), _) => target,
+            (None, GridEdgePolicy::Stop) => return GridNeighbor::Edge,
+            (None, GridEdgePolicy::NextMonitor) => return GridNeighbor::NextMonitor,
+            (None, GridEdgePolicy::Wrap) => match direction {
+                GridDirection::Left => (row, row_len(&self.workspaces, row) - 1),
+                GridDirection::Right => (row, 0),
+                GridDirection::Up => (rows - 1, col),
+                GridDirection::Down => (0, col),
+            },
+            (None, GridEdgePolicy::Create) => match direction {
+                GridDirection::Right if ragged => {
+                    self.workspaces.0[row].push(DesktopWorkspace::create());
+                    (row, col + 1)
+                }
+                GridDirection::Right => {
+                    self.workspaces.add_column();
+                    (row, col + 1)
+                }
+                GridDirection::Down => {
+                    self.workspaces.add_row();
+                    (row + 1, col)
+                }
+                GridDirection::Left | GridDirection::Up => return GridNeighbor::Edge,
+            },
+        };
+
+        let col = col.min(row_len(&self.workspaces, row) - 1);
+        let id = self.workspaces.0[row][col].id.clone();
+        if &id == self.active_workspace_id() {
+            return GridNeighbor::Edge;
+        }
+        GridNeighbor::Workspace(id)
+    }
+
+    /// Add a new workspace at the end of the active row only and return its id,
+    /// the ragged version of [`Self::add_workspace_column`]
+    pub fn add_workspace_to_row(&mut self) -> WorkspaceId {
+        let (row, _) = self.active_position();
+        let workspace = DesktopWorkspace::create();
+        let workspace_id = workspace.id.clone();
+        self.workspaces.0[row].push(workspace);
+        self.active_workspace = workspace_id.clone();
+        workspace_id
+    }
+
+    /// Remove only the cell of the workspace, the ragged version of [`Self::remove_workspace`].
+    /// Windows go to the side workspace in the same row, or to the row above (below for the
+    /// first row) if it was the last one of its row.
+    pub fn remove_workspace_cell(&mut self, workspace_id: &WorkspaceId) -> Result<()> {
+        if self.workspaces.rows().iter().flatten().count() <= 1 {
+            return Err("Cannot remove the last workspace".into());
+        }
+
+        let (row_idx, col_idx) = self
+            .workspaces
+            .position(workspace_id)
+            .ok_or("Workspace not found")?;
+
+        let removed = self.workspaces.0[row_idx].remove(col_idx);
+        let (heir_row, heir_col) = if !self.workspaces.0[row_idx].is_empty() {
+            (row_idx, col_idx.saturating_sub(1))
+        } else {
+            let row = if row_idx == 0 { 1 } else { row_idx - 1 };
+            (row, col_idx.min(self.workspaces.0[row].len() - 1))
+        };
+
+        let heir = &mut self.workspaces.0[heir_row][heir_col];
+        heir.windows.extend(removed.windows);
+        heir.assignments.extend(removed.assignments);
+        if removed.id == self.active_workspace {
+            self.active_workspace = heir.id.clone();
+        }
+
+        self.workspaces.sanitize_ragged();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2 rows, the first with 3 workspaces and the second with 1
+    fn ragged_monitor() -> VirtualDesktopMonitor {
+        let mut monitor = VirtualDesktopMonitor::create();
+        monitor.add_workspace_to_row();
+        monitor.add_workspace_to_row();
+        monitor.workspaces.0.push(vec![DesktopWorkspace::create()]);
+        let first = monitor.workspaces.0[0][0].id.clone();
+        monitor.set_active_workspace(&first).unwrap();
+        monitor
+    }
+
+    fn id_at(monitor: &VirtualDesktopMonitor, row: usize, col: usize) -> WorkspaceId {
+        monitor.workspaces.0[row][col].id.clone()
+    }
+
+    #[test]
+    fn test_edge_policies() {
+        let mut monitor = ragged_monitor();
+        assert!(monitor.workspaces.is_ragged());
+
+        let left = GridDirection::Left;
+        assert_eq!(
+            monitor.neighbor(left, GridEdgePolicy::Stop, true),
+            GridNeighbor::Edge
+        );
+        assert_eq!(
+            monitor.neighbor(left, GridEdgePolicy::NextMonitor, true),
+            GridNeighbor::NextMonitor
+        );
+        assert_eq!(
+            monitor.neighbor(left, GridEdgePolicy::Wrap, true),
+            GridNeighbor::Workspace(id_at(&monitor, 0, 2))
+        );
+        assert_eq!(
+            monitor.neighbor(GridDirection::Up, GridEdgePolicy::Wrap, true),
+            GridNeighbor::Workspace(id_at(&monitor, 1, 0))
+        );
+
+        // moving down from the last column lands on the last workspace of the shorter row
+        monitor
+            .set_active_workspace(&id_at(&monitor, 0, 2))
+            .unwrap();
+        assert_eq!(
+            monitor.neighbor(GridDirection::Down, GridEdgePolicy::Stop, true),
+            GridNeighbor::Workspace(id_at(&monitor, 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_create_policy_grows_only_the_active_row_on_ragged_grids() {
+        let mut monitor = ragged_monitor();
+        let last = id_at(&monitor, 0, 2);
+        monitor.set_active_workspace(&last).unwrap();
+
+        let created = monitor.neighbor(GridDirection::Right, GridEdgePolicy::Create, true);
+        assert_eq!(created, GridNeighbor::Workspace(id_at(&monitor, 0, 3)));
+        assert_eq!(monitor.workspaces.0[1].len(), 1);
+        assert_eq!(monitor.active_workspace_id(), &last);
+
+        // on regular grids a whole column is added
+        monitor
+            .set_active_workspace(&id_at(&monitor, 0, 3))
+            .unwrap();
+        let created = monitor.neighbor(GridDirection::Right, GridEdgePolicy::Create, false);
+        assert_eq!(created, GridNeighbor::Workspace(id_at(&monitor, 0, 4)));
+        assert_eq!(monitor.workspaces.0[1].len(), 2);
+    }
+
+    #[test]
+    fn test_overview_orders() {
+        let monitor = ragged_monitor();
+        let ids = |order| {
+            monitor
+                .workspaces
+                .overview(order, 1)
+                .into_iter()
+                .map(|w| monitor.workspaces.position(&w.id).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(OverviewOrder::ActiveRow), vec![(1, 0)]);
+        assert_eq!(
+            ids(OverviewOrder::RowMajor),
+            vec![(0, 0), (0, 1), (0, 2), (1, 0)]
+        );
+        assert_eq!(
+            ids(OverviewOrder::ColumnMajor),
+            vec![(0, 0), (1, 0), (0, 1), (0, 2)]
+        );
+        assert_eq!(
+            monitor.workspace_at_index(3, OverviewOrder::RowMajor),
+            Some(id_at(&monitor, 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_remove_workspace_cell() -> Result<()> {
+        let mut monitor = ragged_monitor();
+        let lonely = id_at(&monitor, 1, 0);
+        monitor.workspaces.0[1][0].windows.push(7);
+        monitor.set_active_workspace(&lonely)?;
+
+        monitor.remove_workspace_cell(&lonely)?;
+        assert_eq!(monitor.workspaces.row_count(), 1);
+        assert_eq!(monitor.active_workspace_id(), &id_at(&monitor, 0, 0));
+        assert_eq!(monitor.active_workspace().windows, vec![7]);
+
+        let middle = id_at(&monitor, 0, 1);
+        monitor.remove_workspace_cell(&middle)?;
+        assert_eq!(monitor.workspaces.0[0].len(), 2);
+        Ok(())
+    }
+}
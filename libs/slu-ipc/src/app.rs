@@ -1,58 +1,64 @@
-use std::{future::Future, sync::Arc};
+use std::{
+    future::Future,
+    sync::{Arc, LazyLock},
+};
 
-use interprocess::os::windows::named_pipe::{
-    DuplexPipeStream, PipeListenerOptions, pipe_mode::Bytes,
-    tokio::DuplexPipeStream as AsyncDuplexPipeStream,
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWrite, BufReader},
+    sync::broadcast::{self, error::RecvError},
 };
-use windows::Win32::System::RemoteDesktop::{ProcessIdToSessionId, WTSGetActiveConsoleSessionId};
 
 use crate::{
-    common::{
-        IPC, create_security_descriptor, read_from_ipc_stream, send_to_ipc_stream,
-        send_to_ipc_stream_blocking, write_to_ipc_stream,
-    },
+    common::{send_over_transport, write_line_to_ipc_stream},
     error::Result,
     messages::{AppMessage, IpcResponse},
+    protocol::{Connection, Frame, read_request, write_frame},
+    transport::{IpcListener, IpcStream, IpcTransport},
 };
 
-pub struct AppIpc {
-    _priv: (),
+/// Events waiting to be streamed to the subscribed clients.
+const EVENTS_CAPACITY: usize = 256;
+
+static EVENTS: LazyLock<broadcast::Sender<StreamedEvent>> =
+    LazyLock::new(|| broadcast::channel(EVENTS_CAPACITY).0);
+
+#[derive(Clone)]
+struct StreamedEvent {
+    name: Arc<str>,
+    /// serialized `SeelenEventPayload`
+    line: Arc<[u8]>,
 }
 
-impl IPC for AppIpc {
-    fn path() -> String {
-        let session_id = current_session_id().unwrap_or(0);
-        Self::path_with_session(session_id)
-    }
+pub struct AppIpc {
+    _priv: (),
 }
 
 impl AppIpc {
-    /// Constructs the pipe path for a specific session ID
-    pub fn path_with_session(session_id: u32) -> String {
-        format!(r"\\.\pipe\seelen-ui-{}", session_id)
-    }
-
-    pub fn start<R, F>(cb: F) -> Result<()>
+    /// Serves the app protocol over the given transport
+    pub fn serve<T, R, F>(transport: &T, cb: F) -> Result<()>
     where
-        R: Future<Output = IpcResponse> + Send + Sync,
+        T: IpcTransport,
+        R: Future<Output = IpcResponse> + Send + Sync + 'static,
         F: Fn(AppMessage) -> R + Send + Sync + 'static,
     {
-        let sd = create_security_descriptor()?;
-
-        let listener = PipeListenerOptions::new()
-            .path(Self::path())
-            .security_descriptor(Some(sd))
-            .create_tokio_duplex::<Bytes>()?;
+        let mut listener = transport.listen()?;
 
         tokio::spawn(async move {
             let callback = Arc::new(cb);
-            while let Ok(stream) = listener.accept().await {
+            while let Ok(mut stream) = listener.accept().await {
                 let callback = callback.clone();
                 tokio::spawn(async move {
-                    if let Err(err) = Self::process_connection(&stream, callback).await
-                        && let Err(send_err) =
-                            Self::response_to_client(&stream, IpcResponse::Err(err.to_string()))
-                                .await
+                    // legacy framing until the client is read
+                    let mut reply_to = Frame::legacy(Vec::new());
+                    if let Err(err) =
+                        Self::process_connection(&mut stream, &mut reply_to, callback).await
+                        && let Err(send_err) = Self::response_to_client(
+                            &mut stream,
+                            &reply_to,
+                            IpcResponse::Err(err.to_string()),
+                        )
+                        .await
                     {
                         log::error!(
                             "Failed to send error response: {send_err} || Original error: {err}"
@@ -64,63 +70,325 @@ impl AppIpc {
         Ok(())
     }
 
-    async fn process_connection<R, F>(
-        stream: &AsyncDuplexPipeStream<Bytes>,
+    async fn process_connection<S, R, F>(
+        stream: &mut S,
+        reply_to: &mut Frame,
         cb: Arc<F>,
     ) -> Result<()>
     where
+        S: IpcStream,
         R: Future<Output = IpcResponse> + Send + Sync,
         F: Fn(AppMessage) -> R + Send + Sync + 'static,
     {
-        let data = read_from_ipc_stream(stream).await?;
-        if data.is_empty() {
-            return Self::response_to_client(stream, IpcResponse::Success).await;
+        let mut stream = BufReader::new(stream);
+        let request = read_request(&mut stream, reply_to).await?;
+        if request.payload.is_empty() {
+            return Self::response_to_client(&mut stream, &request, IpcResponse::Success).await;
         }
 
-        let message = AppMessage::from_bytes(&data)?;
+        let message = match AppMessage::from_bytes(&request.payload) {
+            Ok(message) => message,
+            Err(err) => {
+                let res = IpcResponse::Err(err.to_string());
+                return Self::response_to_client(&mut stream, &request, res).await;
+            }
+        };
         log::trace!("IPC command received: {message:?}");
-        Self::response_to_client(stream, cb(message).await).await?;
+        if let AppMessage::Subscribe { topics } = message {
+            return Self::stream_events(&mut stream, &request, topics).await;
+        }
+        let res = cb(message).await;
+        Self::response_to_client(&mut stream, &request, res).await
+    }
+
+    async fn stream_events<S: AsyncWrite + Unpin>(
+        stream: &mut S,
+        request: &Frame,
+        topics: Vec<String>,
+    ) -> Result<()> {
+        // subscribe before the acknowledgement so no event is lost in between
+        let mut events = EVENTS.subscribe();
+        Self::response_to_client(stream, request, IpcResponse::Success).await?;
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("IPC subscriber is too slow, {skipped} events were skipped");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            if !topics_match(&topics, &event.name) {
+                continue;
+            }
+            // the client closed the connection
+            if write_line_to_ipc_stream(&mut *stream, &event.line)
+                .await
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Streams the event to the subscribed clients, serialized as a `SeelenEventPayload`
+    /// (`{ "<event>": <payload> }`). Does nothing if there are no subscribers.
+    pub fn broadcast<S: Serialize>(event: &str, payload: &S) -> Result<()> {
+        if EVENTS.receiver_count() == 0 {
+            return Ok(());
+        }
+
+        let mut map = serde_json::Map::new();
+        map.insert(event.to_string(), serde_json::to_value(payload)?);
+        let line = serde_json::to_vec(&map)?;
+        // fails only if all the subscribers disconnected in the meantime
+        let _ = EVENTS.send(StreamedEvent {
+            name: event.into(),
+            line: line.into(),
+        });
         Ok(())
     }
 
-    async fn response_to_client(
-        stream: &AsyncDuplexPipeStream<Bytes>,
+    async fn response_to_client<S: AsyncWrite + Unpin>(
+        stream: &mut S,
+        request: &Frame,
         res: IpcResponse,
     ) -> Result<()> {
-        write_to_ipc_stream(stream, &res.to_bytes()?).await
+        write_frame(stream, &request.reply(res.to_bytes()?)).await
     }
 
-    /// Sends a message to the current session asynchronously
-    pub async fn send(message: AppMessage) -> Result<()> {
-        let stream = AsyncDuplexPipeStream::connect_by_path(Self::path()).await?;
-        send_to_ipc_stream(&stream, &message.to_bytes()?)
+    /// Sends a message over the given transport
+    pub async fn send_via<T: IpcTransport>(transport: &T, message: AppMessage) -> Result<()> {
+        send_over_transport(transport, &message.to_bytes()?)
             .await?
             .ok()
     }
 
-    /// Sends a message to the current session synchronously
-    pub fn send_sync(message: &AppMessage) -> Result<()> {
-        let stream = DuplexPipeStream::connect_by_path(Self::path())?;
-        let data = message.to_bytes()?;
-        send_to_ipc_stream_blocking(&stream, &data)?;
+    /// Sends a message over the given transport, returning the data of the response
+    pub async fn request_via<T: IpcTransport>(
+        transport: &T,
+        message: AppMessage,
+    ) -> Result<Option<serde_json::Value>> {
+        send_over_transport(transport, &message.to_bytes()?)
+            .await?
+            .data()
+    }
+
+    /// Subscribes to the events streamed over the given transport, `on_event` is called with each
+    /// newline-delimited JSON `SeelenEventPayload` until the connection is closed.
+    pub async fn subscribe_via<T, F>(
+        transport: &T,
+        topics: Vec<String>,
+        mut on_event: F,
+    ) -> Result<()>
+    where
+        T: IpcTransport,
+        F: FnMut(&str),
+    {
+        let mut connection = Connection::open(transport).await?;
+        let ack = connection
+            .request(AppMessage::Subscribe { topics }.to_bytes()?)
+            .await?;
+        IpcResponse::from_bytes(&ack)?.ok()?;
+
+        // the same reader is used for the events to not lose buffered lines
+        let reader = connection.into_inner();
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            on_event(&line);
+        }
         Ok(())
     }
 }
 
-/// Gets the current session ID of the process
-pub fn current_session_id() -> Result<u32> {
-    let process_id = std::process::id();
-    let mut session_id = 0;
-    unsafe { ProcessIdToSessionId(process_id, &mut session_id)? };
-    Ok(session_id)
+/// Empty topics match every event, a trailing `*` matches by prefix.
+fn topics_match(topics: &[String], event: &str) -> bool {
+    topics.is_empty()
+        || topics.iter().any(|topic| match topic.strip_suffix('*') {
+            Some(prefix) => event.starts_with(prefix),
+            None => topic == event,
+        })
 }
 
-/// Gets the current interactive session, if any
-pub fn current_interactive_session_id() -> Option<u32> {
-    let session_id = unsafe { WTSGetActiveConsoleSessionId() };
-    if session_id == u32::MAX {
-        None
-    } else {
-        Some(session_id)
+#[cfg(windows)]
+pub use self::session::*;
+
+/// Defaults of the running app, a named pipe per Windows session.
+#[cfg(windows)]
+mod session {
+    use interprocess::os::windows::named_pipe::DuplexPipeStream;
+    use windows::Win32::System::RemoteDesktop::{
+        ProcessIdToSessionId, WTSGetActiveConsoleSessionId,
+    };
+
+    use super::*;
+    use crate::{
+        common::{IPC, send_to_ipc_stream_blocking},
+        transport::NamedPipeTransport,
+    };
+
+    impl IPC for AppIpc {
+        fn path() -> String {
+            let session_id = current_session_id().unwrap_or(0);
+            Self::path_with_session(session_id)
+        }
+    }
+
+    impl AppIpc {
+        /// Constructs the pipe path for a specific session ID
+        pub fn path_with_session(session_id: u32) -> String {
+            format!(r"\\.\pipe\seelen-ui-{}", session_id)
+        }
+
+        pub fn transport() -> NamedPipeTransport {
+            NamedPipeTransport::new(Self::path())
+        }
+
+        pub fn start<R, F>(cb: F) -> Result<()>
+        where
+            R: Future<Output = IpcResponse> + Send + Sync + 'static,
+            F: Fn(AppMessage) -> R + Send + Sync + 'static,
+        {
+            Self::serve(&Self::transport(), cb)
+        }
+
+        /// Sends a message to the current session asynchronously
+        pub async fn send(message: AppMessage) -> Result<()> {
+            Self::send_via(&Self::transport(), message).await
+        }
+
+        /// Sends a message to the current session asynchronously, returning the data of the response
+        pub async fn request(message: AppMessage) -> Result<Option<serde_json::Value>> {
+            Self::request_via(&Self::transport(), message).await
+        }
+
+        /// Subscribes to the events of the current session, see [`AppIpc::subscribe_via`].
+        pub async fn subscribe<F: FnMut(&str)>(topics: Vec<String>, on_event: F) -> Result<()> {
+            Self::subscribe_via(&Self::transport(), topics, on_event).await
+        }
+
+        /// Sends a message to the current session synchronously
+        pub fn send_sync(message: &AppMessage) -> Result<()> {
+            let stream = DuplexPipeStream::connect_by_path(Self::path())?;
+            let data = message.to_bytes()?;
+            send_to_ipc_stream_blocking(&stream, &data)?;
+            Ok(())
+        }
+    }
+
+    /// Gets the current session ID of the process
+    pub fn current_session_id() -> Result<u32> {
+        let process_id = std::process::id();
+        let mut session_id = 0;
+        unsafe { ProcessIdToSessionId(process_id, &mut session_id)? };
+        Ok(session_id)
+    }
+
+    /// Gets the current interactive session, if any
+    pub fn current_interactive_session_id() -> Option<u32> {
+        let session_id = unsafe { WTSGetActiveConsoleSessionId() };
+        if session_id == u32::MAX {
+            None
+        } else {
+            Some(session_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::{
+        common::IPC_TIMEOUT,
+        error::Error,
+        protocol::{PROTOCOL_VERSION, read_frame},
+        transport::MemoryTransport,
+    };
+
+    fn serve_echo(transport: &MemoryTransport) {
+        AppIpc::serve(transport, |message| async move {
+            match message {
+                AppMessage::Debug(msg) => IpcResponse::Data(msg.into()),
+                AppMessage::OpenUri(_) => IpcResponse::Err("not supported".to_owned()),
+                _ => IpcResponse::Success,
+            }
+        })
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_app_protocol_round_trip() -> Result<()> {
+        let transport = MemoryTransport::new();
+        serve_echo(&transport);
+
+        AppIpc::send_via(&transport, AppMessage::Cli(vec!["settings".to_owned()])).await?;
+        let data = AppIpc::request_via(&transport, AppMessage::Debug("ping".to_owned())).await?;
+        assert_eq!(data, Some("ping".into()));
+
+        let err = AppIpc::send_via(&transport, AppMessage::OpenUri("x".to_owned())).await;
+        assert!(matches!(err, Err(Error::IpcResponse(msg)) if msg == "not supported"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_errors_after_the_handshake_keep_the_framing() -> Result<()> {
+        let transport = MemoryTransport::new();
+        serve_echo(&transport);
+
+        let mut stream = Connection::open(&transport).await?.into_inner();
+        // header of an unknown frame kind
+        let mut header = b"SLU".to_vec();
+        header.extend([PROTOCOL_VERSION, 9]);
+        header.extend([0; 8]);
+        stream.write_all(&header).await?;
+        stream.flush().await?;
+
+        let answer = read_frame(&mut stream).await?;
+        assert!(!answer.is_legacy());
+        assert!(IpcResponse::from_bytes(&answer.payload)?.ok().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscription_streams_matching_events() -> Result<()> {
+        let transport = MemoryTransport::new();
+        serve_echo(&transport);
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = transport.clone();
+        tokio::spawn(async move {
+            AppIpc::subscribe_via(&client, vec!["system::*".to_owned()], |line| {
+                let _ = tx.send(line.to_owned());
+            })
+            .await
+        });
+
+        while EVENTS.receiver_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        AppIpc::broadcast("global-focus-changed", &0)?;
+        AppIpc::broadcast("system::monitors-changed", &[1, 2])?;
+
+        let line = tokio::time::timeout(IPC_TIMEOUT, rx.recv()).await.unwrap();
+        assert_eq!(
+            line.as_deref(),
+            Some(r#"{"system::monitors-changed":[1,2]}"#)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_topics_match() {
+        let topics = ["user-changed".to_owned(), "system::*".to_owned()];
+        assert!(topics_match(&[], "user-changed"));
+        assert!(topics_match(&topics, "user-changed"));
+        assert!(topics_match(&topics, "system::monitors-changed"));
+        assert!(!topics_match(&topics, "user::windows-changed"));
     }
 }
@@ -44,6 +44,9 @@ pub enum AppCommand {
     Art(ArtCli),
     TaskSwitcher(TaskSwitcherClient),
     Wallpaper(WallpaperCli),
+    Query(QueryCli),
+    Subscribe(SubscribeCli),
+    Batch(BatchCli),
     /// Toggle the global shortcuts pause state
     ToggleShortcutsPause,
 }
@@ -51,7 +54,7 @@ pub enum AppCommand {
 impl SluCliCommand for AppCommand {
     fn execution_mode(&self) -> CommandExecutionMode {
         match self {
-            AppCommand::Art(_) => CommandExecutionMode::Direct,
+            AppCommand::Art(_) | AppCommand::Subscribe(_) => CommandExecutionMode::Direct,
             AppCommand::Resource(r) => r.execution_mode(),
             _ => CommandExecutionMode::MainInstance,
         }
@@ -88,6 +91,114 @@ pub struct ArtCli {
     pub variant: ArtVariant,
 }
 
+// ===== Query =====
+
+/// Prints information of the running instance as JSON.
+#[derive(Debug, Serialize, Deserialize, clap::Args)]
+pub struct QueryCli {
+    /// Pretty prints the JSON output.
+    #[arg(long, default_value_t)]
+    pub pretty: bool,
+    #[command(subcommand)]
+    pub subcommand: QueryCommand,
+}
+
+#[derive(Debug, Serialize, Deserialize, clap::Subcommand)]
+pub enum QueryCommand {
+    /// Lists the workspaces of each monitor and their windows.
+    Workspaces,
+    /// Dumps the window manager tree of the focused workspace.
+    Tree,
+    /// Reports the focused window and the app configuration matching it.
+    FocusedWindow,
+    /// Lists the loaded resources.
+    Resources {
+        /// Only list resources of this kind.
+        kind: Option<ClapResourceKind>,
+    },
+}
+
+// ===== Subscribe =====
+
+/// Streams the events of the running instance as newline-delimited JSON until interrupted.
+#[derive(Debug, Serialize, Deserialize, clap::Args)]
+pub struct SubscribeCli {
+    /// Event names to stream (e.g. `global-focus-changed`), a trailing `*` matches by prefix
+    /// (e.g. `system::*`). Streams every event if none is given.
+    pub topics: Vec<String>,
+}
+
+// ===== Batch =====
+
+/// Runs several commands in a single round-trip. Every command is validated before running any of
+/// them, and the window manager and workspaces are rendered once at the end.
+#[derive(Debug, Serialize, Deserialize, clap::Args)]
+pub struct BatchCli {
+    /// Commands to run in order, each one as a single argument (e.g. `"virtual-desk switch-to 2"`).
+    /// Read from stdin, one per line, if none is given. Empty lines and lines starting with `#`
+    /// are ignored.
+    pub commands: Vec<String>,
+    /// Keeps running the next commands if one fails.
+    #[arg(long, default_value_t)]
+    pub keep_going: bool,
+}
+
+impl BatchCli {
+    /// Parses the commands, failing on the first invalid one.
+    pub fn parse_commands(&self) -> Result<Vec<AppCommand>, String> {
+        let mut commands = Vec::new();
+        for line in &self.commands {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let argv = std::iter::once("slu".to_string()).chain(split_command_line(line));
+            let cli = <AppCli as clap::Parser>::try_parse_from(argv)
+                .map_err(|err| format!("invalid command `{line}`: {err}"))?;
+            if matches!(
+                cli.command,
+                AppCommand::Batch(_) | AppCommand::Query(_) | AppCommand::Subscribe(_)
+            ) {
+                return Err(format!("`{line}` can't be used in a batch"));
+            }
+            commands.push(cli.command);
+        }
+        Ok(commands)
+    }
+}
+
+/// Splits by whitespace, double quoted parts are kept together.
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_arg = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
 // ===== Resource =====
 
 /// Manage the Seelen Resources.
@@ -112,11 +223,28 @@ pub enum ResourceSubCommand {
     /// Bundles a widget into a single file to be shared.
     ///
     /// Exported file will be at the same location as the passed path
-    /// with a filename `export_{date}.yml`.
+    /// with a filename `bundle {date}.yml`. Its content only depends on the sources,
+    /// which are listed with their checksums in a `SHA256SUMS` file next to the metadata file.
     Bundle {
         kind: ClapResourceKind,
         path: PathBuf,
     },
+    /// Checks the files used by a resource against the `SHA256SUMS` written when bundling it,
+    /// listing the missing, modified and new ones.
+    Verify {
+        /// The resource folder or metadata file
+        path: PathBuf,
+    },
+    /// Checks a resource without loading it, reporting every problem found with its location
+    /// in the sources, following the `!extend`ed files.
+    ///
+    /// Validates the resource against its JSON schema, runs the checks done when loading it,
+    /// and looks for texts without `en` translation and an incompatible `appTargetVersion`.
+    Lint {
+        kind: ClapResourceKind,
+        /// The resource folder or metadata file
+        path: PathBuf,
+    },
     /// Translates a resource text file to all the supported languages by Seelen UI
     /// this file should contain the source language key and value in order to be translated.
     ///
@@ -138,6 +266,8 @@ impl SluCliCommand for ResourceSubCommand {
         match self {
             ResourceSubCommand::Bundle { .. } => CommandExecutionMode::Direct,
             ResourceSubCommand::Translate { .. } => CommandExecutionMode::Direct,
+            ResourceSubCommand::Verify { .. } => CommandExecutionMode::Direct,
+            ResourceSubCommand::Lint { .. } => CommandExecutionMode::Direct,
             ResourceSubCommand::Load { .. } | ResourceSubCommand::Unload { .. } => {
                 CommandExecutionMode::MainInstance
             }
@@ -186,40 +316,91 @@ pub struct VirtualDesktopCli {
 
 #[derive(Debug, Serialize, Deserialize, clap::Subcommand)]
 pub enum VdCommand {
-    /// Switch to a neighbor workspace
-    SwitchTo { direction: DirectionOrIndex },
-    /// Send the active window to a neighbor workspace
-    SendTo { direction: DirectionOrIndex },
-    /// Move to a neighbor workspace and switch to it
-    MoveTo { direction: DirectionOrIndex },
+    /// Switch to a workspace
+    SwitchTo {
+        target: WorkspaceTarget,
+        /// Monitor of the workspace, the one under the cursor by default
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+    /// Send the active window to a workspace
+    SendTo {
+        target: WorkspaceTarget,
+        /// Monitor of the workspace, the one of the active window by default
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+    /// Move the active window to a workspace and switch to it
+    MoveTo {
+        target: WorkspaceTarget,
+        /// Monitor of the workspace, the one of the active window by default
+        #[arg(long)]
+        monitor: Option<String>,
+    },
     /// Create a new workspace column
     CreateNewWorkspace,
     /// Create a new workspace row
     CreateNewWorkspaceRow,
     /// Destroy the current workspace column or row if there is only one column
     DestroyCurrentWorkspace,
+    /// Create a workspace from a template of the settings and launch its apps on it
+    CreateFromTemplate {
+        /// Name of the template
+        name: String,
+        /// Monitor of the workspace, the one under the cursor by default
+        #[arg(long)]
+        monitor: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum DirectionOrIndex {
+/// Workspace targeted by a command, parsed from:
+/// - `left`, `right`, `up` or `down`: the neighbor of the active workspace, the grid edge policy
+///   decides what happens at the edges.
+/// - `<index>`: the workspace at the index (starting at 0) in the overview order, the active row
+///   by default.
+/// - `<row>,<col>`: the workspace at the cell (starting at 0), missing rows and columns are created.
+/// - `id:<id>`: the workspace with the given id.
+/// - `name:<name>` or just `<name>`: the first workspace with the given name, ignoring case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkspaceTarget {
     Direction(Direction),
     Index(usize),
+    Cell { row: usize, col: usize },
+    Id(String),
+    Name(String),
 }
 
-impl std::str::FromStr for DirectionOrIndex {
+impl std::str::FromStr for WorkspaceTarget {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
         if let Ok(n) = s.parse::<usize>() {
-            Ok(Self::Index(n))
-        } else {
-            Ok(match s.to_lowercase().as_str() {
-                "left" => Self::Direction(Direction::Left),
-                "right" => Self::Direction(Direction::Right),
-                "up" => Self::Direction(Direction::Up),
-                "down" => Self::Direction(Direction::Down),
-                _ => return Err("Invalid direction or index".to_string()),
-            })
+            return Ok(Self::Index(n));
+        }
+        if let Some((row, col)) = s.split_once(',')
+            && let (Ok(row), Ok(col)) = (row.trim().parse(), col.trim().parse())
+        {
+            return Ok(Self::Cell { row, col });
+        }
+
+        let target = match s.to_lowercase().as_str() {
+            "left" => Self::Direction(Direction::Left),
+            "right" => Self::Direction(Direction::Right),
+            "up" => Self::Direction(Direction::Up),
+            "down" => Self::Direction(Direction::Down),
+            _ => match (s.strip_prefix("id:"), s.strip_prefix("name:")) {
+                (Some(id), _) => Self::Id(id.to_string()),
+                (_, Some(name)) => Self::Name(name.to_string()),
+                _ => Self::Name(s.to_string()),
+            },
+        };
+
+        match &target {
+            Self::Id(value) | Self::Name(value) if value.is_empty() => Err(
+                "Invalid workspace, expected a direction, index, row,col, id or name".to_string(),
+            ),
+            _ => Ok(target),
         }
     }
 }
@@ -317,6 +498,18 @@ pub enum StepWay {
     Prev,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum TabSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Rotation {
+    Clockwise,
+    CounterClockwise,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 pub enum Axis {
     Horizontal,
@@ -358,12 +551,57 @@ pub enum WmCommand {
     },
     /// Resets the size of the containers in current workspace to the default size.
     ResetWorkspaceSize,
+    /// Sets the size of the window to an exact percentage of its row or column.
+    SetSize {
+        /// Axis to resize, left/right and top/bottom are the same as horizontal/vertical.
+        axis: Axis,
+        /// Percentage of the space (5 - 95).
+        percentage: f32,
+    },
+    /// Gives the same size to the window and its siblings.
+    Equalize {
+        /// Apply to all the containers of the current workspace.
+        #[arg(long)]
+        workspace: bool,
+    },
+    /// Rotates the container of the window, switching between horizontal and vertical.
+    Rotate {
+        way: Rotation,
+        /// Apply to all the containers of the current workspace.
+        #[arg(long)]
+        workspace: bool,
+    },
+    /// Mirrors the container of the window, reversing the order of its children.
+    Flip {
+        /// Apply to all the containers of the current workspace.
+        #[arg(long)]
+        workspace: bool,
+    },
     /// Toggles the floating state of the window
     ToggleFloat,
     /// Toggles workspace layout mode to monocle (single stack)
     ToggleMonocle,
+    /// Changes the layout of the current workspace to a built-in layout.
+    SetLayout {
+        #[command(subcommand)]
+        layout: WmLayoutPreset,
+    },
+    /// Restores the configured layout of the current workspace.
+    ResetLayout,
+    /// Saves the layout of the current workspace (sizes, stacks and windows), it will be
+    /// restored on next startup if the workspace still uses the same layout.
+    SaveLayout,
+    /// Restores the saved layout of the current workspace, moving its windows to their saved positions.
+    RestoreLayout,
     /// Cycles the foregrounf node if it is a stack
     CycleStack { way: StepWay },
+    /// Moves the tab of the window one position on its stack.
+    MoveTab { side: TabSide },
+    /// Takes the window out of its stack into a new node next to the stack.
+    PullTab {
+        /// Side of the stack where the window will be placed.
+        side: Direction,
+    },
     /// Focuses the window in the specified position.
     Focus {
         /// The position of the window to focus.
@@ -379,6 +617,64 @@ pub enum WmCommand {
         /// Direction to move
         side: Direction,
     },
+    /// Named scratchpads, hidden windows that can be shown as floating windows on demand.
+    Scratchpad {
+        #[command(subcommand)]
+        action: ScratchpadAction,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, clap::Subcommand)]
+pub enum ScratchpadAction {
+    /// Shows the scratchpad centered on the current monitor, or hides it if it is focused.
+    Toggle {
+        /// Name of the scratchpad.
+        name: String,
+    },
+    /// Sends the focused window to the scratchpad, hiding it.
+    Send {
+        /// Name of the scratchpad, it is created if it doesn't exist.
+        name: String,
+    },
+    /// Takes the focused window out of its scratchpad, back to the current workspace.
+    Release,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, clap::Subcommand)]
+pub enum WmLayoutPreset {
+    /// Master windows on a column and the rest of windows on a second column.
+    MasterStack {
+        /// Amount of windows on the master column.
+        #[arg(long, default_value_t = 1)]
+        master_count: usize,
+        /// Width of the master column (0.1 - 0.9).
+        #[arg(long, default_value_t = 0.5)]
+        ratio: f32,
+    },
+    /// Each new window takes half of the space of the previous one.
+    Dwindle,
+    /// Like dwindle but placing the windows in a spiral.
+    Spiral,
+    /// Master window centered, the rest of windows on the sides.
+    CenteredMaster {
+        /// Width of the master window (0.1 - 0.9).
+        #[arg(long, default_value_t = 0.5)]
+        ratio: f32,
+    },
+    /// Windows placed row by row.
+    Grid {
+        /// Amount of columns.
+        #[arg(long, default_value_t = 2)]
+        columns: usize,
+    },
+    /// All windows on a single stack.
+    Monocle,
+    /// Each window opens a new column, columns that don't fit are placed past the monitor edge.
+    Scrollable {
+        /// Width of each column relative to the monitor (0.1 - 1.0).
+        #[arg(long, default_value_t = 0.5)]
+        column_width: f32,
+    },
 }
 
 // ===== TaskSwitcher =====
@@ -417,3 +713,67 @@ pub enum WallpaperCommand {
     /// Cycle to the previous wallpaper
     Prev,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command_line() {
+        assert_eq!(
+            split_command_line(r#"  wm scratchpad toggle "my notes"  "" "#),
+            ["wm", "scratchpad", "toggle", "my notes", ""]
+        );
+    }
+
+    #[test]
+    fn test_batch_commands_are_validated_together() {
+        let mut batch = BatchCli {
+            commands: vec![
+                "# comment".to_string(),
+                "virtual-desk create-new-workspace".to_string(),
+                "virtual-desk switch-to 2".to_string(),
+            ],
+            keep_going: false,
+        };
+        let commands = batch.parse_commands().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(
+            &commands[1],
+            AppCommand::VirtualDesk(VirtualDesktopCli {
+                subcommand: VdCommand::SwitchTo {
+                    target: WorkspaceTarget::Index(2),
+                    monitor: None,
+                }
+            })
+        ));
+
+        batch.commands.push("virtual-desk unknown".to_string());
+        assert!(batch.parse_commands().is_err());
+
+        batch.commands = vec!["batch settings".to_string()];
+        assert!(batch.parse_commands().is_err());
+    }
+
+    #[test]
+    fn test_workspace_target_parsing() {
+        let parse = |s: &str| s.parse::<WorkspaceTarget>();
+        assert_eq!(
+            parse("Left"),
+            Ok(WorkspaceTarget::Direction(Direction::Left))
+        );
+        assert_eq!(parse("3"), Ok(WorkspaceTarget::Index(3)));
+        assert_eq!(parse("1, 2"), Ok(WorkspaceTarget::Cell { row: 1, col: 2 }));
+        assert_eq!(parse("id:abc"), Ok(WorkspaceTarget::Id("abc".to_string())));
+        assert_eq!(
+            parse("name:left"),
+            Ok(WorkspaceTarget::Name("left".to_string()))
+        );
+        assert_eq!(
+            parse("web, mail"),
+            Ok(WorkspaceTarget::Name("web, mail".to_string()))
+        );
+        assert!(parse("id:").is_err());
+        assert!(parse(" ").is_err());
+    }
+}
@@ -1,20 +1,16 @@
-use std::{
-    io::{BufRead, Write},
-    time::Duration,
-};
+use std::time::Duration;
 
-use interprocess::os::windows::{
-    named_pipe::{
-        DuplexPipeStream, pipe_mode::Bytes, tokio::DuplexPipeStream as AsyncDuplexPipeStream,
-    },
-    security_descriptor::{AsSecurityDescriptorMutExt, SecurityDescriptor},
-};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
 
-use crate::{error::Result, messages::IpcResponse};
+use crate::{
+    error::Result,
+    messages::IpcResponse,
+    protocol::Connection,
+    transport::{IpcStream, IpcTransport},
+};
 
-/// https://learn.microsoft.com/en-us/windows/win32/secauthz/security-descriptor-control
-pub static SE_DACL_PROTECTED: u16 = 4096u16;
+#[cfg(windows)]
+pub use self::named_pipe::*;
 
 /// End of transmission block marker for IPC messages
 pub const END_OF_TRANSMISSION_BLOCK: u8 = 0x17;
@@ -25,46 +21,8 @@ pub const IPC_TIMEOUT: Duration = Duration::from_secs(3);
 /// Maximum number of retries for failed IPC operations
 pub const MAX_RETRIES: u32 = 3;
 
-/// IPC trait for common connection operations
-pub trait IPC {
-    fn path() -> String;
-
-    #[allow(async_fn_in_trait)]
-    async fn server_process_id() -> Result<u32> {
-        let stream = AsyncDuplexPipeStream::connect_by_path(Self::path()).await?;
-        let pid = stream.server_process_id()?;
-        write_to_ipc_stream(&stream, &[]).await?;
-        Ok(pid)
-    }
-
-    fn can_stablish_connection() -> bool {
-        // Run the blocking IPC probe in a dedicated thread with a hard timeout so
-        // the caller never hangs if the server accepts the connection but stops
-        // responding (which would leave `read_until` blocked forever).
-        let path = Self::path();
-        let (tx, rx) = std::sync::mpsc::channel();
-        std::thread::spawn(move || {
-            let ok = (|| -> crate::error::Result<()> {
-                let stream = DuplexPipeStream::<Bytes>::connect_by_path(path)?;
-                send_to_ipc_stream_blocking(&stream, &[])?.ok()
-            })()
-            .is_ok();
-            let _ = tx.send(ok);
-        });
-        rx.recv_timeout(IPC_TIMEOUT).unwrap_or(false)
-    }
-}
-
-/// Creates a security descriptor for IPC pipes
-pub fn create_security_descriptor() -> Result<SecurityDescriptor> {
-    let mut sd = SecurityDescriptor::new()?;
-    unsafe { sd.set_dacl(std::ptr::null_mut(), false)? };
-    sd.set_control(SE_DACL_PROTECTED, SE_DACL_PROTECTED)?;
-    Ok(sd)
-}
-
-/// Reads data from an async IPC stream with timeout
-pub async fn read_from_ipc_stream(stream: &AsyncDuplexPipeStream<Bytes>) -> Result<Vec<u8>> {
+/// Reads legacy framed data from an async IPC stream with timeout
+pub async fn read_from_ipc_stream<S: AsyncRead + Unpin>(stream: S) -> Result<Vec<u8>> {
     let mut reader = BufReader::new(stream);
     let mut buf = Vec::new();
 
@@ -78,8 +36,8 @@ pub async fn read_from_ipc_stream(stream: &AsyncDuplexPipeStream<Bytes>) -> Resu
     Ok(buf)
 }
 
-/// Writes data to an async IPC stream with timeout
-pub async fn write_to_ipc_stream(stream: &AsyncDuplexPipeStream<Bytes>, buf: &[u8]) -> Result<()> {
+/// Writes legacy framed data to an async IPC stream with timeout
+pub async fn write_to_ipc_stream<S: AsyncWrite + Unpin>(stream: S, buf: &[u8]) -> Result<()> {
     let mut writter = BufWriter::new(stream);
 
     tokio::time::timeout(IPC_TIMEOUT, async {
@@ -94,32 +52,36 @@ pub async fn write_to_ipc_stream(stream: &AsyncDuplexPipeStream<Bytes>, buf: &[u
     Ok(())
 }
 
+/// Writes a newline terminated line to an async IPC stream with timeout, used by event streams
+pub async fn write_line_to_ipc_stream<S: AsyncWrite + Unpin>(stream: S, line: &[u8]) -> Result<()> {
+    let mut writter = BufWriter::new(stream);
+
+    tokio::time::timeout(IPC_TIMEOUT, async {
+        writter.write_all(line).await?;
+        writter.write_all(b"\n").await?;
+        writter.flush().await?;
+        Ok::<(), std::io::Error>(())
+    })
+    .await
+    .map_err(|_| crate::error::Error::Timeout("Failed to write to IPC stream".to_string()))??;
+
+    Ok(())
+}
+
 /// Sends data and receives response from an async IPC stream
-pub async fn send_to_ipc_stream(
-    stream: &AsyncDuplexPipeStream<Bytes>,
-    buf: &[u8],
-) -> Result<IpcResponse> {
-    write_to_ipc_stream(stream, buf).await?;
-    let buf = read_from_ipc_stream(stream).await?;
+pub async fn send_to_ipc_stream<S: IpcStream>(stream: &mut S, buf: &[u8]) -> Result<IpcResponse> {
+    write_to_ipc_stream(&mut *stream, buf).await?;
+    let buf = read_from_ipc_stream(&mut *stream).await?;
     IpcResponse::from_bytes(&buf)
 }
 
-/// Blocking version to test connections without needed of tokio runtime
-pub fn send_to_ipc_stream_blocking(
-    stream: &DuplexPipeStream<Bytes>,
+/// Opens a new connection on the transport to send data and receive the response
+pub async fn send_over_transport<T: IpcTransport>(
+    transport: &T,
     buf: &[u8],
 ) -> Result<IpcResponse> {
-    let mut writter = std::io::BufWriter::new(stream);
-    writter.write_all(buf)?;
-    writter.write_all(&[END_OF_TRANSMISSION_BLOCK])?;
-    writter.flush()?;
-
-    let mut reader = std::io::BufReader::new(stream);
-    let mut buf = Vec::new();
-    reader.read_until(END_OF_TRANSMISSION_BLOCK, &mut buf)?;
-    buf.pop();
-
-    IpcResponse::from_bytes(&buf)
+    let mut connection = Connection::open(transport).await?;
+    IpcResponse::from_bytes(&connection.request(buf.to_vec()).await?)
 }
 
 /// Sends data with retry logic and exponential backoff
@@ -155,3 +117,77 @@ where
         crate::error::Error::Timeout("Unknown error during IPC send".to_string())
     }))
 }
+
+#[cfg(windows)]
+mod named_pipe {
+    use std::io::{BufRead, Write};
+
+    use interprocess::os::windows::{
+        named_pipe::{
+            DuplexPipeStream, pipe_mode::Bytes, tokio::DuplexPipeStream as AsyncDuplexPipeStream,
+        },
+        security_descriptor::{AsSecurityDescriptorMutExt, SecurityDescriptor},
+    };
+
+    use super::{END_OF_TRANSMISSION_BLOCK, IPC_TIMEOUT, write_to_ipc_stream};
+    use crate::{error::Result, messages::IpcResponse};
+
+    /// https://learn.microsoft.com/en-us/windows/win32/secauthz/security-descriptor-control
+    pub static SE_DACL_PROTECTED: u16 = 4096u16;
+
+    /// IPC trait for common connection operations
+    pub trait IPC {
+        fn path() -> String;
+
+        #[allow(async_fn_in_trait)]
+        async fn server_process_id() -> Result<u32> {
+            let stream = AsyncDuplexPipeStream::connect_by_path(Self::path()).await?;
+            let pid = stream.server_process_id()?;
+            write_to_ipc_stream(&stream, &[]).await?;
+            Ok(pid)
+        }
+
+        fn can_stablish_connection() -> bool {
+            // Run the blocking IPC probe in a dedicated thread with a hard timeout so
+            // the caller never hangs if the server accepts the connection but stops
+            // responding (which would leave `read_until` blocked forever).
+            let path = Self::path();
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let ok = (|| -> crate::error::Result<()> {
+                    let stream = DuplexPipeStream::<Bytes>::connect_by_path(path)?;
+                    send_to_ipc_stream_blocking(&stream, &[])?.ok()
+                })()
+                .is_ok();
+                let _ = tx.send(ok);
+            });
+            rx.recv_timeout(IPC_TIMEOUT).unwrap_or(false)
+        }
+    }
+
+    /// Creates a security descriptor for IPC pipes
+    pub fn create_security_descriptor() -> Result<SecurityDescriptor> {
+        let mut sd = SecurityDescriptor::new()?;
+        unsafe { sd.set_dacl(std::ptr::null_mut(), false)? };
+        sd.set_control(SE_DACL_PROTECTED, SE_DACL_PROTECTED)?;
+        Ok(sd)
+    }
+
+    /// Blocking version to test connections without needed of tokio runtime
+    pub fn send_to_ipc_stream_blocking(
+        stream: &DuplexPipeStream<Bytes>,
+        buf: &[u8],
+    ) -> Result<IpcResponse> {
+        let mut writter = std::io::BufWriter::new(stream);
+        writter.write_all(buf)?;
+        writter.write_all(&[END_OF_TRANSMISSION_BLOCK])?;
+        writter.flush()?;
+
+        let mut reader = std::io::BufReader::new(stream);
+        let mut buf = Vec::new();
+        reader.read_until(END_OF_TRANSMISSION_BLOCK, &mut buf)?;
+        buf.pop();
+
+        IpcResponse::from_bytes(&buf)
+    }
+}
@@ -8,8 +8,11 @@ pub enum Error {
     IpcResponse(String),
     #[error("Serde Json Error: {0}")]
     SerdeJson(#[from] serde_json::Error),
+    #[error("IPC Protocol Error: {0}")]
+    Protocol(String),
     #[error("IPC Timeout: {0}")]
     Timeout(String),
+    #[cfg(windows)]
     #[error("Windows Error: {0}")]
     Windows(#[from] windows::core::Error),
 }
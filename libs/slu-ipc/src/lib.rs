@@ -3,9 +3,12 @@ pub mod commands;
 pub mod common;
 pub mod error;
 pub mod messages;
+pub mod protocol;
 pub mod service;
+pub mod transport;
 
 // Re-export main types for convenience
 pub use app::AppIpc;
+#[cfg(windows)]
 pub use common::IPC;
 pub use service::ServiceIpc;
@@ -14,13 +14,21 @@ use crate::{
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum IpcResponse {
     Success,
+    /// Success carrying the JSON result of the request, used by queries.
+    Data(serde_json::Value),
     Err(String),
 }
 
 impl IpcResponse {
     pub fn ok(self) -> Result<()> {
+        self.data().map(|_| ())
+    }
+
+    /// Returns the carried data, `None` for responses without data.
+    pub fn data(self) -> Result<Option<serde_json::Value>> {
         match self {
-            IpcResponse::Success => Ok(()),
+            IpcResponse::Success => Ok(None),
+            IpcResponse::Data(data) => Ok(Some(data)),
             IpcResponse::Err(err) => Err(Error::IpcResponse(err)),
         }
     }
@@ -52,6 +60,13 @@ pub enum AppMessage {
     TrayChanged(Win32TrayEvent),
     /// Debug message for logging and diagnostics
     Debug(String),
+    /// Keeps the connection open streaming the events of the app as newline-delimited JSON.
+    /// Handled by [`crate::AppIpc`] itself, so it never reaches the message callback.
+    Subscribe {
+        /// Event names to stream, a trailing `*` matches by prefix (e.g. `system::*`).
+        /// Empty streams every event.
+        topics: Vec<String>,
+    },
 }
 
 impl AppMessage {
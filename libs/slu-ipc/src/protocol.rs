@@ -0,0 +1,360 @@
+//! Wire protocol shared by every IPC channel.
+//!
+//! Version 2 frames are `SLU` + version (u8) + kind (u8) + request id (u32 LE) + payload length
+//! (u32 LE) + payload + [`END_OF_TRANSMISSION_BLOCK`]. The trailing byte is not needed by the
+//! framing, it makes legacy peers (version 1, JSON terminated by the same byte) answer with an
+//! error instead of waiting for the timeout, which is how old builds are detected.
+//!
+//! Clients start each connection with a handshake carrying the protocol and `slu-ipc` versions,
+//! servers answer it with theirs. Legacy frames are still understood on both sides.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+
+use crate::{
+    common::{END_OF_TRANSMISSION_BLOCK, IPC_TIMEOUT},
+    error::{Error, Result},
+    transport::{IpcStream, IpcTransport},
+};
+
+/// Version of the wire protocol spoken by this build.
+pub const PROTOCOL_VERSION: u8 = 2;
+/// JSON terminated by [`END_OF_TRANSMISSION_BLOCK`], without header.
+pub const LEGACY_PROTOCOL_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 3] = b"SLU";
+const HEADER_LEN: usize = 13;
+/// Frames bigger than this are rejected to not allocate garbage lengths.
+const MAX_PAYLOAD_LEN: usize = 64 * 1024 * 1024;
+
+static NEXT_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Handshake,
+    Message,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub version: u8,
+    pub kind: FrameKind,
+    /// Always 0 on legacy frames.
+    pub request_id: u32,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn message(payload: Vec<u8>) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            kind: FrameKind::Message,
+            request_id: NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+            payload,
+        }
+    }
+
+    pub fn legacy(payload: Vec<u8>) -> Self {
+        Self {
+            version: LEGACY_PROTOCOL_VERSION,
+            kind: FrameKind::Message,
+            request_id: 0,
+            payload,
+        }
+    }
+
+    fn handshake(request_id: u32) -> Result<Self> {
+        Ok(Self {
+            version: PROTOCOL_VERSION,
+            kind: FrameKind::Handshake,
+            request_id,
+            payload: serde_json::to_vec(&Handshake::current())?,
+        })
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.version == LEGACY_PROTOCOL_VERSION
+    }
+
+    /// Answer to this frame, using the same framing and request id.
+    pub fn reply(&self, payload: Vec<u8>) -> Self {
+        if self.is_legacy() {
+            return Self::legacy(payload);
+        }
+        Self {
+            version: PROTOCOL_VERSION,
+            kind: FrameKind::Message,
+            request_id: self.request_id,
+            payload,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.payload.len() + 1);
+        if !self.is_legacy() {
+            bytes.extend_from_slice(MAGIC);
+            bytes.push(self.version);
+            bytes.push(match self.kind {
+                FrameKind::Handshake => 0,
+                FrameKind::Message => 1,
+            });
+            bytes.extend_from_slice(&self.request_id.to_le_bytes());
+            bytes.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.payload);
+        bytes.push(END_OF_TRANSMISSION_BLOCK);
+        bytes
+    }
+}
+
+/// Versions exchanged at the start of each connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub protocol: u8,
+    /// Version of the `slu-ipc` crate the peer was built with.
+    pub version: String,
+}
+
+impl Handshake {
+    pub fn current() -> Self {
+        Self {
+            protocol: PROTOCOL_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Fails if the peer can't be understood, warns if it is just another build.
+    pub fn check(&self) -> Result<()> {
+        let current = Self::current();
+        if self.protocol != current.protocol {
+            return Err(Error::Protocol(format!(
+                "mismatched slu-ipc versions, this side runs {} (protocol v{}) and the other side \
+                 runs {} (protocol v{}). Restart Seelen UI to finish the update.",
+                current.version, current.protocol, self.version, self.protocol
+            )));
+        }
+        if self.version != current.version {
+            log::warn!(
+                "IPC peer runs slu-ipc {} while this side runs {}",
+                self.version,
+                current.version
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Reads a frame of any version with timeout.
+pub async fn read_frame<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Frame> {
+    tokio::time::timeout(IPC_TIMEOUT, read_frame_untimed(reader))
+        .await
+        .map_err(|_| Error::Timeout("Failed to read from IPC stream".to_string()))?
+}
+
+async fn read_frame_untimed<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Frame> {
+    let first = reader.fill_buf().await?.first().copied();
+    if first != Some(MAGIC[0]) {
+        let mut payload = Vec::new();
+        reader
+            .read_until(END_OF_TRANSMISSION_BLOCK, &mut payload)
+            .await?;
+        payload.pop();
+        return Ok(Frame::legacy(payload));
+    }
+
+    let mut header = [0u8; HEADER_LEN];
+    reader.read_exact(&mut header).await?;
+    if &header[..3] != MAGIC {
+        return Err(Error::Protocol("invalid frame header".to_string()));
+    }
+
+    let kind = match header[4] {
+        0 => FrameKind::Handshake,
+        1 => FrameKind::Message,
+        kind => return Err(Error::Protocol(format!("unknown frame kind {kind}"))),
+    };
+    let request_id = u32::from_le_bytes(header[5..9].try_into().unwrap());
+    let len = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+    if len > MAX_PAYLOAD_LEN {
+        return Err(Error::Protocol(format!("frame of {len} bytes is too big")));
+    }
+
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).await?;
+    if reader.read_u8().await? != END_OF_TRANSMISSION_BLOCK {
+        return Err(Error::Protocol("missing end of frame".to_string()));
+    }
+
+    Ok(Frame {
+        version: header[3],
+        kind,
+        request_id,
+        payload,
+    })
+}
+
+/// Writes a frame with timeout.
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame) -> Result<()> {
+    tokio::time::timeout(IPC_TIMEOUT, async {
+        writer.write_all(&frame.to_bytes()).await?;
+        writer.flush().await
+    })
+    .await
+    .map_err(|_| Error::Timeout("Failed to write to IPC stream".to_string()))??;
+    Ok(())
+}
+
+/// Server side of a connection, answers the handshake if the client sent one and returns
+/// the request to be processed.
+///
+/// `reply_to` is updated with each frame read, so failures can be answered with the framing
+/// and request id the client is waiting for.
+pub async fn read_request<S>(stream: &mut BufReader<S>, reply_to: &mut Frame) -> Result<Frame>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let frame = read_frame(stream).await?;
+    *reply_to = frame.reply(Vec::new());
+    if frame.kind != FrameKind::Handshake {
+        return Ok(frame);
+    }
+
+    let peer: Handshake = serde_json::from_slice(&frame.payload)?;
+    if let Err(err) = peer.check() {
+        log::error!("{err}");
+    }
+    // always answered, so the client can report the mismatch too
+    write_frame(stream, &Frame::handshake(frame.request_id)?).await?;
+    let request = read_frame(stream).await?;
+    *reply_to = request.reply(Vec::new());
+    Ok(request)
+}
+
+/// Client side of a connection to a server of any protocol version.
+pub struct Connection<S> {
+    stream: BufReader<S>,
+    protocol: u8,
+}
+
+impl<S: IpcStream> Connection<S> {
+    /// Connects negotiating the protocol version, falls back to the legacy framing if the
+    /// server is an old build.
+    pub async fn open<T: IpcTransport<Stream = S>>(transport: &T) -> Result<Self> {
+        let mut stream = BufReader::new(transport.connect().await?);
+        let hello = Frame::handshake(NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed))?;
+        write_frame(&mut stream, &hello).await?;
+
+        let answer = read_frame(&mut stream).await?;
+        if answer.is_legacy() {
+            // legacy servers answer the handshake with an error and close the connection
+            log::debug!("IPC server uses the legacy protocol, falling back");
+            return Ok(Self {
+                stream: BufReader::new(transport.connect().await?),
+                protocol: LEGACY_PROTOCOL_VERSION,
+            });
+        }
+
+        let peer: Handshake = serde_json::from_slice(&answer.payload)?;
+        peer.check()?;
+        Ok(Self {
+            stream,
+            protocol: peer.protocol,
+        })
+    }
+
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// Sends the payload and waits for the response to it.
+    pub async fn request(&mut self, payload: Vec<u8>) -> Result<Vec<u8>> {
+        let request = if self.protocol == LEGACY_PROTOCOL_VERSION {
+            Frame::legacy(payload)
+        } else {
+            Frame::message(payload)
+        };
+        write_frame(&mut self.stream, &request).await?;
+
+        let response = read_frame(&mut self.stream).await?;
+        if response.request_id != request.request_id {
+            return Err(Error::Protocol(format!(
+                "expected the response to request {} but got {}",
+                request.request_id, response.request_id
+            )));
+        }
+        Ok(response.payload)
+    }
+
+    /// Gives back the buffered stream, to keep reading after a request.
+    pub fn into_inner(self) -> BufReader<S> {
+        self.stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{read_from_ipc_stream, write_to_ipc_stream},
+        messages::IpcResponse,
+        transport::{IpcListener, MemoryTransport},
+    };
+
+    #[tokio::test]
+    async fn test_frames_carry_the_end_of_transmission_byte() -> Result<()> {
+        let payload = vec![b'{', END_OF_TRANSMISSION_BLOCK, b'}'];
+        let frame = Frame::message(payload.clone());
+        let mut bytes = frame.to_bytes();
+        bytes.extend(Frame::legacy(b"\"Success\"".to_vec()).to_bytes());
+
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_frame(&mut reader).await?, frame);
+        let legacy = read_frame(&mut reader).await?;
+        assert!(legacy.is_legacy());
+        assert_eq!(legacy.payload, b"\"Success\"");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fallback_to_legacy_server() -> Result<()> {
+        let transport = MemoryTransport::new();
+        let mut listener = transport.listen()?;
+        tokio::spawn(async move {
+            while let Ok(mut stream) = listener.accept().await {
+                let data = read_from_ipc_stream(&mut stream).await.unwrap();
+                let response = match serde_json::from_slice::<String>(&data) {
+                    Ok(msg) => IpcResponse::Data(msg.into()),
+                    Err(err) => IpcResponse::Err(err.to_string()),
+                };
+                write_to_ipc_stream(&mut stream, &response.to_bytes().unwrap())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut connection = Connection::open(&transport).await?;
+        assert_eq!(connection.protocol(), LEGACY_PROTOCOL_VERSION);
+        let response = connection.request(b"\"ping\"".to_vec()).await?;
+        assert_eq!(
+            IpcResponse::from_bytes(&response)?.data()?,
+            Some("ping".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_rejects_other_protocols() {
+        assert!(Handshake::current().check().is_ok());
+        let err = Handshake {
+            protocol: 3,
+            version: "9.0.0".to_string(),
+        }
+        .check()
+        .unwrap_err();
+        assert!(err.to_string().contains("9.0.0 (protocol v3)"));
+    }
+}
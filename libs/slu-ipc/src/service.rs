@@ -1,60 +1,44 @@
 use std::{future::Future, sync::Arc};
 
-use interprocess::os::windows::named_pipe::{
-    DuplexPipeStream, PipeListenerOptions, pipe_mode::Bytes,
-    tokio::DuplexPipeStream as AsyncDuplexPipeStream,
-};
+use tokio::io::{AsyncWrite, BufReader};
 
 use crate::{
-    app::current_session_id,
-    common::{
-        IPC, create_security_descriptor, read_from_ipc_stream, send_to_ipc_stream,
-        send_to_ipc_stream_blocking, send_with_retry, write_to_ipc_stream,
-    },
+    common::{send_over_transport, send_with_retry},
     error::Result,
     messages::{IpcResponse, SvcAction, SvcMessage},
+    protocol::{Frame, read_request, write_frame},
+    transport::{IpcListener, IpcStream, IpcTransport},
 };
 
 pub struct ServiceIpc {
     _priv: (),
 }
 
-impl IPC for ServiceIpc {
-    fn path() -> String {
-        let session_id = current_session_id().unwrap_or(0);
-        Self::path_with_session(session_id)
-    }
-}
-
 impl ServiceIpc {
-    /// Constructs the pipe path for a specific session ID
-    pub fn path_with_session(session_id: u32) -> String {
-        format!(r"\\.\pipe\seelen-ui-service-{}", session_id)
-    }
-}
-
-impl ServiceIpc {
-    pub fn start<R, F>(cb: F) -> Result<()>
+    /// Serves the service protocol over the given transport
+    pub fn serve<T, R, F>(transport: &T, cb: F) -> Result<()>
     where
-        R: Future<Output = IpcResponse> + Send + Sync,
+        T: IpcTransport,
+        R: Future<Output = IpcResponse> + Send + Sync + 'static,
         F: Fn(SvcAction) -> R + Send + Sync + 'static,
     {
-        let sd = create_security_descriptor()?;
-
-        let listener = PipeListenerOptions::new()
-            .path(Self::path())
-            .security_descriptor(Some(sd))
-            .create_tokio_duplex::<Bytes>()?;
+        let mut listener = transport.listen()?;
 
         tokio::spawn(async move {
             let callback = Arc::new(cb);
-            while let Ok(stream) = listener.accept().await {
+            while let Ok(mut stream) = listener.accept().await {
                 let callback = callback.clone();
                 tokio::spawn(async move {
-                    if let Err(err) = Self::process_connection(&stream, callback).await
-                        && let Err(send_err) =
-                            Self::response_to_client(&stream, IpcResponse::Err(err.to_string()))
-                                .await
+                    // legacy framing until the client is read
+                    let mut reply_to = Frame::legacy(Vec::new());
+                    if let Err(err) =
+                        Self::process_connection(&mut stream, &mut reply_to, callback).await
+                        && let Err(send_err) = Self::response_to_client(
+                            &mut stream,
+                            &reply_to,
+                            IpcResponse::Err(err.to_string()),
+                        )
+                        .await
                     {
                         log::error!(
                             "Failed to send error response: {send_err} || Original error: {err}"
@@ -66,64 +50,143 @@ impl ServiceIpc {
         Ok(())
     }
 
-    async fn process_connection<F, R>(
-        stream: &AsyncDuplexPipeStream<Bytes>,
+    async fn process_connection<S, F, R>(
+        stream: &mut S,
+        reply_to: &mut Frame,
         cb: Arc<F>,
     ) -> Result<()>
     where
+        S: IpcStream,
         R: Future<Output = IpcResponse> + Send + Sync,
         F: Fn(SvcAction) -> R + Send + Sync + 'static,
     {
-        let data = read_from_ipc_stream(stream).await?;
-        if data.is_empty() {
-            return Self::response_to_client(stream, IpcResponse::Success).await;
+        let mut stream = BufReader::new(stream);
+        let request = read_request(&mut stream, reply_to).await?;
+        if request.payload.is_empty() {
+            return Self::response_to_client(&mut stream, &request, IpcResponse::Success).await;
         }
 
-        let message = SvcMessage::from_bytes(&data)?;
+        let message = match SvcMessage::from_bytes(&request.payload) {
+            Ok(message) => message,
+            Err(err) => {
+                let res = IpcResponse::Err(err.to_string());
+                return Self::response_to_client(&mut stream, &request, res).await;
+            }
+        };
         if !message.is_signature_valid() {
-            Self::response_to_client(
-                stream,
-                IpcResponse::Err("Unauthorized connection".to_owned()),
-            )
-            .await?;
-            return Ok(());
+            let res = IpcResponse::Err("Unauthorized connection".to_owned());
+            return Self::response_to_client(&mut stream, &request, res).await;
         }
 
         log::trace!("IPC command received: {:?}", message.action);
-        Self::response_to_client(stream, cb(message.action).await).await?;
-        Ok(())
+        let res = cb(message.action).await;
+        Self::response_to_client(&mut stream, &request, res).await
     }
 
-    async fn response_to_client(
-        stream: &AsyncDuplexPipeStream<Bytes>,
+    async fn response_to_client<S: AsyncWrite + Unpin>(
+        stream: &mut S,
+        request: &Frame,
         res: IpcResponse,
     ) -> Result<()> {
-        write_to_ipc_stream(stream, &res.to_bytes()?).await
+        write_frame(stream, &request.reply(res.to_bytes()?)).await
     }
 
-    pub async fn send(message: SvcAction) -> Result<()> {
+    /// Sends a signed action over the given transport, retrying on connection failures
+    pub async fn send_via<T: IpcTransport>(transport: &T, message: SvcAction) -> Result<()> {
         let data = SvcMessage {
             token: SvcMessage::signature().to_string(),
             action: message,
         }
         .to_bytes()?;
 
-        send_with_retry(|| Self::try_send(&data)).await
+        send_with_retry(|| send_over_transport(transport, &data)).await
     }
+}
 
-    pub fn send_blocking(message: SvcAction) -> Result<IpcResponse> {
-        let data = SvcMessage {
-            token: SvcMessage::signature().to_string(),
-            action: message,
+#[cfg(windows)]
+mod session {
+    use interprocess::os::windows::named_pipe::DuplexPipeStream;
+
+    use super::*;
+    use crate::{
+        app::current_session_id,
+        common::{IPC, send_to_ipc_stream_blocking},
+        transport::NamedPipeTransport,
+    };
+
+    impl IPC for ServiceIpc {
+        fn path() -> String {
+            let session_id = current_session_id().unwrap_or(0);
+            Self::path_with_session(session_id)
         }
-        .to_bytes()?;
+    }
 
-        let stream = DuplexPipeStream::connect_by_path(Self::path())?;
-        send_to_ipc_stream_blocking(&stream, &data)
+    impl ServiceIpc {
+        /// Constructs the pipe path for a specific session ID
+        pub fn path_with_session(session_id: u32) -> String {
+            format!(r"\\.\pipe\seelen-ui-service-{}", session_id)
+        }
+
+        pub fn transport() -> NamedPipeTransport {
+            NamedPipeTransport::new(Self::path())
+        }
+
+        pub fn start<R, F>(cb: F) -> Result<()>
+        where
+            R: Future<Output = IpcResponse> + Send + Sync + 'static,
+            F: Fn(SvcAction) -> R + Send + Sync + 'static,
+        {
+            Self::serve(&Self::transport(), cb)
+        }
+
+        pub async fn send(message: SvcAction) -> Result<()> {
+            Self::send_via(&Self::transport(), message).await
+        }
+
+        pub fn send_blocking(message: SvcAction) -> Result<IpcResponse> {
+            let data = SvcMessage {
+                token: SvcMessage::signature().to_string(),
+                action: message,
+            }
+            .to_bytes()?;
+
+            let stream = DuplexPipeStream::connect_by_path(Self::path())?;
+            send_to_ipc_stream_blocking(&stream, &data)
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{send_over_transport, send_to_ipc_stream},
+        transport::LocalSocketTransport,
+    };
+
+    #[tokio::test]
+    async fn test_service_protocol_over_local_socket() -> Result<()> {
+        let transport = LocalSocketTransport::new(format!("slu-ipc-test-{}", std::process::id()));
+        ServiceIpc::serve(&transport, |action| async move {
+            match action {
+                SvcAction::SetForeground(_) => IpcResponse::Success,
+                _ => IpcResponse::Err("unexpected".to_owned()),
+            }
+        })?;
+
+        ServiceIpc::send_via(&transport, SvcAction::SetForeground(1)).await?;
 
-    async fn try_send(data: &[u8]) -> Result<IpcResponse> {
-        let stream = AsyncDuplexPipeStream::connect_by_path(Self::path()).await?;
-        send_to_ipc_stream(&stream, data).await
+        // messages without the connection token are rejected
+        let unsigned = SvcMessage {
+            token: "invalid".to_owned(),
+            action: SvcAction::SetForeground(1),
+        };
+        let response = send_over_transport(&transport, &unsigned.to_bytes()?).await?;
+        assert!(response.ok().is_err());
+
+        // empty messages are used as connection probes
+        let mut stream = transport.connect().await?;
+        assert!(send_to_ipc_stream(&mut stream, &[]).await?.ok().is_ok());
+        Ok(())
     }
 }
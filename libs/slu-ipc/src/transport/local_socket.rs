@@ -0,0 +1,49 @@
+use interprocess::local_socket::{
+    GenericNamespaced, ListenerOptions, Name, ToNsName,
+    tokio::{Listener, Stream},
+    traits::tokio::Stream as _,
+};
+
+use crate::{
+    error::Result,
+    transport::{IpcListener, IpcTransport},
+};
+
+/// Transport over the local sockets of the platform, Unix domain sockets or named pipes on Windows.
+#[derive(Debug, Clone)]
+pub struct LocalSocketTransport {
+    name: String,
+}
+
+impl LocalSocketTransport {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    fn socket_name(&self) -> Result<Name<'_>> {
+        Ok(self.name.as_str().to_ns_name::<GenericNamespaced>()?)
+    }
+}
+
+impl IpcTransport for LocalSocketTransport {
+    type Stream = Stream;
+    type Listener = Listener;
+
+    fn listen(&self) -> Result<Listener> {
+        Ok(ListenerOptions::new()
+            .name(self.socket_name()?)
+            .create_tokio()?)
+    }
+
+    async fn connect(&self) -> Result<Stream> {
+        Ok(Stream::connect(self.socket_name()?).await?)
+    }
+}
+
+impl IpcListener for Listener {
+    type Stream = Stream;
+
+    async fn accept(&mut self) -> Result<Stream> {
+        Ok(interprocess::local_socket::traits::tokio::Listener::accept(self).await?)
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    io::ErrorKind,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::DuplexStream,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+};
+
+use crate::{
+    error::Result,
+    transport::{IpcListener, IpcTransport},
+};
+
+/// Size of the in memory buffer of each direction of a connection.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// In process transport, intended to test the protocols without touching the OS.
+/// Clones share the same address, so one clone can be used to listen and others to connect.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTransport {
+    server: Arc<Mutex<Option<UnboundedSender<DuplexStream>>>>,
+}
+
+pub struct MemoryListener {
+    incoming: UnboundedReceiver<DuplexStream>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IpcTransport for MemoryTransport {
+    type Stream = DuplexStream;
+    type Listener = MemoryListener;
+
+    fn listen(&self) -> Result<MemoryListener> {
+        let (tx, incoming) = mpsc::unbounded_channel();
+        *self.server.lock().unwrap() = Some(tx);
+        Ok(MemoryListener { incoming })
+    }
+
+    async fn connect(&self) -> Result<DuplexStream> {
+        let server = self.server.lock().unwrap().clone();
+        let (client, server_side) = tokio::io::duplex(BUFFER_SIZE);
+        match server {
+            Some(server) if server.send(server_side).is_ok() => Ok(client),
+            _ => Err(std::io::Error::from(ErrorKind::ConnectionRefused).into()),
+        }
+    }
+}
+
+impl IpcListener for MemoryListener {
+    type Stream = DuplexStream;
+
+    async fn accept(&mut self) -> Result<DuplexStream> {
+        self.incoming
+            .recv()
+            .await
+            .ok_or_else(|| std::io::Error::from(ErrorKind::BrokenPipe).into())
+    }
+}
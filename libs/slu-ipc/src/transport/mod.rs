@@ -0,0 +1,41 @@
+//! Transports carrying the framed IPC messages. The protocols don't depend on how the bytes
+//! travel, so the same servers and clients can run over named pipes, local sockets or in memory.
+
+mod local_socket;
+mod memory;
+#[cfg(windows)]
+mod named_pipe;
+
+pub use local_socket::LocalSocketTransport;
+pub use memory::MemoryTransport;
+#[cfg(windows)]
+pub use named_pipe::NamedPipeTransport;
+
+use std::future::Future;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::error::Result;
+
+/// Bidirectional byte stream of a single connection.
+pub trait IpcStream: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> IpcStream for T {}
+
+/// Server side of a transport, yields a stream per connected client.
+pub trait IpcListener: Send + 'static {
+    type Stream: IpcStream;
+
+    fn accept(&mut self) -> impl Future<Output = Result<Self::Stream>> + Send;
+}
+
+/// Address of an IPC server and the way to reach it.
+pub trait IpcTransport: Send + Sync {
+    type Stream: IpcStream;
+    type Listener: IpcListener<Stream = Self::Stream>;
+
+    /// Binds the server side of the transport.
+    fn listen(&self) -> Result<Self::Listener>;
+
+    /// Opens a new connection to the server.
+    fn connect(&self) -> impl Future<Output = Result<Self::Stream>> + Send;
+}
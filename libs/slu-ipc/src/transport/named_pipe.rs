@@ -0,0 +1,48 @@
+use interprocess::os::windows::named_pipe::{
+    PipeListenerOptions,
+    pipe_mode::Bytes,
+    tokio::{DuplexPipeStream, PipeListener},
+};
+
+use crate::{
+    common::create_security_descriptor,
+    error::Result,
+    transport::{IpcListener, IpcTransport},
+};
+
+/// Transport over a Windows named pipe accessible from every user of the machine.
+#[derive(Debug, Clone)]
+pub struct NamedPipeTransport {
+    path: String,
+}
+
+impl NamedPipeTransport {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl IpcTransport for NamedPipeTransport {
+    type Stream = DuplexPipeStream<Bytes>;
+    type Listener = PipeListener<Bytes, Bytes>;
+
+    fn listen(&self) -> Result<Self::Listener> {
+        let sd = create_security_descriptor()?;
+        Ok(PipeListenerOptions::new()
+            .path(self.path.clone())
+            .security_descriptor(Some(sd))
+            .create_tokio_duplex::<Bytes>()?)
+    }
+
+    async fn connect(&self) -> Result<Self::Stream> {
+        Ok(DuplexPipeStream::connect_by_path(self.path.clone()).await?)
+    }
+}
+
+impl IpcListener for PipeListener<Bytes, Bytes> {
+    type Stream = DuplexPipeStream<Bytes>;
+
+    async fn accept(&mut self) -> Result<Self::Stream> {
+        Ok(PipeListener::accept(self).await?)
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::atomic::Ordering;
 
-use slu_ipc::messages::SvcAction;
+use slu_ipc::{messages::SvcAction, AppIpc};
 use tauri::{AppHandle, Emitter, Wry};
 use windows::Win32::System::TaskScheduler::{ITaskService, TaskScheduler};
 
@@ -46,6 +46,7 @@ where
         // log::debug!("Skipping event {event} because session is not active");
         return;
     }
+    AppIpc::broadcast(event, &payload).log_error();
     get_app_handle().emit(event, payload).log_error();
 }
 
@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use slu_ipc::commands::BatchCli;
+
+use crate::{
+    error::Result,
+    virtual_desktops::{events::VirtualDesktopEvent, SluWorkspacesManager2},
+    widgets::window_manager::state_v2::{TwmState, TwmStateEvent},
+};
+
+static ACTIVE_BATCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// While a batch is running the window manager and workspaces are not rendered,
+/// a single render of the final state is requested once the batch ends.
+pub fn is_batching() -> bool {
+    ACTIVE_BATCHES.load(Ordering::Acquire) > 0
+}
+
+struct BatchGuard;
+
+impl BatchGuard {
+    fn start() -> Self {
+        ACTIVE_BATCHES.fetch_add(1, Ordering::AcqRel);
+        Self
+    }
+}
+
+impl Drop for BatchGuard {
+    fn drop(&mut self) {
+        if ACTIVE_BATCHES.fetch_sub(1, Ordering::AcqRel) == 1 {
+            TwmState::send(TwmStateEvent::Changed);
+            SluWorkspacesManager2::send(VirtualDesktopEvent::StateChanged);
+        }
+    }
+}
+
+pub async fn process(cmd: BatchCli) -> Result<()> {
+    // nothing runs if any of the commands is invalid
+    let commands = cmd.parse_commands()?;
+
+    let _guard = BatchGuard::start();
+    for (idx, command) in commands.into_iter().enumerate() {
+        // boxed as batches are processed by `process_app_command` too
+        if let Err(err) = Box::pin(super::process_app_command(command)).await {
+            if !cmd.keep_going {
+                return Err(format!("batch stopped at command {}: {err}", idx + 1).into());
+            }
+            log::warn!("batch command {} failed: {err}", idx + 1);
+        }
+    }
+    Ok(())
+}
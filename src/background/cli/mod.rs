@@ -1,4 +1,6 @@
+pub mod batch;
 mod debugger;
+mod query;
 mod self_pipe;
 pub mod shortcuts;
 mod svc_pipe;
@@ -9,7 +11,11 @@ pub use svc_pipe::ServicePipe;
 
 use std::sync::atomic::Ordering;
 
-use slu_ipc::{commands::AppCommand, messages::AppMessage, AppIpc};
+use slu_ipc::{
+    commands::AppCommand,
+    messages::{AppMessage, IpcResponse},
+    AppIpc,
+};
 
 use crate::{
     error::Result,
@@ -65,6 +71,17 @@ pub async fn handle_console_client() -> Result<()> {
     Ok(())
 }
 
+/// Same as [`process_app_command`] but also handles the commands that answer with data.
+pub async fn process_app_request(cmd: AppCommand) -> Result<IpcResponse> {
+    match cmd {
+        AppCommand::Query(query) => Ok(IpcResponse::Data(query::process(query)?)),
+        cmd => {
+            process_app_command(cmd).await?;
+            Ok(IpcResponse::Success)
+        }
+    }
+}
+
 pub async fn process_app_command(cmd: AppCommand) -> Result<()> {
     match cmd {
         AppCommand::Settings => {
@@ -97,6 +114,9 @@ pub async fn process_app_command(cmd: AppCommand) -> Result<()> {
         AppCommand::Wallpaper(command) => {
             wallpaper_cli::process(command)?;
         }
+        AppCommand::Batch(command) => {
+            batch::process(command).await?;
+        }
         AppCommand::ToggleShortcutsPause => {
             shortcuts::toggle_pause()?;
         }
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use seelen_core::{
+    resource::{ResourceId, ResourceKind, SluResource},
+    state::{AppConfig, TwmRuntimeTree, VirtualDesktops, WorkspaceId},
+    system_state::FocusedApp,
+};
+use serde::Serialize;
+use slu_ipc::commands::{QueryCli, QueryCommand};
+
+use crate::{
+    error::Result, resources::RESOURCES, virtual_desktops::SluWorkspacesManager2,
+    widgets::window_manager::state_v2::WM_STATE, windows_api::window::Window,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceTree {
+    workspace_id: WorkspaceId,
+    /// `None` if the window manager didn't create a tree for the workspace yet.
+    tree: Option<TwmRuntimeTree>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FocusedWindow {
+    window: FocusedApp,
+    app_config: Option<AppConfig>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceSummary {
+    kind: ResourceKind,
+    id: ResourceId,
+    path: PathBuf,
+    bundled: bool,
+}
+
+impl ResourceSummary {
+    fn of<R: SluResource>(id: &ResourceId, resource: &R) -> Self {
+        let internal = &resource.metadata().internal;
+        Self {
+            kind: R::KIND,
+            id: id.clone(),
+            path: internal.path.clone(),
+            bundled: internal.bundled,
+        }
+    }
+}
+
+pub fn process(cmd: QueryCli) -> Result<serde_json::Value> {
+    let value = match cmd.subcommand {
+        QueryCommand::Workspaces => {
+            let desktops: VirtualDesktops = SluWorkspacesManager2::instance().into();
+            serde_json::to_value(desktops)?
+        }
+        QueryCommand::Tree => serde_json::to_value(focused_workspace_tree()?)?,
+        QueryCommand::FocusedWindow => {
+            let window = Window::get_foregrounded();
+            serde_json::to_value(FocusedWindow {
+                window: window.as_focused_app_information(),
+                app_config: window.get_app_config().ok().flatten(),
+            })?
+        }
+        QueryCommand::Resources { kind } => {
+            serde_json::to_value(loaded_resources(kind.map(Into::into)))?
+        }
+    };
+    Ok(value)
+}
+
+fn focused_workspace_tree() -> Result<WorkspaceTree> {
+    let monitor_id = Window::get_foregrounded().monitor_id();
+    let workspace_id = SluWorkspacesManager2::instance()
+        .monitors
+        .get(&monitor_id, |m| m.active_workspace_id().clone())
+        .ok_or("Monitor not found")?;
+    let tree = WM_STATE.lock().state.workspaces.get(&workspace_id).cloned();
+    Ok(WorkspaceTree { workspace_id, tree })
+}
+
+fn loaded_resources(kind: Option<ResourceKind>) -> Vec<ResourceSummary> {
+    let wanted = |k: ResourceKind| kind.is_none_or(|kind| kind == k);
+    let mut list = Vec::new();
+
+    if wanted(ResourceKind::Theme) {
+        list.extend(
+            RESOURCES
+                .themes()
+                .iter()
+                .map(|r| ResourceSummary::of(&r.id, r.as_ref())),
+        );
+    }
+    if wanted(ResourceKind::Plugin) {
+        list.extend(
+            RESOURCES
+                .plugins()
+                .iter()
+                .map(|r| ResourceSummary::of(&r.id, r.as_ref())),
+        );
+    }
+    if wanted(ResourceKind::Widget) {
+        list.extend(
+            RESOURCES
+                .widgets()
+                .iter()
+                .map(|r| ResourceSummary::of(&r.id, r.as_ref())),
+        );
+    }
+    if wanted(ResourceKind::IconPack) {
+        list.extend(
+            RESOURCES
+                .icon_packs()
+                .iter()
+                .map(|r| ResourceSummary::of(&r.id, r.as_ref())),
+        );
+    }
+    if wanted(ResourceKind::Wallpaper) {
+        list.extend(
+            RESOURCES
+                .wallpapers()
+                .iter()
+                .map(|r| ResourceSummary::of(&r.id, r.as_ref())),
+        );
+    }
+    list
+}
@@ -6,16 +6,16 @@ use slu_ipc::{
 };
 
 use crate::{
-    cli::{process_app_command, uri::process_uri},
+    cli::{process_app_request, uri::process_uri},
     error::{Result, ResultLogExt},
     modules::system_tray::SystemTrayManager,
 };
 
 pub struct SelfPipe;
 impl SelfPipe {
-    async fn handle_raw_cli_message(argv: Vec<String>) -> Result<()> {
+    async fn handle_raw_cli_message(argv: Vec<String>) -> Result<IpcResponse> {
         if argv.is_empty() {
-            return Ok(());
+            return Ok(IpcResponse::Success);
         }
 
         // Normalize argv: always use a fixed program name as argv[0] for clap.
@@ -33,26 +33,28 @@ impl SelfPipe {
             };
 
         if let Ok(cli) = AppCli::try_parse_from(normalized) {
-            if let Err(err) = process_app_command(cli.command).await {
+            return process_app_request(cli.command).await.inspect_err(|err| {
                 log::error!("Failed to process command: {err}");
-                return Err(err);
-            }
+            });
         }
-        Ok(())
+        Ok(IpcResponse::Success)
     }
 
     async fn handle_message(message: AppMessage) -> IpcResponse {
         match message {
             AppMessage::Cli(argv) => {
-                if let Err(err) = Self::handle_raw_cli_message(argv).await {
-                    return IpcResponse::Err(err.to_string());
-                }
+                return Self::handle_raw_cli_message(argv)
+                    .await
+                    .unwrap_or_else(|err| IpcResponse::Err(err.to_string()));
             }
             AppMessage::Command(cmd) => {
-                if let Err(err) = process_app_command(cmd).await {
-                    log::error!("Failed to process command: {err}");
-                    return IpcResponse::Err(err.to_string());
-                }
+                return match process_app_request(cmd).await {
+                    Ok(response) => response,
+                    Err(err) => {
+                        log::error!("Failed to process command: {err}");
+                        IpcResponse::Err(err.to_string())
+                    }
+                };
             }
             AppMessage::OpenUri(uri) => {
                 tokio::spawn(async move {
@@ -63,6 +65,8 @@ impl SelfPipe {
                 SystemTrayManager::handle_tray_event(event);
             }
             AppMessage::Debug(_msg) => {}
+            // subscriptions are handled by the pipe listener
+            AppMessage::Subscribe { .. } => {}
         }
 
         IpcResponse::Success
@@ -40,6 +40,11 @@ pub async fn process_uri(uri: &str) -> Result<()> {
         }
 
         let file = SluResourceFile::load(&path).await?;
+        if let Err(err) = check_resource_trust(&file) {
+            trigger_dialog_backend(error_dialog(Uuid::new_v4(), format!("{err:?}").as_str()))
+                .log_error();
+            return Err(err);
+        }
         store_file_on_respective_user_folder(&file).await?;
 
         let dialog_id = Uuid::new_v4();
@@ -191,7 +196,9 @@ async fn _download_resource(url: &str) -> Result<SluResourceFile> {
         return Err(status.into());
     }
 
-    let file = res.json::<SluResourceFile>().await?;
+    let mut file = res.json::<SluResourceFile>().await?;
+    file.verify_signature()?;
+    check_resource_trust(&file)?;
     let saved_path = store_file_on_respective_user_folder(&file).await?;
 
     if file.resource.kind == ResourceKind::IconPack {
@@ -207,6 +214,26 @@ async fn _download_resource(url: &str) -> Result<SluResourceFile> {
     Ok(file)
 }
 
+/// Applies the policy of the user to the checked signature before installing the resource.
+fn check_resource_trust(file: &SluResourceFile) -> Result<()> {
+    let state = FULL_STATE.load();
+    let trust = &state.settings.resource_trust;
+    trust.check(file)?;
+    match trust.publisher_of(&file.signature_status) {
+        Some(publisher) => log::info!(
+            "Resource {} is signed by trusted publisher {}",
+            file.resource.slug,
+            publisher.name
+        ),
+        None => log::debug!(
+            "Resource {} is {:?}",
+            file.resource.slug,
+            file.signature_status
+        ),
+    }
+    Ok(())
+}
+
 fn update_dialog_to_added_resource(dialog_id: Uuid, resource: &Resource) -> Result<()> {
     let config = resource_to_dialog(dialog_id, resource)?;
     trigger_dialog_backend(config)?;
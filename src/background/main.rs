@@ -46,6 +46,7 @@ use crate::{
     app::get_app_handle,
     error::ResultLogExt,
     utils::{constants::SEELEN_COMMON, CRONOMETER},
+    widgets::window_manager::state_v2::TwmState,
 };
 
 static APP_HANDLE: OnceLock<tauri::AppHandle<tauri::Wry>> = OnceLock::new();
@@ -207,6 +208,7 @@ fn app_callback(_: &tauri::AppHandle<tauri::Wry>, event: tauri::RunEvent) {
             None => api.prevent_exit(),
         },
         tauri::RunEvent::Exit => {
+            TwmState::flush_snapshots();
             log::info!("───────────────────── Exiting Seelen UI ─────────────────────");
         }
         _ => {}
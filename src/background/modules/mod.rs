@@ -18,6 +18,10 @@ pub mod user;
 #[macro_export]
 macro_rules! event_manager {
     ($name:ident, $event:ty) => {
+        $crate::event_manager!($name, $event, |_event: &$event| false);
+    };
+    // events matching `$skip` at send time are dropped before reaching the subscribers
+    ($name:ident, $event:ty, $skip:expr) => {
         static CHANNEL: std::sync::LazyLock<(
             crossbeam_channel::Sender<$event>,
             crossbeam_channel::Receiver<$event>,
@@ -51,6 +55,9 @@ macro_rules! event_manager {
             }
 
             pub fn send(event: $event) {
+                if ($skip)(&event) {
+                    return;
+                }
                 Self::_init_thread();
                 if let Err(e) = CHANNEL.0.send(event) {
                     log::error!("Failed to send event: {e}");
@@ -106,6 +106,14 @@ impl ResourceManager {
                 let mut widget = Widget::load(path).await?;
                 widget.metadata.internal.bundled =
                     path.starts_with(SEELEN_COMMON.bundled_widgets_path());
+                // also for widgets not installed through the app or before enabling the policy
+                if !widget.metadata.internal.bundled {
+                    FULL_STATE.load().settings.resource_trust.check_status(
+                        &ResourceKind::Widget,
+                        &*widget.id,
+                        &widget.metadata.internal.signature,
+                    )?;
+                }
 
                 widget
                     .plugins
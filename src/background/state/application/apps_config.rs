@@ -1,4 +1,4 @@
-use seelen_core::state::{AppConfig, AppsConfigurationList};
+use seelen_core::state::{AppConfig, AppExtraFlag, AppsConfigurationList};
 
 use crate::{
     error::Result,
@@ -31,6 +31,13 @@ impl Window {
             .search(&title, &class, &exe, &path)
             .cloned())
     }
+
+    pub fn has_app_flag(&self, flag: &AppExtraFlag) -> bool {
+        self.get_app_config()
+            .ok()
+            .flatten()
+            .is_some_and(|config| config.options.contains(flag))
+    }
 }
 
 impl AppSettings {
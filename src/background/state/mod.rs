@@ -7,13 +7,13 @@ use seelen_core::{
     state::{
         twm::TwmPlugin,
         value::{KnownPlugin, PluginValue},
-        WorkspaceId,
+        WindowManagerSettings, WorkspaceId,
     },
     system_state::MonitorId,
 };
 use uuid::Uuid;
 
-use crate::resources::RESOURCES;
+use crate::{resources::RESOURCES, virtual_desktops::SluWorkspacesManager2};
 
 impl AppSettings {
     pub fn is_widget_enabled(&self, widget_id: &WidgetId) -> bool {
@@ -74,39 +74,24 @@ impl AppSettings {
         *layout.clone()
     }
 
-    pub fn get_wm_layout_id(&self, _workspace_id: &WorkspaceId) -> PluginId {
-        let mut default = self.settings.by_widget.wm.default_layout.clone();
-        if !default.is_valid() {
-            default = "@default/wm-bspwm".into();
+    pub fn get_wm_layout_id(&self, workspace_id: &WorkspaceId) -> PluginId {
+        let layout = self.get_wm_settings(workspace_id).default_layout;
+        if layout.is_valid() {
+            layout
+        } else {
+            "@default/wm-bspwm".into()
         }
+    }
 
-        /* let Ok(id) = monitor.stable_id() else {
-            return default;
-        }; */
-
-        /* let config = match self.settings.monitors_v3.get(&id) {
-            Some(config) => config,
-            None => return default,
-        };
-
-        let workspace = match config.workspaces_v2.get(workspace_idx) {
-            Some(workspace) => workspace,
-            None => return default,
-        };
-
-        match &workspace.layout {
-            Some(layout_id) => {
-                let layout_id: PluginId = layout_id.as_str().into();
-                if layout_id.is_valid() {
-                    layout_id
-                } else {
-                    default
-                }
-            }
-            None => default,
-        } */
-
-        default
+    /// Window manager settings of the workspace, resolving the overrides of the workspace and its monitor
+    pub fn get_wm_settings(&self, workspace_id: &WorkspaceId) -> WindowManagerSettings {
+        match SluWorkspacesManager2::instance()
+            .workspace_index
+            .get(workspace_id, |id| id.clone())
+        {
+            Some(monitor_id) => self.settings.wm_settings_for(&monitor_id, workspace_id),
+            None => self.settings.by_widget.wm.clone(),
+        }
     }
 
     pub fn locale(&self) -> &String {
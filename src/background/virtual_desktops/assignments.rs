@@ -0,0 +1,33 @@
+use seelen_core::state::{WindowIdentity, WorkspaceId};
+
+use crate::{virtual_desktops::SluWorkspacesManager2, windows_api::window::Window};
+
+/// Identity used to recognize the window after a restart, `None` if the executable is unknown.
+pub fn window_identity(window: &Window) -> Option<WindowIdentity> {
+    let path = window.process().program_path().ok()?;
+    Some(WindowIdentity {
+        path: path.to_string_lossy().to_string(),
+        umid: window.app_user_model_id().map(|umid| umid.to_string()),
+        class: window.class(),
+        title: window.title(),
+    })
+}
+
+impl SluWorkspacesManager2 {
+    /// Workspace the window was on in a previous session, if it matches a remembered one.
+    /// The assignment is claimed so the next window of the same app doesn't take it too.
+    pub(super) fn take_assigned_workspace(&self, window: &Window) -> Option<WorkspaceId> {
+        let identity = window_identity(window)?;
+        self.monitors.with_lock(|monitors| {
+            let (monitor_id, (_, workspace_id, idx)) = monitors
+                .iter()
+                .filter_map(|(id, monitor)| Some((id, monitor.find_assignment(&identity)?)))
+                .max_by_key(|(_, (score, ..))| *score)?;
+            let monitor_id = monitor_id.clone();
+            let monitor = monitors.get_mut(&monitor_id)?;
+            monitor.claim_assignment(&workspace_id, idx, window.address());
+            log::trace!("{window} was on workspace {workspace_id} in a previous session");
+            Some(workspace_id)
+        })
+    }
+}
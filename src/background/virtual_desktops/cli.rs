@@ -1,11 +1,16 @@
-use seelen_core::{state::WorkspaceId, system_state::MonitorId};
+use seelen_core::{
+    state::{GridDirection, GridNeighbor, VirtualDesktopMonitor, WorkspaceId},
+    system_state::MonitorId,
+};
 pub use slu_ipc::commands::VirtualDesktopCli;
-use slu_ipc::commands::{Direction, DirectionOrIndex, VdCommand};
+use slu_ipc::commands::{Direction, VdCommand, WorkspaceTarget};
 
 use crate::{
     error::Result,
+    state::application::FULL_STATE,
     virtual_desktops::SluWorkspacesManager2,
-    windows_api::{input::Mouse, monitor::Monitor, window::Window},
+    widgets::window_manager::cli::get_neartest_monitor_at_side,
+    windows_api::{input::Mouse, monitor::Monitor, window::Window, MonitorEnumerator},
 };
 
 pub fn process(cmd: VirtualDesktopCli) -> Result<()> {
@@ -21,78 +26,143 @@ fn cursor_monitor_id() -> Result<MonitorId> {
     Monitor::from(&point).stable_id()
 }
 
-/// Id of the workspace neighboring the given monitor's active workspace in the
-/// grid, in the given direction. `None` if there is no workspace on that side.
-fn neighbor_workspace_id(
+fn grid_direction(direction: Direction) -> GridDirection {
+    match direction {
+        Direction::Left => GridDirection::Left,
+        Direction::Right => GridDirection::Right,
+        Direction::Up => GridDirection::Up,
+        Direction::Down => GridDirection::Down,
+    }
+}
+
+/// Workspace neighboring the given monitor's active workspace in the grid, in the given
+/// direction, following the edge policy. `None` if there is nothing to switch to.
+fn neighbor_workspace(
     vd: &SluWorkspacesManager2,
     monitor_id: &MonitorId,
     direction: Direction,
-) -> Result<Option<WorkspaceId>> {
-    vd.monitors.with_lock(|monitors| {
-        let monitor = monitors.get(monitor_id).ok_or("Monitor not found")?;
-        let (row, col) = monitor
-            .workspaces
-            .position(monitor.active_workspace_id())
-            .ok_or("Active workspace not found")?;
-
-        let (desired_row, desired_col) = match direction {
-            Direction::Left => (row, col.saturating_sub(1)),
-            Direction::Right => (row, col + 1),
-            Direction::Up => (row.saturating_sub(1), col),
-            Direction::Down => (row + 1, col),
-        };
-
-        Result::Ok(
-            monitor
-                .workspaces
-                .get(desired_row, desired_col)
-                .map(|w| w.id.clone()),
-        )
-    })
+) -> Result<Option<(MonitorId, WorkspaceId)>> {
+    match vd.get_neighbor_desktop(monitor_id, grid_direction(direction))? {
+        GridNeighbor::Workspace(id) => Ok(Some((monitor_id.clone(), id))),
+        GridNeighbor::Edge => Ok(None),
+        GridNeighbor::NextMonitor => {
+            let Some(monitor) = MonitorEnumerator::enumerate_win32()?
+                .into_iter()
+                .find(|m| m.stable_id().is_ok_and(|id| &id == monitor_id))
+            else {
+                return Ok(None);
+            };
+            let Some(next) = get_neartest_monitor_at_side(&monitor, direction)? else {
+                return Ok(None);
+            };
+            let next_id = next.stable_id()?;
+            Ok(vd
+                .monitors
+                .get(&next_id, |m| m.active_workspace_id().clone())
+                .map(|id| (next_id, id)))
+        }
+    }
 }
 
-/// Id of the workspace targeted by a `DirectionOrIndex`: either the neighbor in the
-/// given direction, or the workspace at the given index in the active row.
-fn target_workspace_id(
+/// Workspace targeted by a command and the monitor owning it. `None` if there is nothing
+/// in the given direction or at the given index.
+///
+/// `default_monitor` is used for the relative targets unless a monitor was given, names are
+/// searched on it first.
+fn resolve_target(
     vd: &SluWorkspacesManager2,
-    monitor_id: &MonitorId,
-    target: DirectionOrIndex,
-) -> Result<Option<WorkspaceId>> {
-    match target {
-        DirectionOrIndex::Direction(direction) => neighbor_workspace_id(vd, monitor_id, direction),
-        DirectionOrIndex::Index(index) => vd
-            .monitors
-            .get(monitor_id, |monitor| {
-                monitor.active_row().get(index).map(|w| w.id.clone())
-            })
-            .ok_or_else(|| "Monitor not found".into()),
+    default_monitor: MonitorId,
+    monitor: Option<String>,
+    target: WorkspaceTarget,
+) -> Result<Option<(MonitorId, WorkspaceId)>> {
+    let explicit_monitor = monitor.map(MonitorId::from);
+    if let Some(monitor_id) = &explicit_monitor {
+        if !vd.monitors.contains_key(monitor_id) {
+            return Err(format!("Monitor {monitor_id} not found").into());
+        }
     }
+    let monitor_id = explicit_monitor.clone().unwrap_or(default_monitor);
+
+    let workspace_id = match target {
+        WorkspaceTarget::Direction(direction) => {
+            return neighbor_workspace(vd, &monitor_id, direction);
+        }
+        WorkspaceTarget::Index(index) => {
+            let order = FULL_STATE.load().settings.virtual_desktops.overview_order;
+            vd.monitors
+                .get(&monitor_id, |monitor| {
+                    monitor.workspace_at_index(index, order)
+                })
+                .ok_or("Monitor not found")?
+        }
+        WorkspaceTarget::Cell { row, col } => {
+            Some(vd.get_or_create_desktop_at(&monitor_id, row, col)?)
+        }
+        WorkspaceTarget::Id(id) => {
+            let workspace_id = WorkspaceId::from(id);
+            let owner = vd
+                .workspace_index
+                .get(&workspace_id, |m| m.clone())
+                .ok_or_else(|| format!("Workspace {workspace_id} not found"))?;
+            if explicit_monitor.is_some_and(|m| m != owner) {
+                return Err(
+                    format!("Workspace {workspace_id} is not on monitor {monitor_id}").into(),
+                );
+            }
+            return Ok(Some((owner, workspace_id)));
+        }
+        WorkspaceTarget::Name(name) => {
+            let find = |monitor: &VirtualDesktopMonitor| {
+                monitor.workspaces.get_by_name(&name).map(|w| w.id.clone())
+            };
+            let found = vd.monitors.with_lock(|monitors| {
+                if let Some(id) = monitors.get(&monitor_id).and_then(&find) {
+                    return Some((monitor_id.clone(), id));
+                }
+                if explicit_monitor.is_some() {
+                    return None;
+                }
+                monitors
+                    .iter()
+                    .find_map(|(m, monitor)| find(monitor).map(|id| (m.clone(), id)))
+            });
+            return match found {
+                Some(found) => Ok(Some(found)),
+                None => Err(format!("Workspace named {name:?} not found").into()),
+            };
+        }
+    };
+    Ok(workspace_id.map(|id| (monitor_id, id)))
 }
 
 fn process_vd_command(cmd: VdCommand) -> Result<()> {
     let vd = SluWorkspacesManager2::instance();
 
     match cmd {
-        VdCommand::SwitchTo { direction } => {
-            let monitor_id = cursor_monitor_id()?;
-            if let Some(target_workspace_id) = target_workspace_id(vd, &monitor_id, direction)? {
-                vd.switch_to_id(&monitor_id, &target_workspace_id)?;
+        VdCommand::SwitchTo { target, monitor } => {
+            let default_monitor = cursor_monitor_id()?;
+            if let Some((monitor_id, workspace_id)) =
+                resolve_target(vd, default_monitor, monitor, target)?
+            {
+                vd.switch_to_id(&monitor_id, &workspace_id)?;
             }
         }
-        VdCommand::SendTo { direction } => {
+        VdCommand::SendTo { target, monitor } => {
             let focused_win = Window::get_foregrounded();
-            let monitor_id = focused_win.monitor().stable_id()?;
-            if let Some(target_workspace_id) = target_workspace_id(vd, &monitor_id, direction)? {
-                vd.send_to(&focused_win, &target_workspace_id)?;
+            let default_monitor = focused_win.monitor().stable_id()?;
+            if let Some((_, workspace_id)) = resolve_target(vd, default_monitor, monitor, target)? {
+                vd.send_to(&focused_win, &workspace_id)?;
             }
         }
-        VdCommand::MoveTo { direction } => {
+        VdCommand::MoveTo { target, monitor } => {
             let focused_win = Window::get_foregrounded();
-            let monitor_id = focused_win.monitor().stable_id()?;
-            if let Some(target_workspace_id) = target_workspace_id(vd, &monitor_id, direction)? {
-                vd.send_to(&focused_win, &target_workspace_id)?;
+            let default_monitor = focused_win.monitor().stable_id()?;
+            if let Some((monitor_id, workspace_id)) =
+                resolve_target(vd, default_monitor, monitor, target)?
+            {
+                vd.send_to(&focused_win, &workspace_id)?;
                 std::thread::sleep(std::time::Duration::from_millis(20));
-                vd.switch_to_id(&monitor_id, &target_workspace_id)?;
+                vd.switch_to_id(&monitor_id, &workspace_id)?;
             }
         }
         VdCommand::CreateNewWorkspace => {
@@ -113,6 +183,21 @@ fn process_vd_command(cmd: VdCommand) -> Result<()> {
                 .ok_or("Monitor not found")?;
             vd.destroy_desktop(&monitor_id, &workspace_id)?;
         }
+        VdCommand::CreateFromTemplate { name, monitor } => {
+            let template = FULL_STATE
+                .load()
+                .settings
+                .workspace_templates
+                .iter()
+                .find(|t| t.name.eq_ignore_ascii_case(&name))
+                .cloned()
+                .ok_or_else(|| format!("Workspace template {name:?} not found"))?;
+            let monitor_id = match monitor {
+                Some(monitor) => MonitorId::from(monitor),
+                None => cursor_monitor_id()?,
+            };
+            vd.create_from_template(&monitor_id, &template)?;
+        }
     }
     Ok(())
 }
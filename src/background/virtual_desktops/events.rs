@@ -15,7 +15,8 @@ pub enum VirtualDesktopEvent {
         monitor: MonitorId,
         workspace: WorkspaceId,
     },
-    /// Emitted when the virtual desktops state changes (e.g., wallpapers updated)
+    /// Emitted when the virtual desktops state changes (e.g., wallpapers updated),
+    /// the webviews are only rendered on it.
     StateChanged,
     // DesktopNameChanged(WorkspaceId, String),
     /* DesktopMoved {
@@ -18,15 +18,16 @@ use crate::{
 fn get_vd_manager() -> &'static SluWorkspacesManager2 {
     static TAURI_EVENT_REGISTRATION: Once = Once::new();
     TAURI_EVENT_REGISTRATION.call_once(|| {
-        SluWorkspacesManager2::subscribe(|event| {
+        SluWorkspacesManager2::subscribe(|event| match event {
             // As switching is atomic operation the data was send via the event to avoid waiting for switch end.
-            if let VirtualDesktopEvent::SwitchingDesktop(payload) = event {
+            VirtualDesktopEvent::SwitchingDesktop(payload) => {
                 emit_to_webviews(SeelenEvent::VirtualDesktopsChanged, payload);
-                return;
             }
-
-            let payload: VirtualDesktops = SluWorkspacesManager2::instance().into();
-            emit_to_webviews(SeelenEvent::VirtualDesktopsChanged, payload);
+            VirtualDesktopEvent::StateChanged => {
+                let payload: VirtualDesktops = SluWorkspacesManager2::instance().into();
+                emit_to_webviews(SeelenEvent::VirtualDesktopsChanged, payload);
+            }
+            _ => {}
         });
     });
 
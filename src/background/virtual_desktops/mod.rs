@@ -1,6 +1,9 @@
+mod assignments;
 pub mod cli;
 pub mod events;
 pub mod handlers;
+mod scratchpads;
+pub mod templates;
 pub mod wallpapers;
 
 use std::collections::HashMap;
@@ -9,18 +12,27 @@ use std::io::Write;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::LazyLock;
 
-use seelen_core::state::{DesktopWorkspace, VirtualDesktopMonitor, VirtualDesktops, WorkspaceId};
+use seelen_core::handlers::SeelenEvent;
+use seelen_core::state::{
+    DesktopWorkspace, GridDirection, GridNeighbor, VirtualDesktopMonitor, VirtualDesktops,
+    WorkspaceId,
+};
 use seelen_core::system_state::MonitorId;
 use slu_utils::{debounce, Debounce};
+use tauri::Listener;
 use windows::Win32::UI::WindowsAndMessaging::{SW_FORCEMINIMIZE, SW_MINIMIZE, SW_RESTORE};
 
+use crate::app::get_app_handle;
+use crate::cli::batch::is_batching;
 use crate::error::{Result, ResultLogExt};
 use crate::event_manager;
 use crate::hook::HookManager;
 use crate::modules::apps::application::{UserAppWinEvent, UserAppsManager};
 use crate::modules::monitors::{MonitorManager, MonitorManagerEvent};
+use crate::state::application::FULL_STATE;
 use crate::utils::constants::SEELEN_COMMON;
 use crate::utils::lock_free::{SyncHashMap, SyncVec};
+use crate::virtual_desktops::assignments::window_identity;
 use crate::virtual_desktops::wallpapers::WorkspaceWallpapersManager;
 use crate::windows_api::window::event::WinEvent;
 use crate::windows_api::window::Window;
@@ -51,13 +63,25 @@ pub struct SluWorkspacesManager2 {
     pub monitors: SyncHashMap<MonitorId, VirtualDesktopMonitor>,
     pub workspace_index: SyncHashMap<WorkspaceId, MonitorId>,
     pub pinned: SyncVec<isize>,
+    pub scratchpads: SyncHashMap<String, DesktopWorkspace>,
     /// Count of in-flight `switch_to_id` calls. Used instead of a bool so that
     /// concurrent switches (e.g. on different monitors) don't race: the last
     /// one to finish is the only one allowed to clear the "switching" state.
     switching: AtomicU32,
 }
 
-event_manager!(SluWorkspacesManager2, VirtualDesktopEvent);
+// a batch renders once on its end, see `cli::batch`
+event_manager!(
+    SluWorkspacesManager2,
+    VirtualDesktopEvent,
+    |event: &VirtualDesktopEvent| {
+        is_batching()
+            && matches!(
+                event,
+                VirtualDesktopEvent::StateChanged | VirtualDesktopEvent::SwitchingDesktop(_)
+            )
+    }
+);
 
 impl SluWorkspacesManager2 {
     pub fn instance() -> &'static Self {
@@ -76,7 +100,8 @@ impl SluWorkspacesManager2 {
             debounce(
                 |_| {
                     let fun = || {
-                        let state: VirtualDesktops = SluWorkspacesManager2::instance().into();
+                        let mut state: VirtualDesktops = SluWorkspacesManager2::instance().into();
+                        state.refresh_assignments(|w| window_identity(&Window::from(w)));
                         let path = SEELEN_COMMON.app_cache_dir().join("workspaces2.json");
                         let mut file = std::fs::File::create(path)?;
                         file.write_all(&serde_json::to_vec(&state)?)?;
@@ -96,7 +121,12 @@ impl SluWorkspacesManager2 {
     fn create() -> Self {
         let mut manager = Self::from(match Self::load_stored() {
             Ok(mut state) => {
-                state.sanitize();
+                state.sanitize(FULL_STATE.load().settings.virtual_desktops.ragged_rows);
+                // handles are reused, so the identity is checked to not take other apps windows
+                state.release_assignments(|addr, identity| {
+                    window_identity(&Window::from(addr))
+                        .is_some_and(|current| current.similarity(identity).is_some())
+                });
                 state
             }
             Err(_) => Default::default(),
@@ -105,9 +135,30 @@ impl SluWorkspacesManager2 {
 
         // Initialize wallpaper manager and set initial wallpapers
         wallpapers::WorkspaceWallpapersManager::init(&manager);
+
+        get_app_handle().listen(SeelenEvent::StateSettingsChanged, |_| {
+            Self::instance().on_settings_changed();
+        });
         manager
     }
 
+    /// Grids left ragged are padded once ragged rows are disabled,
+    /// as the rest of operations expect rows of the same length.
+    fn on_settings_changed(&self) {
+        if FULL_STATE.load().settings.virtual_desktops.ragged_rows {
+            return;
+        }
+
+        let mut grids = Vec::new();
+        self.monitors.for_each(|(monitor_id, monitor)| {
+            monitor.sanitize();
+            grids.push((monitor_id.clone(), Self::grid_ids(monitor)));
+        });
+        for (monitor_id, grid_ids) in grids {
+            self.index_created_desktops(&monitor_id, grid_ids);
+        }
+    }
+
     /// TODO: try to move windows on others native virtual desktops to only one,
     /// or add a warning message to users.
     fn initialize(&mut self) -> Result<()> {
@@ -136,6 +187,12 @@ impl SluWorkspacesManager2 {
             }
         });
 
+        // saved handles could have been reused by other windows since the last session,
+        // so scratchpads are refilled from the app configs on the scan below.
+        self.scratchpads.for_each(|(_, scratchpad)| {
+            scratchpad.windows.clear();
+        });
+
         // create monitors
         for id in MonitorManager::instance().get_cached_ids() {
             if self.monitors.contains_key(&id) {
@@ -144,12 +201,20 @@ impl SluWorkspacesManager2 {
             self.monitors.upsert(id, VirtualDesktopMonitor::create());
         }
 
-        // scan no added windows, but only add the non minimized ones to the current active workspace
+        // scan no added windows, sending back the ones remembered from a previous session,
+        // but only add the non minimized others to the current active workspace
         UserAppsManager::instance()
             .interactable_windows
             .for_each(|data| {
                 let window = Window::from(data.hwnd);
-                if !self.contains(&window) && !window.is_minimized() {
+                if self.contains(&window) {
+                    return;
+                }
+                if let Some(name) = Self::scratchpad_of(&window) {
+                    self.add_to_scratchpad(&window, &name);
+                } else if let Some(workspace_id) = self.take_assigned_workspace(&window) {
+                    self.add_to_workspace(&window, &workspace_id);
+                } else if !window.is_minimized() {
                     self.add_to_current_workspace(&window);
                 }
             });
@@ -172,7 +237,18 @@ impl SluWorkspacesManager2 {
 
         UserAppsManager::subscribe(|event| match event {
             UserAppWinEvent::Added(addr) => {
-                Self::instance().add_to_current_workspace(&Window::from(addr));
+                let window = Window::from(addr);
+                let manager = Self::instance();
+                match Self::scratchpad_of(&window) {
+                    Some(name) => manager.add_to_scratchpad(&window, &name),
+                    None => match manager
+                        .take_template_window(&window)
+                        .or_else(|| manager.take_assigned_workspace(&window))
+                    {
+                        Some(workspace_id) => manager.add_to_workspace(&window, &workspace_id),
+                        None => manager.add_to_current_workspace(&window),
+                    },
+                }
             }
             UserAppWinEvent::Removed(addr) => {
                 Self::instance().remove(&Window::from(addr));
@@ -187,6 +263,21 @@ impl SluWorkspacesManager2 {
         Ok(())
     }
 
+    /// Sends the event followed by a `StateChanged`, the one the webviews are rendered on.
+    fn send_and_render(event: VirtualDesktopEvent) {
+        Self::send(event);
+        Self::send(VirtualDesktopEvent::StateChanged);
+    }
+
+    /// Name of the scratchpad the window belongs to, as set on its app config.
+    fn scratchpad_of(window: &Window) -> Option<String> {
+        window
+            .get_app_config()
+            .ok()
+            .flatten()
+            .and_then(|config| config.scratchpad)
+    }
+
     fn on_win_event(event: WinEvent, window: Window) -> Result<()> {
         let window_id = window.address();
         match event {
@@ -216,7 +307,9 @@ impl SluWorkspacesManager2 {
                     let monitor_id = manager.get_monitor_of_workspace(&workspace_id);
                     // Restore workspace if the window was unminimized by the user via alt+tab or others
                     manager.switch_to_id(&monitor_id, &workspace_id)?;
-                } else if !manager.is_pinned(&window_id) && window.is_interactable_and_not_hidden()
+                } else if !manager.is_pinned(&window_id)
+                    && !manager.is_on_scratchpad(&window_id)
+                    && window.is_interactable_and_not_hidden()
                 {
                     // Add minimized windows during the scanning, to the current active workspace
                     manager.add_to_current_workspace(&window);
@@ -318,7 +411,7 @@ impl SluWorkspacesManager2 {
 
     fn contains(&self, window: &Window) -> bool {
         let window_id = window.address();
-        self.is_pinned(&window_id) || {
+        self.is_pinned(&window_id) || self.is_on_scratchpad(&window_id) || {
             self.monitors
                 .any(|(_, monitor)| monitor.workspaces.get_by_window_id(window_id).is_some())
         }
@@ -359,7 +452,7 @@ impl SluWorkspacesManager2 {
             self.workspace_index
                 .upsert(workspace_id.clone(), monitor_id);
 
-            Self::send(VirtualDesktopEvent::WindowAdded {
+            Self::send_and_render(VirtualDesktopEvent::WindowAdded {
                 window: window_id,
                 desktop: workspace_id,
             });
@@ -371,8 +464,11 @@ impl SluWorkspacesManager2 {
         let window_id = window.address();
         log::trace!("Removing {window} from workspaces");
 
-        // Remove from pinned list
+        // Remove from pinned list and scratchpads
         self.pinned.retain(|w| w != &window_id);
+        self.scratchpads.for_each(|(_, scratchpad)| {
+            scratchpad.windows.retain(|w| w != &window_id);
+        });
 
         // Remove from all workspaces
         self.monitors.for_each(|(_, monitor)| {
@@ -383,7 +479,7 @@ impl SluWorkspacesManager2 {
             }
         });
 
-        Self::send(VirtualDesktopEvent::WindowRemoved { window: window_id });
+        Self::send_and_render(VirtualDesktopEvent::WindowRemoved { window: window_id });
         self.request_save();
     }
 
@@ -402,6 +498,7 @@ impl SluWorkspacesManager2 {
             Self::send(VirtualDesktopEvent::SwitchingDesktop(VirtualDesktops {
                 monitors: monitors.clone(),
                 pinned: self.pinned.to_vec(),
+                scratchpads: self.scratchpads.to_hash_map(),
                 switching: true,
             }));
 
@@ -411,7 +508,7 @@ impl SluWorkspacesManager2 {
             monitor.active_workspace().restore();
 
             log::trace!("Switched to workspace {workspace_id} on monitor {monitor_id}");
-            Self::send(VirtualDesktopEvent::DesktopChanged {
+            Self::send_and_render(VirtualDesktopEvent::DesktopChanged {
                 monitor: monitor_id.clone(),
                 workspace: workspace_id.clone(),
             });
@@ -426,7 +523,7 @@ impl SluWorkspacesManager2 {
             // if it's still > 0, another concurrent switch is still in flight and
             // the "switching" state must remain true.
             if self.switching.fetch_sub(1, Ordering::SeqCst) == 1 {
-                Self::send(VirtualDesktopEvent::SwitchingFinished);
+                Self::send_and_render(VirtualDesktopEvent::SwitchingFinished);
             }
         }
 
@@ -469,7 +566,7 @@ impl SluWorkspacesManager2 {
                     let _ = MINIMIZED_BY_WORKSPACES.insert_sync(window_id);
                 }
 
-                Self::send(VirtualDesktopEvent::WindowMoved {
+                Self::send_and_render(VirtualDesktopEvent::WindowMoved {
                     window: window_id,
                     desktop: workspace_id.clone(),
                 });
@@ -479,13 +576,17 @@ impl SluWorkspacesManager2 {
             .ok_or("Monitor not found")?
     }
 
-    /// Create a new workspace column (or row) on a specific monitor
+    /// Create a new workspace column (or row) on a specific monitor.
+    /// With ragged rows enabled, new columns are only added to the active row.
     pub fn create_desktop(&self, monitor_id: &MonitorId, as_row: bool) -> Result<WorkspaceId> {
+        let ragged = FULL_STATE.load().settings.virtual_desktops.ragged_rows;
         let workspace_id = self
             .monitors
             .get(monitor_id, |monitor| {
                 if as_row {
                     monitor.add_workspace_row()
+                } else if ragged {
+                    monitor.add_workspace_to_row()
                 } else {
                     monitor.add_workspace_column()
                 }
@@ -497,22 +598,97 @@ impl SluWorkspacesManager2 {
         // Set wallpaper to the new workspace
         WorkspaceWallpapersManager::update_workspace_wallpapers_internal(self);
 
-        Self::send(VirtualDesktopEvent::DesktopCreated(workspace_id.clone()));
+        Self::send_and_render(VirtualDesktopEvent::DesktopCreated(workspace_id.clone()));
         self.request_save();
         Ok(workspace_id)
     }
 
+    /// Id of the workspace at the given cell of a monitor, creating the missing rows and columns
+    pub fn get_or_create_desktop_at(
+        &self,
+        monitor_id: &MonitorId,
+        row: usize,
+        col: usize,
+    ) -> Result<WorkspaceId> {
+        let ragged = FULL_STATE.load().settings.virtual_desktops.ragged_rows;
+        let (workspace_id, grid_ids) = self
+            .monitors
+            .get(monitor_id, |monitor| {
+                let workspace_id = monitor.ensure_workspace_at(row, col, ragged)?;
+                Result::Ok((workspace_id, Self::grid_ids(monitor)))
+            })
+            .ok_or("Monitor not found")??;
+        self.index_created_desktops(monitor_id, grid_ids);
+        Ok(workspace_id)
+    }
+
+    /// Workspace next to the active one of the monitor, following the grid settings.
+    /// Workspaces created by the edge policy are indexed before returning.
+    pub fn get_neighbor_desktop(
+        &self,
+        monitor_id: &MonitorId,
+        direction: GridDirection,
+    ) -> Result<GridNeighbor> {
+        let settings = FULL_STATE.load().settings.virtual_desktops.clone();
+        let (neighbor, grid_ids) = self
+            .monitors
+            .get(monitor_id, |monitor| {
+                let neighbor =
+                    monitor.neighbor(direction, settings.edge_policy, settings.ragged_rows);
+                (neighbor, Self::grid_ids(monitor))
+            })
+            .ok_or("Monitor not found")?;
+        self.index_created_desktops(monitor_id, grid_ids);
+        Ok(neighbor)
+    }
+
+    fn grid_ids(monitor: &VirtualDesktopMonitor) -> Vec<WorkspaceId> {
+        monitor
+            .workspaces
+            .rows()
+            .iter()
+            .flatten()
+            .map(|w| w.id.clone())
+            .collect()
+    }
+
+    /// Indexes the workspaces of the grid that were created in place, notifying them
+    fn index_created_desktops(&self, monitor_id: &MonitorId, grid_ids: Vec<WorkspaceId>) {
+        let created: Vec<WorkspaceId> = grid_ids
+            .into_iter()
+            .filter(|id| !self.workspace_index.contains_key(id))
+            .collect();
+        if created.is_empty() {
+            return;
+        }
+
+        for id in &created {
+            self.workspace_index.upsert(id.clone(), monitor_id.clone());
+        }
+        WorkspaceWallpapersManager::update_workspace_wallpapers_internal(self);
+        for id in created {
+            Self::send(VirtualDesktopEvent::DesktopCreated(id));
+        }
+        Self::send(VirtualDesktopEvent::StateChanged);
+        self.request_save();
+    }
+
     /// Destroy a workspace on a specific monitor
     pub fn destroy_desktop(
         &self,
         monitor_id: &MonitorId,
         workspace_id: &WorkspaceId,
     ) -> Result<()> {
+        let ragged = FULL_STATE.load().settings.virtual_desktops.ragged_rows;
         self.monitors
             .get(monitor_id, |monitor| {
                 let was_active = monitor.active_workspace_id() == workspace_id;
                 // Remove the workspace (this moves windows to the previous workspace)
-                monitor.remove_workspace(workspace_id)?;
+                if ragged {
+                    monitor.remove_workspace_cell(workspace_id)?;
+                } else {
+                    monitor.remove_workspace(workspace_id)?;
+                }
                 // If the removed workspace was active, restore the new active workspace
                 if was_active {
                     monitor.active_workspace().restore();
@@ -523,7 +699,26 @@ impl SluWorkspacesManager2 {
 
         // Remove from workspace index
         self.workspace_index.remove(workspace_id);
-        Self::send(VirtualDesktopEvent::DesktopDestroyed(workspace_id.clone()));
+
+        // settings of the workspace (e.g. added by its template) would be never used again
+        let has_settings = FULL_STATE
+            .load()
+            .settings
+            .monitors_v3
+            .get(monitor_id)
+            .is_some_and(|config| config.by_workspace.contains_key(workspace_id));
+        if has_settings {
+            FULL_STATE.rcu(|state| {
+                let mut state = state.cloned();
+                if let Some(config) = state.settings.monitors_v3.get_mut(monitor_id) {
+                    config.by_workspace.remove(workspace_id);
+                }
+                state
+            });
+            FULL_STATE.load().write_settings()?;
+        }
+
+        Self::send_and_render(VirtualDesktopEvent::DesktopDestroyed(workspace_id.clone()));
         self.request_save();
         Ok(())
     }
@@ -540,6 +735,7 @@ impl SluWorkspacesManager2 {
                 monitor.rename_workspace(workspace_id, name)
             })
             .ok_or("Monitor not found")??;
+        Self::send(VirtualDesktopEvent::StateChanged);
         self.request_save();
         Ok(())
     }
@@ -560,6 +756,7 @@ impl From<VirtualDesktops> for SluWorkspacesManager2 {
             monitors: SyncHashMap::from(value.monitors),
             workspace_index: SyncHashMap::from(workspace_index),
             pinned: SyncVec::from(value.pinned),
+            scratchpads: SyncHashMap::from(value.scratchpads),
             switching: AtomicU32::new(0),
         }
     }
@@ -570,6 +767,7 @@ impl From<&SluWorkspacesManager2> for VirtualDesktops {
         Self {
             monitors: value.monitors.to_hash_map(),
             pinned: value.pinned.to_vec(),
+            scratchpads: value.scratchpads.to_hash_map(),
             switching: value.switching.load(Ordering::SeqCst) > 0,
         }
     }
@@ -0,0 +1,85 @@
+use seelen_core::state::DesktopWorkspace;
+
+use crate::{
+    error::Result,
+    virtual_desktops::{
+        events::VirtualDesktopEvent, DesktopWorkspaceExt, SluWorkspacesManager2,
+        MINIMIZED_BY_WORKSPACES,
+    },
+    windows_api::window::Window,
+};
+
+impl SluWorkspacesManager2 {
+    pub fn is_on_scratchpad(&self, window_id: &isize) -> bool {
+        self.scratchpads
+            .any(|(_, scratchpad)| scratchpad.windows.contains(window_id))
+    }
+
+    pub fn scratchpad_windows(&self, name: &str) -> Vec<isize> {
+        self.scratchpads
+            .get(name, |scratchpad| scratchpad.windows.clone())
+            .unwrap_or_default()
+    }
+
+    /// Adds a not tracked window to the scratchpad, creating it if needed, and hides the scratchpad.
+    pub(super) fn add_to_scratchpad(&self, window: &Window, name: &str) {
+        let window_id = window.address();
+        log::trace!("adding {window} to scratchpad {name}");
+        self.scratchpads.get_or_insert(
+            name.to_string(),
+            || DesktopWorkspace {
+                name: Some(name.to_string()),
+                ..DesktopWorkspace::create()
+            },
+            |scratchpad| {
+                if !scratchpad.windows.contains(&window_id) {
+                    scratchpad.windows.push(window_id);
+                }
+                scratchpad.hide(true);
+            },
+        );
+        Self::send(VirtualDesktopEvent::StateChanged);
+        self.request_save();
+    }
+
+    /// Moves the window out of its workspace (or another scratchpad) to the named scratchpad.
+    pub fn send_to_scratchpad(&self, window: &Window, name: &str) -> Result<()> {
+        if !self.contains(window) {
+            return Err(format!("{window} is not tracked by the workspaces").into());
+        }
+        self.remove(window);
+        self.add_to_scratchpad(window, name);
+        Ok(())
+    }
+
+    /// Moves the window out of its scratchpad to the current workspace of its monitor.
+    pub fn release_from_scratchpad(&self, window: &Window) {
+        let window_id = window.address();
+        if !self.is_on_scratchpad(&window_id) {
+            return;
+        }
+
+        self.scratchpads.for_each(|(_, scratchpad)| {
+            scratchpad.windows.retain(|w| w != &window_id);
+        });
+        MINIMIZED_BY_WORKSPACES.remove_sync(&window_id);
+        self.add_to_current_workspace(window);
+    }
+
+    /// Shows all the windows of the scratchpad, including the ones minimized by the user.
+    pub fn show_scratchpad(&self, name: &str) {
+        self.scratchpads.get(name, |scratchpad| {
+            for addr in &scratchpad.windows {
+                if Window::from(*addr).is_minimized() {
+                    let _ = MINIMIZED_BY_WORKSPACES.insert_sync(*addr);
+                }
+            }
+            scratchpad.restore();
+        });
+    }
+
+    pub fn hide_scratchpad(&self, name: &str) {
+        self.scratchpads
+            .get(name, |scratchpad| scratchpad.hide(true));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use seelen_core::{
+    state::{
+        twm::TwmReservation,
+        workspace_templates::{WorkspaceTemplate, WorkspaceTemplateApp},
+        WorkspaceId,
+    },
+    system_state::MonitorId,
+};
+use windows::Win32::UI::WindowsAndMessaging::SW_MINIMIZE;
+
+use crate::{
+    error::{Result, ResultLogExt},
+    state::application::FULL_STATE,
+    utils::lock_free::SyncHashMap,
+    virtual_desktops::{
+        events::VirtualDesktopEvent, SluWorkspacesManager2, MINIMIZED_BY_WORKSPACES,
+    },
+    windows_api::{window::Window, WindowsApi},
+};
+
+/// Apps that are launched but whose window didn't appear yet are not waited for longer than this.
+const PENDING_APP_TIMEOUT: Duration = Duration::from_secs(60);
+
+static PENDING_APPS: LazyLock<Mutex<Vec<PendingApp>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// Places requested by the templates for windows already added to their workspace,
+/// taken by the window manager once it adds them to the layout.
+static PLACEMENTS: LazyLock<SyncHashMap<isize, TemplatePlacement>> =
+    LazyLock::new(SyncHashMap::new);
+
+struct PendingApp {
+    app: WorkspaceTemplateApp,
+    workspace_id: WorkspaceId,
+    launched_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TemplatePlacement {
+    pub node: Option<usize>,
+    pub side: Option<TwmReservation>,
+}
+
+impl TemplatePlacement {
+    /// Place requested for the window by a workspace template, if any.
+    pub fn take(window: &Window) -> Option<Self> {
+        PLACEMENTS.remove(&window.address())
+    }
+}
+
+impl SluWorkspacesManager2 {
+    /// Creates a workspace from the template, switches to it and launches the apps of the template,
+    /// their windows are sent to the workspace as they appear.
+    pub fn create_from_template(
+        &self,
+        monitor_id: &MonitorId,
+        template: &WorkspaceTemplate,
+    ) -> Result<WorkspaceId> {
+        let workspace_id = self.create_desktop(monitor_id, false)?;
+        self.monitors
+            .get(monitor_id, |monitor| {
+                if let Some(workspace) = monitor.workspaces.get_by_id_mut(&workspace_id) {
+                    workspace.name = Some(template.name.clone());
+                    workspace.icon = template.icon.clone();
+                }
+            })
+            .ok_or("Monitor not found")?;
+
+        FULL_STATE.rcu(|state| {
+            let mut state = state.cloned();
+            state
+                .settings
+                .monitors_v3
+                .entry(monitor_id.clone())
+                .or_default()
+                .by_workspace
+                .insert(workspace_id.clone(), template.config.clone());
+            state
+        });
+        FULL_STATE.load().write_settings()?;
+
+        {
+            let mut pending = PENDING_APPS.lock();
+            pending.retain(|p| p.launched_at.elapsed() < PENDING_APP_TIMEOUT);
+            pending.extend(template.apps.iter().map(|app| PendingApp {
+                app: app.clone(),
+                workspace_id: workspace_id.clone(),
+                launched_at: Instant::now(),
+            }));
+        }
+
+        self.switch_to_id(monitor_id, &workspace_id)?;
+        for app in &template.apps {
+            let args = app.args.as_ref().map(|args| args.to_string());
+            WindowsApi::execute(app.program.clone(), args, None, false).log_error();
+        }
+
+        Self::send(VirtualDesktopEvent::StateChanged);
+        self.request_save();
+        Ok(workspace_id)
+    }
+
+    /// Workspace of the template that launched the app of the window, if it is still waited for.
+    /// Each launched app claims only the first matching window.
+    pub(super) fn take_template_window(&self, window: &Window) -> Option<WorkspaceId> {
+        let mut pending = PENDING_APPS.lock();
+        pending.retain(|p| p.launched_at.elapsed() < PENDING_APP_TIMEOUT);
+        if pending.is_empty() {
+            return None;
+        }
+
+        let path = window.process().program_path().ok()?;
+        let exe = path.file_name()?.to_string_lossy();
+        let path = path.to_string_lossy();
+        let (title, class) = (window.title(), window.class());
+
+        let idx = pending.iter().position(|p| {
+            self.workspace_index.contains_key(&p.workspace_id)
+                && p.app.identifier.is_match(&title, &class, &exe, &path)
+        })?;
+        let PendingApp {
+            app, workspace_id, ..
+        } = pending.remove(idx);
+
+        if app.node.is_some() || app.side.is_some() {
+            let placement = TemplatePlacement {
+                node: app.node,
+                side: app.side,
+            };
+            PLACEMENTS.upsert(window.address(), placement);
+        }
+        Some(workspace_id)
+    }
+
+    /// Adds a not tracked window to the given workspace.
+    pub(super) fn add_to_workspace(&self, window: &Window, workspace_id: &WorkspaceId) {
+        let window_id = window.address();
+        let added = self
+            .workspace_index
+            .get(workspace_id, |monitor_id| monitor_id.clone())
+            .and_then(|monitor_id| {
+                self.monitors.get(&monitor_id, |monitor| {
+                    let is_active = monitor.active_workspace_id() == workspace_id;
+                    let workspace = monitor.workspaces.get_by_id_mut(workspace_id)?;
+                    if !workspace.windows.contains(&window_id) {
+                        workspace.windows.push(window_id);
+                    }
+                    Some(is_active)
+                })
+            })
+            .flatten();
+
+        let Some(is_active) = added else {
+            // the workspace was destroyed in the meantime
+            self.add_to_current_workspace(window);
+            return;
+        };
+
+        log::trace!("adding {window} to workspace {workspace_id}");
+        if !is_active {
+            window.show_window(SW_MINIMIZE).ok();
+            let _ = MINIMIZED_BY_WORKSPACES.insert_sync(window_id);
+        }
+        Self::send_and_render(VirtualDesktopEvent::WindowAdded {
+            window: window_id,
+            desktop: workspace_id.clone(),
+        });
+        self.request_save();
+    }
+}
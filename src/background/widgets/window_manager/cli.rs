@@ -1,7 +1,10 @@
-use slu_ipc::commands::AllowedReservations;
+use slu_ipc::commands::{AllowedReservations, Rotation, ScratchpadAction, TabSide, WmLayoutPreset};
 pub use slu_ipc::commands::{Axis, Direction, Sizing, StepWay, WindowManagerCli, WmCommand};
 
-use seelen_core::state::twm::TwmReservation;
+use seelen_core::state::{
+    twm::{TwmPlugin, TwmReservation},
+    WorkspaceId,
+};
 
 use crate::error::Result;
 use crate::state::application::FULL_STATE;
@@ -24,6 +27,30 @@ fn to_wm_reservation(side: AllowedReservations) -> TwmReservation {
     }
 }
 
+fn to_twm_plugin(layout: WmLayoutPreset) -> TwmPlugin {
+    match layout {
+        WmLayoutPreset::MasterStack {
+            master_count,
+            ratio,
+        } => TwmPlugin::master_stack(master_count, ratio),
+        WmLayoutPreset::Dwindle => TwmPlugin::dwindle(),
+        WmLayoutPreset::Spiral => TwmPlugin::spiral(),
+        WmLayoutPreset::CenteredMaster { ratio } => TwmPlugin::centered_master(ratio),
+        WmLayoutPreset::Grid { columns } => TwmPlugin::grid(columns),
+        WmLayoutPreset::Monocle => TwmPlugin::monocle(),
+        WmLayoutPreset::Scrollable { column_width } => TwmPlugin::scrollable(column_width),
+    }
+}
+
+fn get_active_workspace_id(foreground: &Window) -> Result<WorkspaceId> {
+    let monitor_id = foreground.monitor_id();
+    let workspace_id = SluWorkspacesManager2::instance()
+        .monitors
+        .get(&monitor_id, |m| m.active_workspace_id().clone())
+        .ok_or("Monitor not found")?;
+    Ok(workspace_id)
+}
+
 pub fn process(cmd: WindowManagerCli) -> Result<()> {
     process_wm_command(cmd.subcommand)
 }
@@ -74,6 +101,31 @@ fn process_wm_command(cmd: WmCommand) -> Result<()> {
                 }
             }
         }
+        WmCommand::SetSize { axis, percentage } => {
+            WM_STATE.lock().set_size(&foreground, axis, percentage)?;
+        }
+        WmCommand::Equalize { workspace } => {
+            WM_STATE
+                .lock()
+                .arrange_container(&foreground, workspace, |tree, id, recursive| {
+                    tree.equalize(id, recursive)
+                });
+        }
+        WmCommand::Rotate { way, workspace } => {
+            let clockwise = way == Rotation::Clockwise;
+            WM_STATE
+                .lock()
+                .arrange_container(&foreground, workspace, |tree, id, recursive| {
+                    tree.rotate(id, clockwise, recursive)
+                });
+        }
+        WmCommand::Flip { workspace } => {
+            WM_STATE
+                .lock()
+                .arrange_container(&foreground, workspace, |tree, id, recursive| {
+                    tree.flip(id, recursive)
+                });
+        }
         WmCommand::ToggleFloat => {
             let mut state = WM_STATE.lock();
             if !state.is_managed(&foreground) {
@@ -91,13 +143,28 @@ fn process_wm_command(cmd: WmCommand) -> Result<()> {
             TwmState::send(TwmStateEvent::Changed);
         }
         WmCommand::ToggleMonocle => {
-            let monitor_id = foreground.monitor_id();
-            let workspace_id = SluWorkspacesManager2::instance()
-                .monitors
-                .get(&monitor_id, |m| m.active_workspace_id().clone())
-                .ok_or("Monitor not found")?;
+            let workspace_id = get_active_workspace_id(&foreground)?;
             WM_STATE.lock().toggle_monocle(&workspace_id);
         }
+        WmCommand::SetLayout { layout } => {
+            let workspace_id = get_active_workspace_id(&foreground)?;
+            WM_STATE
+                .lock()
+                .set_layout(&workspace_id, Some(to_twm_plugin(layout)));
+        }
+        WmCommand::ResetLayout => {
+            let workspace_id = get_active_workspace_id(&foreground)?;
+            WM_STATE.lock().set_layout(&workspace_id, None);
+        }
+        WmCommand::SaveLayout => {
+            let workspace_id = get_active_workspace_id(&foreground)?;
+            let source = WM_STATE.lock().snapshot_source(&workspace_id)?;
+            TwmState::save_snapshot(&workspace_id, source)?;
+        }
+        WmCommand::RestoreLayout => {
+            let workspace_id = get_active_workspace_id(&foreground)?;
+            WM_STATE.lock().restore_snapshot(&workspace_id)?;
+        }
         WmCommand::Focus { side } | WmCommand::Move { side } => {
             let window_id = foreground.address();
             let fg_rect = foreground.inner_rect()?;
@@ -127,9 +194,17 @@ fn process_wm_command(cmd: WmCommand) -> Result<()> {
             if is_moving {
                 if let Some((_, tree)) = guard.get_tree_for_window_mut(&foreground) {
                     tree.swap_nodes_by_windows(window_id, target_id);
+                    tree.scroll_to_window(&window_id);
                     TwmState::send(TwmStateEvent::Changed);
                 }
             } else {
+                // the target can be on a column scrolled out of the viewport (minimized)
+                if let Some((_, tree)) = guard.get_tree_for_window_mut(&foreground) {
+                    if tree.scroll_to_window(&target_id) {
+                        TwmState::send(TwmStateEvent::Changed);
+                    }
+                }
+                drop(guard);
                 Window::from(target_id).focus()?;
             }
         }
@@ -139,11 +214,68 @@ fn process_wm_command(cmd: WmCommand) -> Result<()> {
         WmCommand::CycleStack { way } => {
             WM_STATE.lock().cycle_stack(&foreground, way)?;
         }
+        WmCommand::MoveTab { side } => {
+            WM_STATE
+                .lock()
+                .move_stack_tab(&foreground, side == TabSide::Right);
+        }
+        WmCommand::PullTab { side } => {
+            let side = match side {
+                Direction::Left => TwmReservation::Left,
+                Direction::Right => TwmReservation::Right,
+                Direction::Up => TwmReservation::Top,
+                Direction::Down => TwmReservation::Bottom,
+            };
+            WM_STATE.lock().pull_stack_tab(&foreground, side);
+        }
+        WmCommand::Scratchpad { action } => {
+            process_scratchpad(&foreground, action)?;
+        }
     };
 
     Ok(())
 }
 
+fn process_scratchpad(foreground: &Window, action: ScratchpadAction) -> Result<()> {
+    let vd = SluWorkspacesManager2::instance();
+    match action {
+        ScratchpadAction::Toggle { name } => {
+            let windows = vd.scratchpad_windows(&name);
+            if windows.is_empty() {
+                log::warn!("Scratchpad {name} has no windows");
+                return Ok(());
+            }
+
+            let mut state = WM_STATE.lock();
+            for w in &windows {
+                state.remove(&Window::from(*w));
+            }
+
+            // a shown but unfocused scratchpad is brought to the current monitor instead of hidden
+            if windows.contains(&foreground.address()) {
+                vd.hide_scratchpad(&name);
+            } else {
+                let monitor = foreground.monitor();
+                let workspace_id = get_active_workspace_id(foreground)?;
+                vd.show_scratchpad(&name);
+                for w in windows {
+                    let window = Window::from(w);
+                    state.add_to_floating(&window, &workspace_id);
+                    twm_set_rect_to_float_initial_size(&window, &monitor)?;
+                }
+            }
+            TwmState::send(TwmStateEvent::Changed);
+        }
+        ScratchpadAction::Send { name } => {
+            vd.send_to_scratchpad(foreground, &name)?;
+        }
+        ScratchpadAction::Release => {
+            vd.release_from_scratchpad(foreground);
+        }
+    }
+    Ok(())
+}
+
 fn process_focus_to_monitor(foreground: &Window, side: Direction) -> Result<()> {
     let source_monitor = foreground.monitor();
 
@@ -7,6 +7,7 @@ use crate::{
     app::emit_to_webviews,
     cli::ServicePipe,
     error::Result,
+    modules::apps::application::UserAppsManager,
     state::application::{performance::PERFORMANCE_MODE, FULL_STATE},
     widgets::window_manager::state_v2::{TwmState, WM_STATE},
     windows_api::{window::Window, WindowsApi},
@@ -14,7 +15,7 @@ use crate::{
 use seelen_core::{
     handlers::SeelenEvent,
     rect::Rect,
-    state::{PerformanceMode, TwmGlobalRuntimeTree},
+    state::{PerformanceMode, TwmGlobalRuntimeTree, TwmStackTabs, WorkspaceId},
 };
 
 #[tauri::command(async)]
@@ -23,6 +24,12 @@ pub fn wm_get_render_tree() -> TwmGlobalRuntimeTree {
     TAURI_EVENT_REGISTRATION.call_once(|| {
         TwmState::subscribe(|_event| {
             let guard = WM_STATE.lock();
+            #[cfg(debug_assertions)]
+            for (workspace_id, tree) in &guard.state.workspaces {
+                if let Err(err) = tree.check_invariants() {
+                    log::error!("wm tree of workspace {workspace_id} is corrupted: {err}");
+                }
+            }
             guard.restore_stacks();
             emit_to_webviews(SeelenEvent::WMTreeChanged, &guard.state);
         });
@@ -31,6 +38,22 @@ pub fn wm_get_render_tree() -> TwmGlobalRuntimeTree {
     WM_STATE.lock().state.clone()
 }
 
+fn emit_stacks() {
+    emit_to_webviews(SeelenEvent::WMStacksChanged, WM_STATE.lock().stacks());
+}
+
+#[tauri::command(async)]
+pub fn wm_get_stacks() -> HashMap<WorkspaceId, Vec<TwmStackTabs>> {
+    static TAURI_EVENT_REGISTRATION: Once = Once::new();
+    TAURI_EVENT_REGISTRATION.call_once(|| {
+        TwmState::subscribe(|_event| emit_stacks());
+        // titles and icons can change without changes on the tree
+        UserAppsManager::subscribe(|_event| emit_stacks());
+    });
+
+    WM_STATE.lock().stacks()
+}
+
 /// Adds the shadow offset to `rect`, unmaximizing `window` first if needed. Returns `None` if
 /// the window is currently in a state that shouldn't be repositioned (closed, minimized, or
 /// being dragged by the user).
@@ -102,6 +102,11 @@ impl WindowManagerV2 {
                 if state.pending_reservation.is_some() {
                     state.cancel_reservation();
                 }
+                if let Some((_, tree)) = state.get_tree_for_window_mut(&window) {
+                    if tree.scroll_to_window(&window.address()) {
+                        TwmState::send(TwmStateEvent::Changed);
+                    }
+                }
             }
             WinEvent::SynThrottledForegroundRectChange => {
                 Self::synthetic_foreground_location_change(&window)?;
@@ -134,8 +139,17 @@ impl WindowManagerV2 {
         match event {
             VirtualDesktopEvent::DesktopChanged { .. } => {
                 state.cancel_reservation();
+                // the paused setting can be different on the new workspace
+                state.recompute_paused_by_monitor();
                 TwmState::send(TwmStateEvent::Changed);
             }
+            VirtualDesktopEvent::DesktopCreated(_)
+            | VirtualDesktopEvent::DesktopDestroyed(_)
+            | VirtualDesktopEvent::StateChanged => {
+                if state.refresh_environments() {
+                    TwmState::send(TwmStateEvent::Changed);
+                }
+            }
             VirtualDesktopEvent::WindowAdded { window, desktop } => {
                 let window = &Window::from(window);
                 if !state.is_managed(window) && Self::should_be_managed(window.hwnd()) {
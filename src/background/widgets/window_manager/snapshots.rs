@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
+
+use seelen_core::{
+    resource::PluginId,
+    state::{
+        TwmLayoutSnapshot, TwmRuntimeTree, TwmTreeSnapshot, TwmWindowInfo, WindowId, WorkspaceId,
+    },
+};
+use slu_utils::{debounce, Debounce};
+
+use crate::{
+    error::{Result, ResultLogExt},
+    utils::constants::SEELEN_COMMON,
+    widgets::window_manager::state_v2::{TwmState, TwmStateEvent, WM_STATE},
+    windows_api::window::Window,
+};
+
+/// What is needed to take the snapshot of a workspace, see [`TwmState::snapshot_source`].
+pub struct SnapshotSource {
+    tree: TwmRuntimeTree,
+    layout: Option<PluginId>,
+}
+
+/// Set when the trees changed since the last automatic save.
+static SNAPSHOTS_OUTDATED: AtomicBool = AtomicBool::new(false);
+
+impl TwmState {
+    fn snapshots_path() -> PathBuf {
+        SEELEN_COMMON.app_cache_dir().join("wm_layouts.json")
+    }
+
+    /// Exclusive lock over the snapshots, taken on a sibling file as the snapshots file is
+    /// replaced instead of written in place.
+    fn lock_snapshots() -> Result<File> {
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::snapshots_path().with_extension("json.lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    fn read_snapshots() -> Result<TwmLayoutSnapshot> {
+        let file = File::open(Self::snapshots_path())?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub(super) fn load_snapshots() -> Result<TwmLayoutSnapshot> {
+        let _lock = Self::lock_snapshots()?;
+        Self::read_snapshots()
+    }
+
+    /// Writes to a temporary file first, so a crash while writing doesn't corrupt the saved layouts.
+    /// Should be called with the lock held.
+    fn store_snapshots(snapshots: &TwmLayoutSnapshot) -> Result<()> {
+        let path = Self::snapshots_path();
+        let temp_path = path.with_extension("json.tmp");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(&serde_json::to_vec(snapshots)?)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    /// Saves the layouts a couple of seconds after the trees stop changing.
+    pub(super) fn enable_snapshots_autosave() {
+        static SAVE_DEBOUNCER: LazyLock<Debounce<()>> = LazyLock::new(|| {
+            debounce(
+                |_| TwmState::flush_snapshots(),
+                std::time::Duration::from_secs(2),
+            )
+        });
+
+        Self::subscribe(|event| match event {
+            TwmStateEvent::Changed => {
+                SNAPSHOTS_OUTDATED.store(true, Ordering::Release);
+                SAVE_DEBOUNCER.call(());
+            }
+        });
+    }
+
+    /// Saves the layouts of all workspaces if they changed since the last save,
+    /// also called on shutdown so pending changes are not lost.
+    pub fn flush_snapshots() {
+        if SNAPSHOTS_OUTDATED.swap(false, Ordering::AcqRel) {
+            let sources = WM_STATE.lock().snapshot_sources();
+            Self::save_all_snapshots(sources).log_error();
+        }
+    }
+
+    fn window_info(window_id: WindowId) -> Option<TwmWindowInfo> {
+        let window = Window::from(window_id);
+        let path = window.process().program_path().ok()?;
+        Some(TwmWindowInfo {
+            id: window_id,
+            title: window.title(),
+            class: window.class(),
+            exe: path.file_name()?.to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    /// Copy of the tree of the workspace and the layout it is linked to, so the windows are
+    /// queried and the snapshot written without holding the state.
+    pub fn snapshot_source(&self, workspace_id: &WorkspaceId) -> Result<SnapshotSource> {
+        let tree = self
+            .state
+            .workspaces
+            .get(workspace_id)
+            .ok_or("Workspace not found")?;
+
+        // layouts set via cli are not linked to a plugin
+        let layout = if self.is_layout_overridden(workspace_id) {
+            None
+        } else {
+            self.layout_cache.get(workspace_id).cloned()
+        };
+        Ok(SnapshotSource {
+            tree: tree.clone(),
+            layout,
+        })
+    }
+
+    fn snapshot_sources(&self) -> HashMap<WorkspaceId, SnapshotSource> {
+        self.state
+            .workspaces
+            .keys()
+            .filter_map(|id| Some((id.clone(), self.snapshot_source(id).ok()?)))
+            .collect()
+    }
+
+    fn take_snapshot(source: SnapshotSource) -> TwmTreeSnapshot {
+        let mut snapshot = source.tree.snapshot(Self::window_info);
+        snapshot.layout = source.layout;
+        snapshot
+    }
+
+    /// Saves the layout of the workspace, replacing the previous snapshot of it.
+    pub fn save_snapshot(workspace_id: &WorkspaceId, source: SnapshotSource) -> Result<()> {
+        let snapshot = Self::take_snapshot(source);
+
+        let _lock = Self::lock_snapshots()?;
+        let mut snapshots = Self::read_snapshots().unwrap_or_default();
+        snapshots.workspaces.insert(workspace_id.clone(), snapshot);
+        Self::store_snapshots(&snapshots)?;
+        log::trace!("wm layout of workspace {workspace_id} saved");
+        Ok(())
+    }
+
+    /// Saves the layouts of the given workspaces, snapshots of workspaces
+    /// that no longer exist are kept.
+    fn save_all_snapshots(sources: HashMap<WorkspaceId, SnapshotSource>) -> Result<()> {
+        let taken: Vec<_> = sources
+            .into_iter()
+            .map(|(id, source)| (id, Self::take_snapshot(source)))
+            .collect();
+
+        let _lock = Self::lock_snapshots()?;
+        let mut snapshots = Self::read_snapshots().unwrap_or_default();
+        snapshots.workspaces.extend(taken);
+        Self::store_snapshots(&snapshots)?;
+        log::trace!("wm layouts saved");
+        Ok(())
+    }
+
+    /// Restores the saved layout of the workspace, re-slotting its windows into their saved nodes.
+    pub fn restore_snapshot(&mut self, workspace_id: &WorkspaceId) -> Result<()> {
+        let snapshots = Self::load_snapshots()?;
+        let snapshot = snapshots
+            .workspaces
+            .get(workspace_id)
+            .ok_or("There is no saved layout for this workspace")?;
+        if !snapshot.is_valid() {
+            return Err("The saved layout is corrupted".into());
+        }
+
+        let current = self
+            .state
+            .workspaces
+            .get(workspace_id)
+            .ok_or("Workspace not found")?;
+        let tree = Self::tree_from_snapshot(workspace_id, current, snapshot)?;
+
+        self.monocle.remove(workspace_id);
+        self.layout_overrides.remove(workspace_id);
+        self.state.workspaces.insert(workspace_id.clone(), tree);
+        Self::send(TwmStateEvent::Changed);
+        Ok(())
+    }
+
+    /// Creates a tree from the snapshot with the windows of `current`, windows
+    /// that are not on the snapshot keep their tiled/floating state.
+    pub(super) fn tree_from_snapshot(
+        workspace_id: &WorkspaceId,
+        current: &TwmRuntimeTree,
+        snapshot: &TwmTreeSnapshot,
+    ) -> Result<TwmRuntimeTree> {
+        let mut unknown = Vec::new();
+        let mut windows = Vec::new();
+        for window_id in current.window_map.keys() {
+            match Self::window_info(*window_id) {
+                Some(info) => windows.push(info),
+                None => unknown.push(*window_id),
+            }
+        }
+
+        let (mut tree, remaining) = TwmRuntimeTree::from_snapshot(snapshot, &windows)?;
+        tree.environment = Self::get_environment(workspace_id);
+        for window_id in remaining.into_iter().chain(unknown) {
+            if current.is_floating(&window_id) {
+                tree.add_to_floating(window_id);
+            } else {
+                for residual in tree.add_to_tiled(window_id) {
+                    tree.add_to_floating(residual);
+                }
+            }
+        }
+        Ok(tree)
+    }
+}
@@ -8,21 +8,26 @@ use seelen_core::{
     rect::Rect,
     resource::PluginId,
     state::{
-        twm::{TwmNodeKind, TwmPlugin, TwmReservation, TwmStackPolicy},
-        NodeId, TwmGlobalRuntimeTree, TwmRuntimeTree, WindowLocation, WorkspaceId,
+        twm::{TwmEnvironment, TwmNodeKind, TwmPlugin, TwmReservation},
+        AppExtraFlag, NodeId, TwmGlobalRuntimeTree, TwmRuntimeTree, TwmStackTab, TwmStackTabs,
+        WindowId, WorkspaceId,
     },
 };
 use windows::Win32::UI::WindowsAndMessaging::SW_FORCEMINIMIZE;
 
 use crate::{
     app::emit_to_webviews,
+    cli::batch::is_batching,
     error::{Result, ResultLogExt},
     event_manager,
     hook::HookManager,
-    modules::apps::application::UserAppsManager,
+    modules::{
+        apps::application::UserAppsManager,
+        monitors::{MonitorManager, MonitorManagerEvent},
+    },
     state::application::FULL_STATE,
     utils::lock_free::TracedMutex,
-    virtual_desktops::SluWorkspacesManager2,
+    virtual_desktops::{templates::TemplatePlacement, SluWorkspacesManager2},
     widgets::window_manager::{
         cli::{Axis, Direction, StepWay},
         handler::{set_app_window_position, set_app_windows_positions},
@@ -33,6 +38,10 @@ use crate::{
 
 pub static MINIMIZED_BY_STACK: LazyLock<scc::HashSet<isize>> = LazyLock::new(scc::HashSet::new);
 
+/// Levels of the parent process chain searched for a swallowable app, apps launched from a
+/// terminal are usually children of the shell and not of the terminal itself.
+const SWALLOW_MAX_DEPTH: usize = 4;
+
 pub static WM_STATE: LazyLock<Arc<TracedMutex<TwmState>>> = LazyLock::new(|| {
     Arc::new(TracedMutex::new({
         let mut state = TwmState::default();
@@ -51,8 +60,10 @@ pub struct PendingReservation {
 pub struct TwmState {
     pub state: TwmGlobalRuntimeTree,
     pub monocle: HashMap<WorkspaceId, bool>,
+    /// Layouts set via cli, they take precedence over the configured layout of the workspace.
+    pub layout_overrides: HashMap<WorkspaceId, TwmPlugin>,
     pub pending_reservation: Option<PendingReservation>,
-    layout_cache: HashMap<WorkspaceId, PluginId>,
+    pub(super) layout_cache: HashMap<WorkspaceId, PluginId>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,29 +71,57 @@ pub enum TwmStateEvent {
     Changed,
 }
 
-event_manager!(TwmState, TwmStateEvent);
+// a batch renders once on its end, see `cli::batch`
+event_manager!(TwmState, TwmStateEvent, |_event: &TwmStateEvent| {
+    is_batching()
+});
 
 impl TwmState {
     fn initialize(&mut self) {
         let vd = SluWorkspacesManager2::instance();
+        // collected first, as creating the trees needs to read the workspaces manager
+        let mut workspaces = Vec::new();
         vd.monitors.for_each(|(_, monitor)| {
             for row in monitor.workspaces.rows() {
                 for workspace in row {
-                    let mut tree = Self::create_tree(&workspace.id);
-                    for &hwnd in &workspace.windows {
-                        let window = Window::from(hwnd);
-                        if WindowManagerV2::should_be_managed_ignoring_minimized(window.hwnd()) {
-                            let residual = tree.add_to_tiled(window.address());
-                            for w in residual {
-                                tree.add_to_floating(w);
-                            }
+                    workspaces.push((workspace.id.clone(), workspace.windows.clone()));
+                }
+            }
+        });
+
+        let settings = FULL_STATE.load();
+        let snapshots = Self::load_snapshots().unwrap_or_default();
+        for (workspace_id, windows) in workspaces {
+            let mut tree = Self::create_tree(&workspace_id);
+            for hwnd in windows {
+                let window = Window::from(hwnd);
+                if WindowManagerV2::should_be_managed_ignoring_minimized(window.hwnd()) {
+                    let residual = tree.add_to_tiled(window.address());
+                    for w in residual {
+                        tree.add_to_floating(w);
+                    }
+                }
+            }
+
+            // saved layouts are only restored if the workspace still uses the same layout
+            if let Some(snapshot) = snapshots.workspaces.get(&workspace_id) {
+                if snapshot.is_valid()
+                    && snapshot.layout.as_ref() == Some(&settings.get_wm_layout_id(&workspace_id))
+                {
+                    match Self::tree_from_snapshot(&workspace_id, &tree, snapshot) {
+                        Ok(restored) => tree = restored,
+                        Err(err) => {
+                            log::warn!(
+                                "saved wm layout of workspace {workspace_id} discarded: {err}"
+                            )
                         }
                     }
-                    self.state.workspaces.insert(workspace.id.clone(), tree);
                 }
             }
-        });
+            self.state.workspaces.insert(workspace_id, tree);
+        }
 
+        Self::enable_snapshots_autosave();
         SluWorkspacesManager2::subscribe(|event| {
             WindowManagerV2::process_vd_event(event).log_error();
         });
@@ -91,6 +130,15 @@ impl TwmState {
             WindowManagerV2::process_win_event(event, origin).log_error()
         });
 
+        MonitorManager::subscribe(|event| {
+            if event == MonitorManagerEvent::ViewsChanged {
+                let mut state = WM_STATE.lock();
+                if state.refresh_environments() {
+                    TwmState::send(TwmStateEvent::Changed);
+                }
+            }
+        });
+
         self.recompute_paused_by_monitor();
         UserAppsManager::subscribe(|_event| {
             let mut state = WM_STATE.lock();
@@ -99,7 +147,6 @@ impl TwmState {
             }
         });
 
-        let settings = FULL_STATE.load();
         for workspace_id in self.state.workspaces.keys() {
             self.layout_cache.insert(
                 workspace_id.clone(),
@@ -118,19 +165,85 @@ impl TwmState {
             }
 
             self.layout_cache.insert(workspace_id.clone(), new_id);
-            if self.monocle.get(&workspace_id).copied().unwrap_or(false) {
+            if self.is_layout_overridden(&workspace_id) {
                 continue;
             }
 
             let new_tree = Self::create_tree(&workspace_id);
             self.change_layout(&workspace_id, new_tree);
         }
+
+        if self.recompute_paused_by_monitor() {
+            Self::send(TwmStateEvent::Changed);
+        }
+    }
+
+    /// Whether the workspace is using monocle or a layout set via cli instead of the configured one.
+    pub fn is_layout_overridden(&self, workspace_id: &WorkspaceId) -> bool {
+        self.monocle.get(workspace_id).copied().unwrap_or(false)
+            || self.layout_overrides.contains_key(workspace_id)
     }
 
     fn create_tree(workspace_id: &WorkspaceId) -> TwmRuntimeTree {
         let settings = FULL_STATE.load();
         let layout = settings.get_wm_layout(workspace_id);
-        TwmRuntimeTree::from_plugin(&layout)
+        Self::create_tree_from_plugin(workspace_id, &layout)
+    }
+
+    fn create_tree_from_plugin(workspace_id: &WorkspaceId, plugin: &TwmPlugin) -> TwmRuntimeTree {
+        let mut tree = TwmRuntimeTree::from_plugin(plugin);
+        tree.environment = Self::get_environment(workspace_id);
+        tree
+    }
+
+    /// Monitor and workspace information of the workspace, used by layout conditions.
+    pub(super) fn get_environment(workspace_id: &WorkspaceId) -> TwmEnvironment {
+        let mut environment = TwmEnvironment::default();
+        let vd = SluWorkspacesManager2::instance();
+        let Some(monitor_id) = vd.workspace_index.get(workspace_id, |id| id.clone()) else {
+            return environment;
+        };
+
+        vd.monitors.get(&monitor_id, |monitor| {
+            let found = monitor
+                .workspaces
+                .rows()
+                .iter()
+                .flatten()
+                .enumerate()
+                .find(|(_, w)| &w.id == workspace_id);
+            if let Some((idx, workspace)) = found {
+                environment.workspace_index = idx;
+                environment.workspace_name = workspace.name.clone();
+            }
+        });
+
+        if let Some(monitor) = MonitorManager::instance()
+            .get_cached_data()
+            .into_iter()
+            .find(|m| m.id == monitor_id)
+        {
+            environment.monitor_width = monitor.rect.width();
+            environment.monitor_height = monitor.rect.height();
+        }
+        environment
+    }
+
+    /// Updates monitor and workspace information on all trees, re-placing windows if needed.
+    /// Returns `true` if any tree changed.
+    pub fn refresh_environments(&mut self) -> bool {
+        let mut changed = false;
+        for (workspace_id, tree) in self.state.workspaces.iter_mut() {
+            let environment = Self::get_environment(workspace_id);
+            if tree.environment == environment {
+                continue;
+            }
+            changed = true;
+            for w in tree.set_environment(environment) {
+                tree.add_to_floating(w);
+            }
+        }
+        changed
     }
 
     fn get_or_insert_tree_mut(&mut self, workspace_id: &WorkspaceId) -> &mut TwmRuntimeTree {
@@ -170,21 +283,7 @@ impl TwmState {
             return false;
         };
 
-        let node = tree.nodes.get_mut(&found_node_id).unwrap();
-
-        if node.kind != TwmNodeKind::Stack {
-            node.kind = TwmNodeKind::Stack;
-            node.stack_policy = TwmStackPolicy::Manual;
-        }
-
-        node.windows.push(window.address());
-        node.active_window = Some(window.address());
-        tree.window_map.insert(
-            window.address(),
-            WindowLocation::Tiled(found_node_id, std::time::SystemTime::now()),
-        );
-
-        true
+        tree.stack_window(found_node_id, window.address())
     }
 
     pub fn is_managed(&self, window: &Window) -> bool {
@@ -241,18 +340,7 @@ impl TwmState {
             }
             TwmReservation::Stack => {
                 let tree = self.get_or_insert_tree_mut(workspace_id);
-                if let Some(node) = tree.nodes.get_mut(&node_id) {
-                    if node.kind != TwmNodeKind::Stack {
-                        node.kind = TwmNodeKind::Stack;
-                        node.stack_policy = TwmStackPolicy::Manual;
-                    }
-                    node.windows.push(window.address());
-                    node.active_window = Some(window.address());
-                    tree.window_map.insert(
-                        window.address(),
-                        WindowLocation::Tiled(node_id, std::time::SystemTime::now()),
-                    );
-                } else {
+                if !tree.stack_window(node_id, window.address()) {
                     let residual = tree.add_to_tiled(window.address());
                     for w in residual {
                         tree.add_to_floating(w);
@@ -272,7 +360,32 @@ impl TwmState {
         }
     }
 
+    /// Places the window where its workspace template asked, `false` if it should be added as usual.
+    fn apply_template_placement(
+        &mut self,
+        window: &Window,
+        workspace_id: &WorkspaceId,
+        placement: TemplatePlacement,
+    ) -> bool {
+        if placement.side == Some(TwmReservation::Float) {
+            self.add_to_floating(window, workspace_id);
+            twm_set_rect_to_float_initial_size(window, &window.monitor()).log_error();
+            return true;
+        }
+        let Some(node) = placement.node else {
+            return false;
+        };
+        let tree = self.get_or_insert_tree_mut(workspace_id);
+        tree.place_window(window.address(), node, placement.side)
+    }
+
     pub fn add_to_layout(&mut self, window: &Window, workspace_id: &WorkspaceId) {
+        if let Some(placement) = TemplatePlacement::take(window) {
+            if self.apply_template_placement(window, workspace_id, placement) {
+                return;
+            }
+        }
+
         if let Some(reservation) = self.pending_reservation.take() {
             emit_to_webviews(SeelenEvent::WMSetReservation, None::<TwmReservation>);
             if &reservation.workspace_id == workspace_id {
@@ -282,6 +395,10 @@ impl TwmState {
             // workspace mismatch: reservation discarded, fall through to normal layout
         }
 
+        if self.try_swallow(window, workspace_id) {
+            return;
+        }
+
         let tree = self.get_or_insert_tree_mut(workspace_id);
 
         if Self::try_add_to_layout_categorized(window, tree) {
@@ -295,10 +412,58 @@ impl TwmState {
         }
     }
 
+    /// Puts the window in the place of the tiled app that launched it (usually a terminal),
+    /// if that app is marked to be swallowed. The app is minimized until the window is removed.
+    fn try_swallow(&mut self, window: &Window, workspace_id: &WorkspaceId) -> bool {
+        if window.has_app_flag(&AppExtraFlag::WmNoSwallow) {
+            return false;
+        }
+
+        let ancestors = window.process().ancestors(SWALLOW_MAX_DEPTH);
+        if ancestors.is_empty() {
+            return false;
+        }
+
+        let tree = self.get_or_insert_tree_mut(workspace_id);
+        let mut by_process = HashMap::new();
+        for w in tree.window_map.keys() {
+            let candidate = Window::from(*w);
+            if tree.is_tiled(w) && candidate.has_app_flag(&AppExtraFlag::WmSwallow) {
+                by_process.entry(candidate.process().id()).or_insert(*w);
+            }
+        }
+        // nearest ancestor first, e.g. a nested terminal instead of the one hosting it
+        let original = ancestors
+            .iter()
+            .find_map(|process| by_process.get(&process.id()).copied());
+        let Some(original) = original else {
+            return false;
+        };
+
+        if !tree.swallow(original, window.address()) {
+            return false;
+        }
+
+        let _ = MINIMIZED_BY_STACK.insert_sync(original);
+        Window::from(original)
+            .show_window(SW_FORCEMINIMIZE)
+            .log_error();
+        true
+    }
+
     pub fn remove(&mut self, window: &Window) {
         let window_id = window.address();
         for tree in self.state.workspaces.values_mut() {
             if tree.contains(&window_id) {
+                // the swallowed app could be closed while hidden, don't bring back a dead window
+                if tree
+                    .swallowed_by(&window_id)
+                    .is_some_and(|original| !Window::from(original).is_window())
+                {
+                    tree.swallowed.remove(&window_id);
+                }
+
+                // a swallowed app gets its place back and is shown by `restore_stacks`
                 let residual = tree.remove_window(&window_id);
                 for w in residual {
                     tree.add_to_floating(w);
@@ -400,6 +565,16 @@ impl TwmState {
                 return Ok(());
             };
             let parent = &tree.nodes[&parent_id];
+            // columns of scrollable nodes don't share space, only the column itself is resized
+            if parent.kind == TwmNodeKind::Scrollable && wanted_kind == TwmNodeKind::Horizontal {
+                let tree_mut = self.state.workspaces.get_mut(&workspace_id).unwrap();
+                let column = tree_mut.nodes.get_mut(&current_id).unwrap();
+                column.grow_factor = (column.grow_factor * (1.0 + percentage / 100.0)).max(0.1);
+                tree_mut.clamp_scroll_offsets();
+                tree_mut.scroll_to_window(&window_id);
+                Self::send(TwmStateEvent::Changed);
+                return Ok(());
+            }
             if parent.kind == wanted_kind {
                 let child_idx = parent
                     .children
@@ -461,6 +636,59 @@ impl TwmState {
         }
     }
 
+    /// Sets the size of the tiled window to an exact percentage of its row or column.
+    pub fn set_size(&mut self, window: &Window, axis: Axis, percentage: f32) -> Result<()> {
+        let window_id = window.address();
+        let Some((_, tree)) = self.get_tree_for_window_mut(window) else {
+            return Ok(());
+        };
+        let Some(node_id) = tree.node_of_window(&window_id) else {
+            return Err("Trying to change size of a non tiled window".into());
+        };
+
+        let kind = match axis {
+            Axis::Horizontal | Axis::Left | Axis::Right => TwmNodeKind::Horizontal,
+            Axis::Vertical | Axis::Top | Axis::Bottom => TwmNodeKind::Vertical,
+        };
+        if !tree.set_share(node_id, kind, percentage / 100.0) {
+            log::warn!("Can't change size if the window is alone on axis");
+            return Ok(());
+        }
+
+        Self::send(TwmStateEvent::Changed);
+        Ok(())
+    }
+
+    /// Applies `arrange` to the container of the tiled window, or to the root of its
+    /// workspace tree if `workspace` (in that case `recursive` is true).
+    pub fn arrange_container(
+        &mut self,
+        window: &Window,
+        workspace: bool,
+        arrange: impl FnOnce(&mut TwmRuntimeTree, NodeId, bool),
+    ) {
+        let window_id = window.address();
+        let Some((_, tree)) = self.get_tree_for_window_mut(window) else {
+            return;
+        };
+        let Some(node_id) = tree.node_of_window(&window_id) else {
+            return;
+        };
+
+        let container_id = if workspace {
+            Some(tree.root)
+        } else {
+            tree.nodes[&node_id].parent
+        };
+        let Some(container_id) = container_id else {
+            log::warn!("The window is not inside a container");
+            return;
+        };
+
+        arrange(tree, container_id, workspace);
+        Self::send(TwmStateEvent::Changed);
+    }
+
     pub fn change_layout(&mut self, workspace_id: &WorkspaceId, mut new_layout: TwmRuntimeTree) {
         let Some(old) = self.state.workspaces.get_mut(workspace_id) else {
             return;
@@ -470,6 +698,7 @@ impl TwmState {
         for floating in old.window_map.keys() {
             new_layout.add_to_floating(*floating);
         }
+        new_layout.swallowed = std::mem::take(&mut old.swallowed);
 
         for w in windows {
             let residuals = new_layout.add_to_tiled(w);
@@ -487,7 +716,8 @@ impl TwmState {
         Self::send(TwmStateEvent::Changed);
     }
 
-    /// Recomputes `paused_by_monitor` from the live interactable-windows list.
+    /// Recomputes `paused_by_monitor` from the live interactable-windows list and the
+    /// `paused` setting of the active workspace of each monitor.
     /// Returns `true` if the computed value changed.
     pub fn recompute_paused_by_monitor(&mut self) -> bool {
         let mut computed: HashMap<seelen_core::system_state::MonitorId, bool> = HashMap::new();
@@ -497,6 +727,18 @@ impl TwmState {
             *entry = *entry || flag;
         }
 
+        let state = FULL_STATE.load();
+        SluWorkspacesManager2::instance()
+            .monitors
+            .for_each(|(monitor_id, monitor)| {
+                if state
+                    .settings
+                    .is_wm_paused_on(monitor_id, monitor.active_workspace_id())
+                {
+                    computed.insert(monitor_id.clone(), true);
+                }
+            });
+
         if computed == self.state.paused_by_monitor {
             return false;
         }
@@ -509,14 +751,34 @@ impl TwmState {
         *is_monocle = !*is_monocle;
 
         let layout = if *is_monocle {
-            TwmRuntimeTree::from_plugin(&TwmPlugin::monocle())
+            Self::create_tree_from_plugin(workspace_id, &TwmPlugin::monocle())
         } else {
-            TwmRuntimeTree::from_plugin(&FULL_STATE.load().get_wm_layout(workspace_id))
+            match self.layout_overrides.get(workspace_id) {
+                Some(plugin) => Self::create_tree_from_plugin(workspace_id, plugin),
+                None => Self::create_tree(workspace_id),
+            }
         };
         self.change_layout(workspace_id, layout);
         Self::send(TwmStateEvent::Changed);
     }
 
+    /// Overrides the layout of the workspace, `None` restores the configured one.
+    pub fn set_layout(&mut self, workspace_id: &WorkspaceId, plugin: Option<TwmPlugin>) {
+        self.monocle.remove(workspace_id);
+        let layout = match plugin {
+            Some(plugin) => {
+                let tree = Self::create_tree_from_plugin(workspace_id, &plugin);
+                self.layout_overrides.insert(workspace_id.clone(), plugin);
+                tree
+            }
+            None => {
+                self.layout_overrides.remove(workspace_id);
+                Self::create_tree(workspace_id)
+            }
+        };
+        self.change_layout(workspace_id, layout);
+    }
+
     pub fn swap_tiled_windows(
         &mut self,
         a: &Window,
@@ -589,8 +851,22 @@ impl TwmState {
         }
 
         let tree = self.state.workspaces.get(workspace_id)?;
+
+        // columns of scrollable nodes can be off-screen, so they are followed by order instead of by rect
+        let scroll_neighbour = match side {
+            Direction::Left => tree.scroll_neighbour(&source_id, false),
+            Direction::Right => tree.scroll_neighbour(&source_id, true),
+            Direction::Up | Direction::Down => None,
+        };
+        if scroll_neighbour.is_some() {
+            return scroll_neighbour;
+        }
+
+        // cached rects of scrolled out windows are stale
+        let hidden = tree.windows_out_of_viewport();
         tree.iter()
             .filter(|n| matches!(n.kind, TwmNodeKind::Leaf | TwmNodeKind::Stack))
+            .filter(|n| !n.windows.iter().any(|w| hidden.contains(w)))
             .filter_map(|n| {
                 let rect = n.rect.as_ref()?;
 
@@ -655,6 +931,8 @@ impl TwmState {
             .filter(|&face_id| face_id != source_id)
     }
 
+    /// Shows the active window of each stack, minimizing the rest and the columns scrolled out
+    /// of the viewport of scrollable nodes.
     pub fn restore_stacks(&self) {
         let mut active_ids = std::collections::HashSet::new();
         SluWorkspacesManager2::instance()
@@ -668,11 +946,21 @@ impl TwmState {
                 continue;
             }
 
+            let hidden = tree.windows_out_of_viewport();
             for node in tree {
                 if !matches!(node.kind, TwmNodeKind::Leaf | TwmNodeKind::Stack) {
                     continue;
                 }
 
+                // columns scrolled out of the viewport are minimized as a whole
+                if node.windows.iter().any(|w| hidden.contains(w)) {
+                    for w in &node.windows {
+                        let _ = MINIMIZED_BY_STACK.insert_sync(*w);
+                        Window::from(*w).show_window(SW_FORCEMINIMIZE).log_error();
+                    }
+                    continue;
+                }
+
                 if let Some(active) = node.active_window {
                     MINIMIZED_BY_STACK.remove_sync(&active);
                     Window::from(active).unminimize().log_error();
@@ -738,6 +1026,61 @@ impl TwmState {
         Self::send(TwmStateEvent::Changed);
         Ok(())
     }
+
+    /// Moves the tab of the window to the left (`forward` false) or right on its stack.
+    pub fn move_stack_tab(&mut self, window: &Window, forward: bool) {
+        let Some((_ws_id, tree)) = self.get_tree_for_window_mut(window) else {
+            return;
+        };
+        if tree.move_stack_tab(&window.address(), forward) {
+            Self::send(TwmStateEvent::Changed);
+        }
+    }
+
+    /// Takes the window out of its stack into a new leaf at `side` of the stack.
+    pub fn pull_stack_tab(&mut self, window: &Window, side: TwmReservation) {
+        let Some((_ws_id, tree)) = self.get_tree_for_window_mut(window) else {
+            return;
+        };
+        if !tree.pull_stack_tab(&window.address(), side) {
+            log::warn!("Only windows on a stack with other windows can be pulled out");
+            return;
+        }
+        tree.normalize();
+        Self::send(TwmStateEvent::Changed);
+    }
+
+    /// Tab bars of the stacks of every workspace, with the title and icon of each window.
+    pub fn stacks(&self) -> HashMap<WorkspaceId, Vec<TwmStackTabs>> {
+        let apps = UserAppsManager::instance().interactable_windows.to_vec();
+        let describe = |window: WindowId| {
+            let app = apps.iter().find(|app| app.hwnd == window);
+            TwmStackTab {
+                window,
+                title: app
+                    .map(|app| app.title.clone())
+                    .unwrap_or_else(|| Window::from(window).title()),
+                icon: app.and_then(|app| {
+                    app.relaunch
+                        .as_ref()
+                        .and_then(|relaunch| relaunch.icon.clone())
+                        .or_else(|| {
+                            app.process
+                                .path
+                                .as_ref()
+                                .map(|path| path.to_string_lossy().to_string())
+                        })
+                }),
+                umid: app.and_then(|app| app.umid.clone()),
+            }
+        };
+
+        self.state
+            .workspaces
+            .iter()
+            .map(|(id, tree)| (id.clone(), tree.stack_tabs(&describe)))
+            .collect()
+    }
 }
 
 pub fn twm_set_rect_to_float_initial_size(window: &Window, monitor: &Monitor) -> Result<()> {
@@ -48,7 +48,8 @@ use windows::{
             PHYSICAL_MONITOR,
         },
         Foundation::{
-            HANDLE, HMODULE, HWND, LPARAM, LUID, MAX_PATH, POINT, RECT, STATUS_SUCCESS, WPARAM,
+            FILETIME, HANDLE, HMODULE, HWND, LPARAM, LUID, MAX_PATH, POINT, RECT, STATUS_SUCCESS,
+            WPARAM,
         },
         Graphics::{
             Dwm::{
@@ -86,8 +87,8 @@ use windows::{
             Shutdown::{ExitWindowsEx, LockWorkStation, EXIT_WINDOWS_FLAGS, SHUTDOWN_REASON},
             SystemInformation::{GetComputerNameExW, COMPUTER_NAME_FORMAT},
             Threading::{
-                GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId, OpenProcess,
-                OpenProcessToken, QueryFullProcessImageNameW, PROCESS_ACCESS_RIGHTS,
+                GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId, GetProcessTimes,
+                OpenProcess, OpenProcessToken, QueryFullProcessImageNameW, PROCESS_ACCESS_RIGHTS,
                 PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
             },
         },
@@ -460,9 +461,11 @@ impl WindowsApi {
         unsafe { GetDesktopWindow() }
     }
 
-    pub fn is_process_frozen(process_id: u32) -> Result<bool> {
+    fn process_extended_basic_information(
+        process_id: u32,
+    ) -> Result<PROCESS_EXTENDED_BASIC_INFORMATION> {
         let handle = Self::open_process(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)?;
-        let is_frozen = unsafe {
+        unsafe {
             let mut buffer: [PROCESS_EXTENDED_BASIC_INFORMATION; 1] = std::mem::zeroed();
             let status = NtQueryInformationProcess(
                 *handle,
@@ -480,10 +483,31 @@ impl WindowsApi {
                 .into());
             }
 
-            let data = buffer[0];
-            data.Anonymous.Flags & ProcessInformationFlag::IsFrozen as u32 != 0
-        };
-        Ok(is_frozen)
+            Ok(buffer[0])
+        }
+    }
+
+    pub fn is_process_frozen(process_id: u32) -> Result<bool> {
+        let data = Self::process_extended_basic_information(process_id)?;
+        Ok(unsafe { data.Anonymous.Flags } & ProcessInformationFlag::IsFrozen as u32 != 0)
+    }
+
+    /// Id of the process that created the given one, the parent could be already closed
+    /// and its id reused by another process.
+    pub fn parent_process_id(process_id: u32) -> Result<u32> {
+        let data = Self::process_extended_basic_information(process_id)?;
+        Ok(data.BasicInfo.InheritedFromUniqueProcessId as u32)
+    }
+
+    /// Creation time of the process, in 100-nanosecond intervals since January 1, 1601 (UTC).
+    pub fn process_creation_time(process_id: u32) -> Result<u64> {
+        let handle = Self::open_process(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)?;
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        unsafe { GetProcessTimes(*handle, &mut creation, &mut exit, &mut kernel, &mut user)? };
+        Ok((u64::from(creation.dwHighDateTime) << 32) | u64::from(creation.dwLowDateTime))
     }
 
     pub fn exe_path_by_process(process_id: u32) -> Result<OsString> {
@@ -58,6 +58,44 @@ impl Process {
         WindowsApi::is_process_frozen(self.0)
     }
 
+    pub fn parent(&self) -> Result<Process> {
+        WindowsApi::parent_process_id(self.0).map(Self)
+    }
+
+    pub fn creation_time(&self) -> Result<u64> {
+        WindowsApi::process_creation_time(self.0)
+    }
+
+    /// Parent chain of the process, nearest first, stopping after `max_depth` levels or on the
+    /// first process that can't be queried.
+    pub fn ancestors(&self, max_depth: usize) -> Vec<Process> {
+        let mut ancestors: Vec<Process> = Vec::new();
+        let Ok(mut created_at) = self.creation_time() else {
+            return ancestors;
+        };
+        let mut current = Self(self.0);
+        while ancestors.len() < max_depth {
+            let Ok(parent) = current.parent() else {
+                break;
+            };
+            // pid 0 is the idle process, also guards against cycles made by reused ids
+            if parent.0 == 0 || parent.0 == self.0 || ancestors.iter().any(|p| p.0 == parent.0) {
+                break;
+            }
+            // a parent created after its child is another process that reused the id of the
+            // already closed parent
+            match parent.creation_time() {
+                Ok(parent_created_at) if parent_created_at <= created_at => {
+                    created_at = parent_created_at;
+                }
+                _ => break,
+            }
+            ancestors.push(Self(parent.0));
+            current = parent;
+        }
+        ancestors
+    }
+
     /// package app user model id, (appx, eg: "Microsoft.WindowsTerminal_8wekyb3d8bbwe!TerminalApp")
     pub fn package_app_user_model_id(&self) -> Result<AppUserModelId> {
         let hprocess = self.open_limited_handle()?;
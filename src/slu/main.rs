@@ -3,7 +3,7 @@ mod resources;
 
 use clap::Parser;
 use slu_ipc::{
-    commands::{AppCli, AppCommand, CommandExecutionMode, SluCliCommand},
+    commands::{AppCli, AppCommand, BatchCli, CommandExecutionMode, SluCliCommand},
     messages::AppMessage,
     AppIpc,
 };
@@ -37,6 +37,9 @@ async fn process_direct(cli: AppCli) -> Result<()> {
     match cli.command {
         AppCommand::Art(cmd) => art::process(cmd),
         AppCommand::Resource(cmd) => resources::process(cmd).await?,
+        AppCommand::Subscribe(cmd) => {
+            AppIpc::subscribe(cmd.topics, |line| println!("{line}")).await?
+        }
         _ => return Err("Command does not support direct execution".into()),
     }
     Ok(())
@@ -44,7 +47,7 @@ async fn process_direct(cli: AppCli) -> Result<()> {
 
 async fn send_to_main_instance(cli: AppCli) -> Result<()> {
     let working_dir = std::env::current_dir()?;
-    let args: Vec<String> = std::env::args()
+    let mut args: Vec<String> = std::env::args()
         .map(|arg| {
             if arg.starts_with("./")
                 || arg.starts_with(".\\")
@@ -58,10 +61,48 @@ async fn send_to_main_instance(cli: AppCli) -> Result<()> {
         })
         .collect();
 
+    if let AppCommand::Batch(batch) = &cli.command {
+        args = batch_args(batch)?;
+    }
+
     if cli.verbose {
         println!("Sending {args:#?}");
     }
 
-    AppIpc::send(AppMessage::Cli(args)).await?;
+    let AppCommand::Query(query) = &cli.command else {
+        AppIpc::send(AppMessage::Cli(args)).await?;
+        return Ok(());
+    };
+
+    let data = AppIpc::request(AppMessage::Cli(args))
+        .await?
+        .unwrap_or_default();
+    let output = if query.pretty {
+        serde_json::to_string_pretty(&data)?
+    } else {
+        serde_json::to_string(&data)?
+    };
+    println!("{output}");
     Ok(())
 }
+
+/// Validates the batch before sending it, reading the commands from stdin if none was given.
+fn batch_args(batch: &BatchCli) -> Result<Vec<String>> {
+    let mut batch = BatchCli {
+        commands: batch.commands.clone(),
+        keep_going: batch.keep_going,
+    };
+    if batch.commands.is_empty() {
+        batch.commands = std::io::stdin().lines().collect::<std::io::Result<_>>()?;
+    }
+    batch.parse_commands()?;
+
+    let mut args = vec!["slu".to_string(), "batch".to_string()];
+    if batch.keep_going {
+        args.push("--keep-going".to_string());
+    }
+    // `--` so lines starting with a dash are not taken as flags
+    args.push("--".to_string());
+    args.extend(batch.commands);
+    Ok(args)
+}
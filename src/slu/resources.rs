@@ -1,19 +1,28 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use owo_colors::OwoColorize;
 use seelen_core::{
     constants::SUPPORTED_LANGUAGES,
-    resource::{ResourceText, SluResource},
+    resource::{
+        extended_yaml_dependencies, lint_resource, LintSeverity, ResourceText, SluResource,
+    },
     state::{IconPack, Plugin, Theme, Wallpaper, Widget},
+    utils::search_resource_entrypoint,
 };
 use slu_ipc::commands::{ClapResourceKind, ResourceManagerCli, ResourceSubCommand};
+use slu_utils::checksums::{CheckSums, Diff};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+/// Checksums of the sources of a bundle, written next to the metadata file of the resource.
+const CHECKSUMS_FILENAME: &str = "SHA256SUMS";
+
 pub async fn process(cmd: ResourceManagerCli) -> Result<()> {
     match cmd.subcommand {
         ResourceSubCommand::Bundle { kind, path } => bundle(kind, path).await?,
         ResourceSubCommand::Translate { path, source_lang } => translate(path, source_lang).await?,
+        ResourceSubCommand::Verify { path } => verify(path).await?,
+        ResourceSubCommand::Lint { kind, path } => lint(kind, path).await?,
         _ => return Err("This command needs Seelen UI to be running".into()),
     }
     Ok(())
@@ -37,39 +46,151 @@ async fn bundle(kind: ClapResourceKind, path: PathBuf) -> Result<()> {
         e.to_string().into()
     };
 
-    match kind {
+    let content = match kind {
         ClapResourceKind::Theme => {
-            let mut theme = Theme::load_ext(&path, false).await.map_err(e)?;
-            theme.metadata.internal.path = to_store_path.clone();
-            theme.save().await.map_err(e)?;
+            let theme = Theme::load_ext(&path, false).await.map_err(e)?;
+            theme.to_bundle_yaml().map_err(e)?
         }
         ClapResourceKind::Plugin => {
-            let mut plugin = Plugin::load_ext(&path, false).await.map_err(e)?;
-            plugin.metadata.internal.path = to_store_path.clone();
-            plugin.save().await.map_err(e)?;
+            let plugin = Plugin::load_ext(&path, false).await.map_err(e)?;
+            plugin.to_bundle_yaml().map_err(e)?
         }
         ClapResourceKind::Widget => {
-            let mut widget = Widget::load_ext(&path, false).await.map_err(e)?;
-            widget.metadata.internal.path = to_store_path.clone();
-            widget.save().await.map_err(e)?;
+            let widget = Widget::load_ext(&path, false).await.map_err(e)?;
+            widget.to_bundle_yaml().map_err(e)?
         }
         ClapResourceKind::IconPack => {
-            let mut icon_pack = IconPack::load_ext(&path, false).await.map_err(e)?;
-            icon_pack.metadata.internal.path = to_store_path.clone();
-            icon_pack.save().await.map_err(e)?;
+            let icon_pack = IconPack::load_ext(&path, false).await.map_err(e)?;
+            icon_pack.to_bundle_yaml().map_err(e)?
         }
         ClapResourceKind::Wallpaper => {
-            let mut wallpaper = Wallpaper::load_ext(&path, false).await.map_err(e)?;
-            wallpaper.metadata.internal.path = to_store_path.clone();
-            wallpaper.save().await.map_err(e)?;
+            let wallpaper = Wallpaper::load_ext(&path, false).await.map_err(e)?;
+            wallpaper.to_bundle_yaml().map_err(e)?
         }
         _ => return Err("Not implemented".into()),
-    }
+    };
+    std::fs::write(&to_store_path, content)?;
+
+    let (root, checksums) = resource_checksums(&path).await?;
+    let checksums_path = root.join(CHECKSUMS_FILENAME);
+    checksums.write(&checksums_path)?;
 
     println!(
         "Bundle created successfully at: {}",
         to_store_path.display()
     );
+    println!(
+        "Checksums of {} files written to: {}",
+        checksums.len(),
+        checksums_path.display()
+    );
+    Ok(())
+}
+
+async fn verify(path: PathBuf) -> Result<()> {
+    let (root, current) = resource_checksums(&path).await?;
+    let checksums_path = root.join(CHECKSUMS_FILENAME);
+    let expected = CheckSums::parse(&std::fs::read(&checksums_path)?)?;
+
+    let mut diffs = expected.compare(&current);
+    if diffs.is_empty() {
+        println!(
+            "{} files match {}",
+            expected.len(),
+            checksums_path.display()
+        );
+        return Ok(());
+    }
+
+    diffs.sort_by(|(_, a), (_, b)| a.cmp(b));
+    for (diff, file) in &diffs {
+        let label = match diff {
+            Diff::Missing => "Missing ".bright_red().to_string(),
+            Diff::Mismatch => "Modified".bright_yellow().to_string(),
+            Diff::Extra => "New     ".bright_green().to_string(),
+        };
+        println!("{label} {}", file.display());
+    }
+    Err(format!(
+        "{} files differ from {}",
+        diffs.len(),
+        checksums_path.display()
+    )
+    .into())
+}
+
+/// Checksums of every file used by the resource at the path, relative to the folder of its
+/// metadata file, which is returned too.
+async fn resource_checksums(path: &Path) -> Result<(PathBuf, CheckSums)> {
+    let entrypoint = if path.is_dir() {
+        search_resource_entrypoint(path)
+            .await
+            .ok_or("No metadata file found")?
+    } else {
+        path.to_path_buf()
+    };
+    let root = entrypoint
+        .parent()
+        .ok_or("Invalid resource path")?
+        .to_path_buf();
+
+    let mut checksums = CheckSums::new();
+    let files = extended_yaml_dependencies(&entrypoint)
+        .await
+        .map_err(|e| e.to_string())?;
+    for file in files {
+        let content = std::fs::read(&file)?;
+        checksums.raw_add(&content, file.strip_prefix(&root).unwrap_or(&file));
+    }
+    Ok((root, checksums))
+}
+
+async fn lint(kind: ClapResourceKind, path: PathBuf) -> Result<()> {
+    let mut version = env!("CARGO_PKG_VERSION")
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    let app_version = (
+        version.next().unwrap_or(0),
+        version.next().unwrap_or(0),
+        version.next().unwrap_or(0),
+    );
+
+    let issues = match kind {
+        ClapResourceKind::Theme => lint_resource::<Theme>(&path, app_version).await,
+        ClapResourceKind::Plugin => lint_resource::<Plugin>(&path, app_version).await,
+        ClapResourceKind::Widget => lint_resource::<Widget>(&path, app_version).await,
+        ClapResourceKind::IconPack => lint_resource::<IconPack>(&path, app_version).await,
+        ClapResourceKind::Wallpaper => lint_resource::<Wallpaper>(&path, app_version).await,
+        _ => return Err("Not implemented".into()),
+    }
+    .map_err(|e| e.to_string())?;
+
+    for issue in &issues {
+        let severity = match issue.severity {
+            LintSeverity::Error => "error".bright_red().to_string(),
+            LintSeverity::Warning => "warning".bright_yellow().to_string(),
+        };
+        let location = match &issue.location {
+            Some(location) => location.to_string(),
+            None => path.display().to_string(),
+        };
+        let pointer = if issue.pointer.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", issue.pointer.bright_black())
+        };
+        println!("{location}: {severity}{pointer}: {}", issue.message);
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == LintSeverity::Error)
+        .count();
+    let warnings = issues.len() - errors;
+    if errors > 0 {
+        return Err(format!("{errors} errors and {warnings} warnings found").into());
+    }
+    println!("No errors found, {warnings} warnings");
     Ok(())
 }
 
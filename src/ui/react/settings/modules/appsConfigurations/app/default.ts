@@ -15,4 +15,5 @@ export const defaultAppConfig: AppConfig = {
   category: null,
   boundMonitor: null,
   boundWorkspace: null,
+  scratchpad: null,
 };
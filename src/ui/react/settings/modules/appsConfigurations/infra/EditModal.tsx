@@ -55,6 +55,8 @@ export const EditAppModal = ({ idx, onCancel, onSave, isNew, open, readonlyApp }
 
   const onSelectMonitor = (value: number | null) => setApp({ ...app, boundMonitor: value });
   const onSelectWorkspace = (value: number | null) => setApp({ ...app, boundWorkspace: value });
+  const updateScratchpad = (e: React.ChangeEvent<HTMLInputElement>) =>
+    setApp({ ...app, scratchpad: e.currentTarget.value || null });
 
   const onChangeOption = (option: AppExtraFlag, checked: boolean) => {
     setApp({
@@ -146,6 +148,14 @@ export const EditAppModal = ({ idx, onCancel, onSave, isNew, open, readonlyApp }
               />
             </SettingsOption>
           </SettingsSubGroup>
+          <SettingsOption>
+            <span>{t("apps_configurations.app.scratchpad")}</span>
+            <Input
+              value={app.scratchpad || ""}
+              placeholder={t("apps_configurations.app.scratchpad_placeholder")}
+              onChange={updateScratchpad}
+            />
+          </SettingsOption>
         </SettingsGroup>
 
         <SettingsGroup>
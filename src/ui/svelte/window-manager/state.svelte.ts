@@ -1,6 +1,12 @@
 import { invoke, RuntimeStyleSheet, SeelenCommand, SeelenEvent, Settings, subscribe, Widget } from "@seelen-ui/lib";
 import { declareDocumentAsLayeredHitbox } from "libs/ui/react/utils/layered";
-import type { FocusedApp, TwmReservation, TwmRuntimeTree, WindowManagerSettings } from "@seelen-ui/lib/types";
+import type {
+  FocusedApp,
+  TwmReservation,
+  TwmRuntimeTree,
+  TwmStackTabs,
+  WindowManagerSettings,
+} from "@seelen-ui/lib/types";
 import { FancyToolbarSide, HideMode } from "@seelen-ui/lib/types";
 import { SeelenWegSide } from "node_modules/@seelen-ui/lib/esm/gen/types/SeelenWegSide";
 
@@ -10,6 +16,9 @@ import { isTouchPrimary } from "libs/ui/svelte/utils/signals.svelte.ts";
 let layouts = lazyRune(() => invoke(SeelenCommand.WmGetRenderTree));
 subscribe(SeelenEvent.WMTreeChanged, layouts.setByPayload);
 
+let stacks = lazyRune(() => invoke(SeelenCommand.WmGetStacks));
+subscribe(SeelenEvent.WMStacksChanged, stacks.setByPayload);
+
 let workspaces = lazyRune(() => invoke(SeelenCommand.StateGetVirtualDesktops));
 subscribe(SeelenEvent.VirtualDesktopsChanged, workspaces.setByPayload);
 
@@ -33,6 +42,7 @@ const [focusedAppInit, settingsInit] = await Promise.all([
   invoke(SeelenCommand.GetFocusedApp),
   Settings.getAsync(),
   layouts.init(),
+  stacks.init(),
   workspaces.init(),
   interactables.init(),
   monitors.init(),
@@ -43,11 +53,28 @@ subscribe(SeelenEvent.GlobalFocusChanged, (e) => {
   focusedApp = e.payload;
 });
 
+const monitorId = Widget.getCurrent().decoded.monitorId!;
+
 let fullSettings = $state(settingsInit);
-let settings = $state<WindowManagerSettings>(settingsInit.byWidget["@seelen/window-manager"]);
 Settings.onChange((s) => {
   fullSettings = s;
-  settings = s.byWidget["@seelen/window-manager"];
+});
+
+// overrides are resolved as workspace → monitor → global
+let settings = $derived.by<WindowManagerSettings>(() => {
+  const global = fullSettings.byWidget["@seelen/window-manager"];
+  const monitorConfig = fullSettings.monitorsV3[monitorId];
+  const activeWsId = workspaces.value?.monitors?.[monitorId]?.active_workspace;
+  const chain = [monitorConfig?.wm, activeWsId ? monitorConfig?.byWorkspace[activeWsId]?.wm : undefined];
+
+  const resolved = { ...global };
+  for (const overrides of chain) {
+    if (!overrides) continue;
+    resolved.workspaceGap = overrides.workspaceGap ?? resolved.workspaceGap;
+    resolved.workspacePadding = overrides.workspacePadding ?? resolved.workspacePadding;
+    resolved.workspaceMargin = overrides.workspaceMargin ?? resolved.workspaceMargin;
+  }
+  return resolved;
 });
 
 // =================================================
@@ -73,8 +100,6 @@ $effect.root(() => {
 //                   Positioning
 // =================================================
 
-const monitorId = Widget.getCurrent().decoded.monitorId!;
-
 const widgetRect = $derived.by(() => {
   const monitor = monitors.value.find((m) => m.id === monitorId);
   if (!monitor) {
@@ -170,6 +195,11 @@ class _State {
     if (!activeWsId) return null;
     return layouts.value?.workspaces?.[activeWsId] ?? null;
   }
+  getStackTabs(nodeId: number): TwmStackTabs | null {
+    const activeWsId = workspaces.value?.monitors?.[monitorId]?.active_workspace;
+    if (!activeWsId) return null;
+    return stacks.value?.[activeWsId]?.find((stack) => stack.node === nodeId) ?? null;
+  }
   get forceRepositioning() {
     return forceRepositioning;
   }
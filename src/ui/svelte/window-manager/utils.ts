@@ -10,6 +10,7 @@ export class NodeUtils {
         return node.windows.length === 0;
       case TwmNodeKind.Horizontal:
       case TwmNodeKind.Vertical:
+      case TwmNodeKind.Scrollable:
         return node.children.every((id) => NodeUtils.isEmpty(tree, id));
     }
   }
@@ -23,6 +24,7 @@ export class NodeUtils {
         return node.windows.includes(searchingWindow);
       case TwmNodeKind.Horizontal:
       case TwmNodeKind.Vertical:
+      case TwmNodeKind.Scrollable:
         return node.children.some((id) => NodeUtils.contains(tree, id, searchingWindow));
     }
   }
@@ -42,7 +44,26 @@ export class NodeUtils {
       }
       case TwmNodeKind.Horizontal:
       case TwmNodeKind.Vertical:
+      case TwmNodeKind.Scrollable:
         return node.children.some((id) => NodeUtils.some(tree, id, predicate));
     }
   }
+
+  /** Columns with windows of a scrollable node, as start/end in widths of the node. */
+  static columnSpans(
+    tree: TwmRuntimeTree,
+    nodeId: number,
+  ): { id: number; start: number; end: number }[] {
+    const node = tree.nodes[nodeId];
+    if (!node) return [];
+    let cursor = 0;
+    return node.children
+      .filter((id) => !NodeUtils.isEmpty(tree, id))
+      .map((id) => {
+        const width = node.columnWidth * Math.max(tree.nodes[id]?.growFactor ?? 1, 0);
+        const span = { id, start: cursor, end: cursor + width };
+        cursor += width;
+        return span;
+      });
+  }
 }